@@ -0,0 +1,17 @@
+use std::process::Command;
+
+/// Record the version of the Rust compiler for the default `User-Agent`
+fn main() {
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let version = Command::new(rustc)
+        .arg("--version")
+        .output()
+        .ok()
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .and_then(|out| out.split_whitespace().nth(1).map(str::to_string));
+
+    if let Some(version) = version {
+        println!("cargo:rustc-env=DEEPL_RUSTC_VERSION={version}");
+    }
+    println!("cargo:rerun-if-env-changed=RUSTC");
+}
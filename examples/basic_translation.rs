@@ -15,7 +15,7 @@ async fn main() {
     let response = api
         .translate_text(str, Lang::DE)
         .source_lang(Lang::EN)
-        .ignore_tags(vec!["keep".to_owned()])
+        .ignore_tags(["keep"])
         .tag_handling(TagHandling::Xml)
         .await
         .unwrap();
@@ -0,0 +1,14 @@
+//! Serve the fake DeepL API until interrupted, e.g. for the wasm32 tests:
+//!
+//! ```sh
+//! cargo run --example fake_server --features test-util
+//! ```
+
+use deepl::testing::FakeServer;
+
+#[tokio::main]
+async fn main() {
+    let server = FakeServer::start().await;
+    println!("{}", server.endpoint());
+    std::future::pending::<()>().await;
+}
@@ -0,0 +1,41 @@
+//! Accept any [`TranslationProvider`] in application code, and swap the DeepL client for an
+//! in-memory fake in tests:
+//!
+//! ```sh
+//! cargo run --example provider --features test-util
+//! ```
+
+use std::sync::Arc;
+
+use deepl::{testing::StaticProvider, Error, Lang, TranslateOptions, TranslationProvider};
+
+struct Greeter {
+    provider: Arc<dyn TranslationProvider>,
+}
+
+impl Greeter {
+    async fn greet(&self, name: &str, lang: Lang) -> Result<String, Error> {
+        let resp = self
+            .provider
+            .translate(
+                vec![format!("Hello {name}")],
+                lang,
+                TranslateOptions::default(),
+            )
+            .await?;
+        Ok(resp.to_string())
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Error> {
+    // in production: `Arc::new(DeepLApi::from_env()?)`
+    let provider = StaticProvider::new().with_translation("Hello Ferris", Lang::DE, "Hallo Ferris");
+    let greeter = Greeter {
+        provider: Arc::new(provider),
+    };
+
+    assert_eq!(greeter.greet("Ferris", Lang::DE).await?, "Hallo Ferris");
+    println!("{}", greeter.greet("Ferris", Lang::FR).await?);
+    Ok(())
+}
@@ -0,0 +1,22 @@
+//! Translate text from a browser, through a proxy which keeps the key on the server side.
+//!
+//! ```sh
+//! cargo build --example wasm --target wasm32-unknown-unknown --no-default-features
+//! ```
+
+#[cfg(target_arch = "wasm32")]
+mod wasm {
+    use deepl::{DeepLApi, Lang};
+    use wasm_bindgen::prelude::*;
+
+    #[wasm_bindgen]
+    pub async fn translate(proxy: String, text: String) -> Result<String, JsError> {
+        // the proxy adds the real key
+        let deepl = DeepLApi::with("proxy").endpoint(proxy).build()?;
+        let translated = deepl.translate_text(text, Lang::DE).await?;
+
+        Ok(translated.to_string())
+    }
+}
+
+fn main() {}
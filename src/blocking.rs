@@ -0,0 +1,330 @@
+//! A blocking client for the DeepL API, enabled by the `blocking` feature.
+//!
+//! The blocking [`DeepLApi`] drives the async client on a private single thread runtime, so it
+//! shares every setting of [`DeepLApiBuilder`](crate::DeepLApiBuilder) and all response types.
+//! Requests end with an explicit `.send()` instead of `.await`.
+//!
+//! # Panics
+//!
+//! Like `reqwest::blocking`, the client must not be used inside an async runtime.
+//!
+//! # Example
+//!
+//! ```rust
+//! use deepl::{blocking::DeepLApi, Lang};
+//!
+//! let deepl = DeepLApi::with("Your DeepL Key").build_blocking().unwrap();
+//! let resp = deepl
+//!     .translate_text("Hello World", Lang::DE)
+//!     .source_lang(Lang::EN)
+//!     .send()
+//!     .unwrap();
+//! ```
+
+use crate::{
+    endpoint::{
+        translate::{PreserveFormatting, SplitSentences},
+        Result,
+    },
+    glossary::{EntriesFormat, GlossaryLanguagePair, GlossaryResp},
+    DeepLApiBuilder, Error, Formality, KeyStatus, Lang, LangInfo, LangType, ModelType, TagHandling,
+    TranslateTextResp, UsageResponse,
+};
+use std::{borrow::Borrow, future::IntoFuture, sync::Arc};
+use tokio::runtime::Runtime;
+
+/// Blocking version of [`crate::DeepLApi`], cheap to clone.
+#[derive(Debug, Clone)]
+pub struct DeepLApi {
+    api: crate::DeepLApi,
+    runtime: Arc<Runtime>,
+}
+
+impl DeepLApi {
+    /// Create a new api instance with auth key. Finish the builder with
+    /// [`build_blocking`](DeepLApiBuilder::build_blocking).
+    pub fn with(key: &str) -> DeepLApiBuilder {
+        crate::DeepLApi::with(key)
+    }
+
+    /// Create a new api instance from the environment, see [`crate::DeepLApi::from_env`].
+    pub fn from_env() -> Result<DeepLApi> {
+        crate::DeepLApi::with_env()?.build_blocking()
+    }
+
+    /// Wrap an async api instance, sharing its connection pool and settings.
+    ///
+    /// # Error
+    ///
+    /// Return [`Error::InvalidConfig`] if the runtime can't be created.
+    pub fn from_async(api: crate::DeepLApi) -> Result<DeepLApi> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|err| Error::InvalidConfig(format!("fail to create runtime: {err}")))?;
+
+        Ok(DeepLApi {
+            api,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// The async api instance used under the hood
+    pub fn as_async(&self) -> &crate::DeepLApi {
+        &self.api
+    }
+
+    fn wait<F: IntoFuture>(&self, fut: F) -> F::Output {
+        self.runtime.block_on(fut.into_future())
+    }
+
+    /// Translate the given text, see [`crate::DeepLApi::translate_text`].
+    pub fn translate_text(
+        &self,
+        text: impl crate::ToTranslatable,
+        target_lang: impl crate::IntoParam<Lang>,
+    ) -> TranslateRequester<'_> {
+        TranslateRequester {
+            client: self,
+            inner: self.api.translate_text(text, target_lang),
+        }
+    }
+
+    /// Get the current DeepL API usage, see [`crate::DeepLApi::get_usage`].
+    pub fn get_usage(&self) -> Result<UsageResponse> {
+        self.wait(self.api.get_usage())
+    }
+
+    /// Check the configured key, see [`crate::DeepLApi::verify_key`].
+    pub fn verify_key(&self) -> Result<KeyStatus> {
+        self.wait(self.api.verify_key())
+    }
+
+    /// Retrieve supported languages, see [`crate::DeepLApi::languages`].
+    pub fn languages(&self, lang_type: LangType) -> Result<Vec<LangInfo>> {
+        self.wait(self.api.languages(lang_type))
+    }
+
+    /// Create a glossary, see [`crate::DeepLApi::create_glossary`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let resp = deepl
+    ///     .create_glossary("My Glossary")
+    ///     .source_lang(Lang::EN)
+    ///     .target_lang(Lang::DE)
+    ///     .entries([("Hello", "Guten Tag")])
+    ///     .send()
+    ///     .unwrap();
+    /// ```
+    pub fn create_glossary(&self, name: impl ToString) -> CreateGlossary<'_> {
+        CreateGlossary {
+            client: self,
+            name: name.to_string(),
+            source_lang: None,
+            target_lang: None,
+            entries: Vec::new(),
+            format: EntriesFormat::TSV,
+        }
+    }
+
+    /// List all glossaries, see [`crate::DeepLApi::list_all_glossaries`].
+    pub fn list_all_glossaries(&self) -> Result<Vec<GlossaryResp>> {
+        self.wait(self.api.list_all_glossaries())
+    }
+
+    /// Retrieve meta information of a glossary, see [`crate::DeepLApi::retrieve_glossary_details`].
+    pub fn retrieve_glossary_details(&self, id: impl ToString) -> Result<GlossaryResp> {
+        self.wait(self.api.retrieve_glossary_details(id))
+    }
+
+    /// Delete a glossary, see [`crate::DeepLApi::delete_glossary`].
+    pub fn delete_glossary(&self, id: impl ToString) -> Result<()> {
+        self.wait(self.api.delete_glossary(id))
+    }
+
+    /// List the entries of a glossary, see [`crate::DeepLApi::retrieve_glossary_entries`].
+    pub fn retrieve_glossary_entries(&self, id: impl ToString) -> Result<Vec<(String, String)>> {
+        self.wait(self.api.retrieve_glossary_entries(id))
+    }
+
+    /// List the supported glossary language pairs, see
+    /// [`crate::DeepLApi::list_glossary_language_pairs`].
+    pub fn list_glossary_language_pairs(&self) -> Result<Vec<GlossaryLanguagePair>> {
+        self.wait(self.api.list_glossary_language_pairs())
+    }
+}
+
+impl DeepLApiBuilder {
+    /// Build a blocking api instance, see [`build`](Self::build).
+    pub fn build_blocking(self) -> Result<DeepLApi> {
+        DeepLApi::from_async(self.build()?)
+    }
+}
+
+/// Blocking builder type for `TranslateRequester`
+#[derive(Debug)]
+pub struct TranslateRequester<'a> {
+    client: &'a DeepLApi,
+    inner: crate::endpoint::translate::TranslateRequester<'a>,
+}
+
+macro_rules! forward_setters {
+    ($($field:ident: $ty:ty,)*) => {
+        $(
+            #[doc = concat!("Setter for `", stringify!($field), "`")]
+            pub fn $field(&mut self, $field: impl crate::IntoParam<$ty>) -> &mut Self {
+                self.inner.$field($field);
+                self
+            }
+        )*
+    };
+}
+
+impl TranslateRequester<'_> {
+    forward_setters! {
+        context: String,
+        source_lang: Lang,
+        split_sentences: SplitSentences,
+        preserve_formatting: PreserveFormatting,
+        formality: Formality,
+        glossary_id: String,
+        tag_handling: TagHandling,
+        non_splitting_tags: Vec<String>,
+        splitting_tags: Vec<String>,
+        ignore_tags: Vec<String>,
+        show_billed_characters: bool,
+        model_type: ModelType,
+        outline_detection: bool,
+    }
+
+    /// Set a header on this request only, see [`crate::DeepLApi::with_header`]
+    pub fn header(&mut self, name: &str, value: &str) -> &mut Self {
+        self.inner.header(name, value);
+        self
+    }
+
+    /// Send the request even if the body exceeds the limit of DeepL, see
+    /// [`crate::endpoint::translate::TranslateRequester::skip_size_check`]
+    pub fn skip_size_check(&mut self) -> &mut Self {
+        self.inner.skip_size_check();
+        self
+    }
+
+    /// Send the request and wait for the response
+    pub fn send(&mut self) -> Result<TranslateTextResp> {
+        self.client.wait(&mut self.inner)
+    }
+}
+
+/// Blocking builder type for creating a glossary
+#[derive(Debug)]
+pub struct CreateGlossary<'a> {
+    client: &'a DeepLApi,
+    name: String,
+    source_lang: Option<Lang>,
+    target_lang: Option<Lang>,
+    entries: Vec<(String, String)>,
+    format: EntriesFormat,
+}
+
+impl CreateGlossary<'_> {
+    /// Setter for `source_lang`, required
+    pub fn source_lang(&mut self, lang: Lang) -> &mut Self {
+        self.source_lang = Some(lang);
+        self
+    }
+
+    /// Setter for `target_lang`, required
+    pub fn target_lang(&mut self, lang: Lang) -> &mut Self {
+        self.target_lang = Some(lang);
+        self
+    }
+
+    /// The entries of the glossary, see
+    /// [`CreateGlossaryBuilder::entries`](crate::glossary::CreateGlossaryBuilder::entries).
+    pub fn entries<S, T, B, I>(&mut self, iter: I) -> &mut Self
+    where
+        S: ToString,
+        T: ToString,
+        B: Borrow<(S, T)>,
+        I: IntoIterator<Item = B>,
+    {
+        self.entries = iter
+            .into_iter()
+            .map(|t| (t.borrow().0.to_string(), t.borrow().1.to_string()))
+            .collect();
+        self
+    }
+
+    /// Setter for `format`, TSV by default
+    pub fn format(&mut self, format: EntriesFormat) -> &mut Self {
+        self.format = format;
+        self
+    }
+
+    /// Send the request and wait for the response
+    ///
+    /// # Error
+    ///
+    /// Return [`Error::InvalidConfig`] if the source or target language is not set.
+    pub fn send(&mut self) -> Result<GlossaryResp> {
+        let (Some(source_lang), Some(target_lang)) = (&self.source_lang, &self.target_lang) else {
+            return Err(Error::InvalidConfig(
+                "source_lang and target_lang of a glossary are required".to_string(),
+            ));
+        };
+
+        let req = self
+            .client
+            .api
+            .create_glossary(&self.name)
+            .source_lang(source_lang.clone())
+            .target_lang(target_lang.clone())
+            .entries(&self.entries)
+            .format(self.format)
+            .send();
+        self.client.wait(req)
+    }
+}
+
+#[test]
+fn test_blocking_client() {
+    use crate::testing::{FakeServer, FAKE_AUTH_KEY};
+
+    let server_rt = Runtime::new().unwrap();
+    let server = server_rt.block_on(FakeServer::start());
+    let deepl = DeepLApi::with(FAKE_AUTH_KEY)
+        .endpoint(server.endpoint())
+        .build_blocking()
+        .unwrap();
+
+    let resp = deepl
+        .translate_text("Hello", Lang::DE)
+        .source_lang(Lang::EN)
+        .send()
+        .unwrap();
+    assert_eq!(resp.translations.len(), 1);
+    assert!(deepl.get_usage().unwrap().character_limit > 0);
+    assert!(!deepl.languages(LangType::Target).unwrap().is_empty());
+
+    let glossary = deepl
+        .create_glossary("blocking")
+        .source_lang(Lang::EN)
+        .target_lang(Lang::DE)
+        .entries([("Hello", "Hallo")])
+        .send()
+        .unwrap();
+    assert_eq!(
+        deepl
+            .retrieve_glossary_entries(&glossary.glossary_id)
+            .unwrap(),
+        vec![("Hello".to_string(), "Hallo".to_string())]
+    );
+    deepl.delete_glossary(&glossary.glossary_id).unwrap();
+    assert!(deepl.list_all_glossaries().unwrap().is_empty());
+
+    let mut missing_lang = deepl.create_glossary("invalid");
+    assert!(matches!(missing_lang.send(), Err(Error::InvalidConfig(_))));
+}
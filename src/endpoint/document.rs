@@ -1,15 +1,31 @@
-use super::{Pollable, Result};
-use crate::{impl_requester, Formality, Lang};
+use super::{ResponseFuture, Result};
+use crate::{impl_requester, Formality, Lang, RawResponse, TranslateOptions};
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::multipart::Part;
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "tokio-fs")]
+use std::path::{Path, PathBuf};
+use std::{borrow::Cow, future::IntoFuture};
+#[cfg(not(target_arch = "wasm32"))]
 use std::{
-    future::IntoFuture,
-    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
 };
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::io::AsyncRead;
+use tokio::io::AsyncWrite;
+#[cfg(feature = "tokio-fs")]
 use tokio::io::AsyncWriteExt;
+#[cfg(feature = "tokio-fs")]
 use tokio_stream::StreamExt;
+#[cfg(not(target_arch = "wasm32"))]
+use tokio_util::io::ReaderStream;
 
 /// Response from api/v2/document
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UploadDocumentResp {
     /// A unique ID assigned to the uploaded document and the translation process.
     /// Must be used when referring to this particular document in subsequent API requests.
@@ -21,7 +37,7 @@ pub struct UploadDocumentResp {
 }
 
 /// Response from api/v2/document/$ID
-#[derive(Deserialize, Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DocumentStatusResp {
     /// A unique ID assigned to the uploaded document and the requested translation process.
     /// The same ID that was used when requesting the translation status.
@@ -40,7 +56,7 @@ pub struct DocumentStatusResp {
 }
 
 /// Possible value of the document translate status
-#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum DocumentTranslateStatus {
     /// The translation job is waiting in line to be processed
@@ -59,10 +75,140 @@ impl DocumentTranslateStatus {
     }
 }
 
+/// Format of the translated document, if not the format of the uploaded one. DeepL rejects
+/// conversions it doesn't support with an [`Error::Api`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DocumentOutputFormat {
+    Docx,
+    Pptx,
+    Xlsx,
+    Pdf,
+    Htm,
+    Html,
+    Txt,
+    Xlf,
+    Xliff,
+    Srt,
+    /// Another file extension, without the dot
+    Other(String),
+}
+
+impl AsRef<str> for DocumentOutputFormat {
+    fn as_ref(&self) -> &str {
+        match self {
+            Self::Docx => "docx",
+            Self::Pptx => "pptx",
+            Self::Xlsx => "xlsx",
+            Self::Pdf => "pdf",
+            Self::Htm => "htm",
+            Self::Html => "html",
+            Self::Txt => "txt",
+            Self::Xlf => "xlf",
+            Self::Xliff => "xliff",
+            Self::Srt => "srt",
+            Self::Other(extension) => extension,
+        }
+    }
+}
+
+impl std::fmt::Display for DocumentOutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_ref())
+    }
+}
+
+impl Serialize for DocumentOutputFormat {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_ref())
+    }
+}
+
+/// Content of a document to upload
+#[derive(Debug, Clone, Serialize)]
+pub enum DocumentSource {
+    /// Read the document from the file system
+    #[cfg(feature = "tokio-fs")]
+    Path(PathBuf),
+    /// Document already in memory, with the file name telling DeepL its type
+    Bytes { content: Vec<u8>, filename: String },
+    /// Document streamed from a reader, with the file name telling DeepL its type
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(skip)]
+    Reader {
+        reader: DocumentReader,
+        filename: String,
+    },
+}
+
+/// A reader streamed as the document, see [`DeepLApi::upload_document_from_reader`]. Clones
+/// share the reader, so only the first upload can read it.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone)]
+pub struct DocumentReader {
+    reader: Arc<Mutex<Option<BoxedReader>>>,
+    length: Option<u64>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+type BoxedReader = Pin<Box<dyn AsyncRead + Send>>;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl std::fmt::Debug for DocumentReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DocumentReader")
+            .field("length", &self.length)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Bytes read from a [`DocumentReader`] while uploading, and the error that stopped it
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default)]
+struct ReadProgress {
+    read: AtomicU64,
+    error: Mutex<Option<std::io::Error>>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl DocumentReader {
+    /// Take the reader as the file part of the form, fail if it was already taken
+    fn into_part(self, filename: String) -> Result<(Part, Arc<ReadProgress>)> {
+        let reader = self.reader.lock().unwrap().take().ok_or_else(|| {
+            Error::InvalidInput(format!("the reader of {filename:?} was already uploaded"))
+        })?;
+        let progress = Arc::new(ReadProgress::default());
+        let shared = progress.clone();
+        let stream = tokio_stream::StreamExt::map(ReaderStream::new(reader), move |chunk| {
+            match chunk {
+                Ok(bytes) => {
+                    shared.read.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+                    Ok(bytes)
+                }
+                Err(err) => {
+                    // reqwest only keeps the message, the error itself is returned by the upload
+                    let copy = std::io::Error::new(err.kind(), err.to_string());
+                    *shared.error.lock().unwrap() = Some(err);
+                    Err(copy)
+                }
+            }
+        });
+
+        let body = reqwest::Body::wrap_stream(stream);
+        let part = match self.length {
+            Some(length) => Part::stream_with_length(body, length),
+            None => Part::stream(body),
+        };
+        Ok((part.file_name(filename), progress))
+    }
+}
+
 impl_requester! {
     UploadDocumentRequester {
         @required{
-            file_path: PathBuf,
+            document: DocumentSource,
             target_lang: Lang,
         };
         @optional{
@@ -70,91 +216,269 @@ impl_requester! {
             filename: String,
             formality: Formality,
             glossary_id: String,
+            output_format: DocumentOutputFormat,
+            retry: bool,
         };
     } -> Result<UploadDocumentResp, Error>;
 }
 
+/// Names of the multipart fields set by the upload, which extra parameters can't override
+const UPLOAD_FIELDS: [&str; 7] = [
+    "file",
+    "filename",
+    "formality",
+    "glossary_id",
+    "output_format",
+    "source_lang",
+    "target_lang",
+];
+
 impl<'a> UploadDocumentRequester<'a> {
-    fn to_multipart_form(&self) -> reqwest::multipart::Form {
+    /// Text fields of the multipart form
+    fn form_fields(&self) -> Vec<(Cow<'static, str>, String)> {
         let Self {
             source_lang,
             target_lang,
             formality,
             glossary_id,
+            output_format,
             ..
         } = self;
 
-        let mut form = reqwest::multipart::Form::new();
+        let mut fields = Vec::new();
 
         // SET source_lang
         if let Some(lang) = source_lang {
-            form = form.text("source_lang", lang.to_string());
+            fields.push(("source_lang".into(), lang.to_string()));
         }
 
         // SET target_lang
-        form = form.text("target_lang", target_lang.to_string());
+        fields.push(("target_lang".into(), target_lang.to_string()));
 
         // SET formality
         if let Some(formal) = formality {
-            form = form.text("formality", formal.to_string());
+            fields.push(("formality".into(), formal.to_string()));
         }
 
         // SET glossary
         if let Some(id) = glossary_id {
-            form = form.text("glossary_id", id.to_string());
+            fields.push(("glossary_id".into(), id.to_string()));
+        }
+
+        // SET output_format
+        if let Some(format) = output_format {
+            fields.push(("output_format".into(), format.to_string()));
+        }
+
+        // SET the extra parameters, the typed ones win
+        for (key, value) in &self.options.extra_params {
+            if UPLOAD_FIELDS.contains(&key.as_str()) {
+                continue;
+            }
+            let value = match value {
+                serde_json::Value::Null => continue,
+                serde_json::Value::String(value) => value.clone(),
+                value => value.to_string(),
+            };
+            fields.push((key.clone().into(), value));
         }
 
-        form
+        fields
     }
 
-    fn send(&self) -> Pollable<'a, Result<UploadDocumentResp>> {
-        let mut form = self.to_multipart_form();
-        let client = self.client.clone();
-        let filename = self.filename.clone();
-        let file_path = self.file_path.clone();
+    /// Use the glossary, setting `glossary_id` and the `source_lang` of the glossary
+    ///
+    /// # Error
+    ///
+    /// Uploading fails with [`Error::InvalidInput`] if the glossary isn't for the target
+    /// language.
+    pub fn glossary(&mut self, glossary: &crate::glossary::GlossaryResp) -> &mut Self {
+        if let Err(err) = glossary.check_target(&self.target_lang) {
+            self.options.set_invalid_input(err);
+        }
+        self.glossary_id = Some(glossary.glossary_id.clone());
+        self.source_lang = Some(glossary.source_lang.clone());
+        self
+    }
 
-        let fut = async move {
-            // SET file && filename asynchronously
-            let file = tokio::fs::read(&file_path).await.map_err(|err| {
-                Error::ReadFileError(file_path.to_str().unwrap().to_string(), err)
-            })?;
-
-            let mut part = reqwest::multipart::Part::bytes(file);
-            if let Some(filename) = filename {
-                part = part.file_name(filename.to_string());
-                form = form.text("filename", filename);
-            } else {
-                part = part.file_name(file_path.file_name().expect(
-                    "No extension found for this file, and no filename given, cannot make request",
-                ).to_str().expect("not a valid UTF-8 filepath!").to_string());
+    /// A clone of this requester uploading another file, to use a configured requester as a
+    /// template
+    #[cfg(feature = "tokio-fs")]
+    pub fn with_path(&self, fp: impl Into<PathBuf>) -> Self {
+        let mut requester = self.clone();
+        requester.document = DocumentSource::Path(fp.into());
+        requester
+    }
+
+    /// A clone of this requester uploading another document in memory, see
+    /// [`DeepLApi::upload_document_from_bytes`]
+    pub fn with_bytes(&self, content: impl Into<Vec<u8>>, filename: impl ToString) -> Self {
+        let mut requester = self.clone();
+        requester.document = DocumentSource::Bytes {
+            content: content.into(),
+            filename: filename.to_string(),
+        };
+        requester
+    }
+
+    /// Size of a document streamed by [`DeepLApi::upload_document_from_reader`], sent as the
+    /// length of the file part. Ignored for other documents.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn content_length(&mut self, length: u64) -> &mut Self {
+        if let DocumentSource::Reader { reader, .. } = &mut self.document {
+            reader.length = Some(length);
+        }
+        self
+    }
+
+    /// Set the options of `options` that apply to documents, i.e. `source_lang`, `formality` and
+    /// `glossary_id`, keeping the others. The options for text only, like `tag_handling` or
+    /// `context`, are ignored, so one [`TranslateOptions`] can be used for text and documents.
+    pub fn with_options(&mut self, options: &TranslateOptions) -> &mut Self {
+        if let Some(lang) = &options.source_lang {
+            self.source_lang = Some(lang.clone());
+        }
+        if let Some(formality) = &options.formality {
+            self.formality = Some(formality.clone());
+        }
+        if let Some(id) = &options.glossary_id {
+            self.glossary_id = Some(id.clone());
+        }
+        self
+    }
+
+    /// Send an extra text field in the multipart form, see
+    /// [`TranslateRequester::extra_param`](crate::endpoint::translate::TranslateRequester::extra_param).
+    /// A string is sent as is, other values as JSON and `null` not at all.
+    pub fn extra_param(&mut self, key: &str, value: serde_json::Value) -> &mut Self {
+        self.options.insert_extra_param(key, value);
+        self
+    }
+
+    /// Fail with [`Error::InvalidInput`] before uploading if the formality isn't supported by
+    /// the target language, see
+    /// [`TranslateRequester::validate_formality`](crate::endpoint::translate::TranslateRequester::validate_formality)
+    pub fn validate_formality(&mut self) -> &mut Self {
+        self.options.validate_formality = true;
+        self
+    }
+
+    fn send(&self) -> ResponseFuture<'a, UploadDocumentResp> {
+        let named = match &self.document {
+            #[cfg(feature = "tokio-fs")]
+            DocumentSource::Path(_) => None,
+            DocumentSource::Bytes { filename, .. } => {
+                Some(self.filename.as_ref().unwrap_or(filename))
             }
+            #[cfg(not(target_arch = "wasm32"))]
+            DocumentSource::Reader { filename, .. } => {
+                Some(self.filename.as_ref().unwrap_or(filename))
+            }
+        };
+        if let Some(name) = named.filter(|name| std::path::Path::new(name).extension().is_none()) {
+            let err = Error::InvalidInput(format!(
+                "filename {name:?} has no extension to tell the type of the document"
+            ));
+            return ResponseFuture::new(Box::pin(std::future::ready(Err(err))));
+        }
 
-            form = form.part("file", part);
+        if let Some(DocumentOutputFormat::Other(extension)) = &self.output_format {
+            if extension.is_empty() || !extension.chars().all(char::is_alphanumeric) {
+                let err = Error::InvalidInput(format!("invalid output format {extension:?}"));
+                return ResponseFuture::new(Box::pin(std::future::ready(Err(err))));
+            }
+        }
 
-            let res = client
-                .post(client.get_endpoint("document"))
-                .multipart(form)
-                .send()
-                .await
-                .map_err(|err| Error::RequestFail(format!("fail to upload file: {err}")))?;
+        let fields = self.form_fields();
+        let formality = self
+            .options
+            .validate_formality
+            .then(|| (self.target_lang.clone(), self.formality.clone()));
+        let retry = self.retry.unwrap_or(false);
+        let client = self.options.apply(&self.client);
+        let filename = self.filename.clone();
+        let document = self.document.clone();
 
-            if !res.status().is_success() {
-                return super::extract_deepl_error(res).await;
+        let fut = async move {
+            let client = client?;
+            if let Some((target_lang, formality)) = formality {
+                client
+                    .check_formality(&target_lang, formality.as_ref())
+                    .await?;
             }
+            client
+                .call("upload_document", None, async {
+                let url = client.get_endpoint("document")?;
+                let text_form = || {
+                    let mut form = reqwest::multipart::Form::new();
+                    for (name, value) in &fields {
+                        form = form.text(name.clone(), value.clone());
+                    }
+                    if let Some(filename) = &filename {
+                        form = form.text("filename", filename.to_string());
+                    }
+                    form
+                };
 
-            let res: UploadDocumentResp = res.json().await.map_err(|err| {
-                Error::InvalidResponse(format!("fail to decode response body: {err}"))
-            })?;
-            Ok(res)
+                let (file, default_filename) = match document {
+                    // SET file && filename asynchronously
+                    #[cfg(feature = "tokio-fs")]
+                    DocumentSource::Path(file_path) => {
+                        let file = tokio::fs::read(&file_path).await.map_err(|source| {
+                            Error::ReadFileError { path: file_path.clone(), source }
+                        })?;
+                        let default_filename = file_path.file_name().expect(
+                            "No extension found for this file, and no filename given, cannot make request",
+                        ).to_str().expect("not a valid UTF-8 filepath!").to_string();
+                        (file, default_filename)
+                    }
+                    DocumentSource::Bytes { content, filename } => (content, filename),
+                    // a stream can't be sent again, so it is never retried
+                    #[cfg(not(target_arch = "wasm32"))]
+                    DocumentSource::Reader { reader, filename: default_filename } => {
+                        let name = filename.clone().unwrap_or(default_filename);
+                        let (part, progress) = reader.into_part(name.clone())?;
+                        let res = client
+                            .execute(client.post(url).multipart(text_form().part("file", part)))
+                            .await;
+                        client.inner.stats.add_uploaded(progress.read.load(Ordering::Relaxed));
+                        if let Some(source) = progress.error.lock().unwrap().take() {
+                            return Err(Error::ReadFileError { path: name.into(), source });
+                        }
+                        return upload_response(res?).await;
+                    }
+                };
+
+                client.inner.stats.add_uploaded(file.len() as u64);
+                let make_request = || {
+                    let part = reqwest::multipart::Part::bytes(file.clone())
+                        .file_name(filename.clone().unwrap_or_else(|| default_filename.clone()));
+                    client.post(url.clone()).multipart(text_form().part("file", part))
+                };
+
+                let res = client.execute_rebuilt(make_request, retry).await?;
+                upload_response(res).await
+                })
+                .await
         };
 
-        Box::pin(fut)
+        ResponseFuture::new(Box::pin(fut))
+    }
+}
+
+async fn upload_response(res: reqwest::Response) -> Result<UploadDocumentResp> {
+    if !res.status().is_success() {
+        return super::extract_deepl_error(res).await;
     }
+
+    res.json()
+        .await
+        .map_err(|err| Error::InvalidResponse(format!("fail to decode response body: {err}")))
 }
 
 impl<'a> IntoFuture for UploadDocumentRequester<'a> {
     type Output = Result<UploadDocumentResp>;
-    type IntoFuture = Pollable<'a, Self::Output>;
+    type IntoFuture = ResponseFuture<'a, UploadDocumentResp>;
 
     fn into_future(self) -> Self::IntoFuture {
         self.send()
@@ -163,13 +487,35 @@ impl<'a> IntoFuture for UploadDocumentRequester<'a> {
 
 impl<'a> IntoFuture for &mut UploadDocumentRequester<'a> {
     type Output = Result<UploadDocumentResp>;
-    type IntoFuture = Pollable<'a, Self::Output>;
+    type IntoFuture = ResponseFuture<'a, UploadDocumentResp>;
 
     fn into_future(self) -> Self::IntoFuture {
         self.send()
     }
 }
 
+impl<'a> IntoFuture for &UploadDocumentRequester<'a> {
+    type Output = Result<UploadDocumentResp>;
+    type IntoFuture = ResponseFuture<'a, UploadDocumentResp>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        self.send()
+    }
+}
+
+/// Path of a file being downloaded, removed on drop unless the download completed
+#[cfg(feature = "tokio-fs")]
+struct PartialFile<'p>(Option<&'p Path>);
+
+#[cfg(feature = "tokio-fs")]
+impl Drop for PartialFile<'_> {
+    fn drop(&mut self) {
+        if let Some(path) = self.0 {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
 impl DeepLApi {
     /// Upload document to DeepL API server, return [`UploadDocumentResp`] for
     /// querying the translation status and to download the translated document once
@@ -187,60 +533,106 @@ impl DeepLApi {
     /// let filepath = std::path::PathBuf::from("./hamlet.txt");
     /// let response = deepl.upload_document(&filepath, Lang::ZH)
     ///         .source_lang(Lang::EN)
-    ///         .filename("Hamlet.txt".to_string())
+    ///         .filename("Hamlet.txt")
     ///         .formality(Formality::Default)
-    ///         .glossary_id("def3a26b-3e84-45b3-84ae-0c0aaf3525f7".to_string())
+    ///         .glossary_id("def3a26b-3e84-45b3-84ae-0c0aaf3525f7")
     ///         .await
     ///         .unwrap();
     /// ```
     ///
     /// Read the example `upload_document` in repository for detailed usage
+    #[cfg(feature = "tokio-fs")]
     pub fn upload_document(
         &self,
         fp: impl Into<std::path::PathBuf>,
         target_lang: Lang,
-    ) -> UploadDocumentRequester {
-        UploadDocumentRequester::new(self, fp.into(), target_lang)
+    ) -> UploadDocumentRequester<'_> {
+        UploadDocumentRequester::new(self, DocumentSource::Path(fp.into()), target_lang)
     }
 
-    async fn open_file_to_write(p: &Path) -> Result<tokio::fs::File> {
-        let open_result = tokio::fs::OpenOptions::new()
-            .append(true)
-            .create_new(true)
-            .open(p)
-            .await;
-
-        if let Ok(file) = open_result {
-            return Ok(file);
-        }
+    /// Like [`upload_document`](Self::upload_document), but upload a document in memory. The
+    /// `filename` extension tells DeepL the type of the document, a filename without extension
+    /// fails with [`Error::InvalidInput`] before uploading. Also available on wasm32.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let response = deepl
+    ///     .upload_document_from_bytes(b"Hello World".to_vec(), "hello.txt", Lang::DE)
+    ///     .await
+    ///     .unwrap();
+    /// ```
+    pub fn upload_document_from_bytes(
+        &self,
+        content: impl Into<Vec<u8>>,
+        filename: impl ToString,
+        target_lang: Lang,
+    ) -> UploadDocumentRequester<'_> {
+        let document = DocumentSource::Bytes {
+            content: content.into(),
+            filename: filename.to_string(),
+        };
+        UploadDocumentRequester::new(self, document, target_lang)
+    }
 
-        let err = open_result.unwrap_err();
-        if err.kind() != std::io::ErrorKind::AlreadyExists {
-            return Err(Error::WriteFileError(format!(
-                "Fail to open file {p:?}: {err}"
-            )));
-        }
+    /// Like [`upload_document_from_bytes`](Self::upload_document_from_bytes), but stream the
+    /// document from `reader`, e.g. straight from an object store, without holding it in memory.
+    /// Set its size with [`content_length`](UploadDocumentRequester::content_length) if known.
+    ///
+    /// A stream can't be sent twice, so the upload is never retried. If reading fails, the upload
+    /// fails with [`Error::ReadFileError`] whose path is the filename.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let object = bucket.get_object("reports/q3.pdf").await?;
+    /// let response = deepl
+    ///     .upload_document_from_reader(object.into_async_read(), "q3.pdf", Lang::DE)
+    ///     .content_length(object.size)
+    ///     .await?;
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn upload_document_from_reader(
+        &self,
+        reader: impl AsyncRead + Send + 'static,
+        filename: impl ToString,
+        target_lang: Lang,
+    ) -> UploadDocumentRequester<'_> {
+        let document = DocumentSource::Reader {
+            reader: DocumentReader {
+                reader: Arc::new(Mutex::new(Some(Box::pin(reader)))),
+                length: None,
+            },
+            filename: filename.to_string(),
+        };
+        UploadDocumentRequester::new(self, document, target_lang)
+    }
 
-        tokio::fs::remove_file(p).await.map_err(|err| {
-            Error::WriteFileError(format!(
-                "There was already a file there and it is not deletable: {err}"
-            ))
-        })?;
-        dbg!("Detect exist, removed");
-
-        let open_result = tokio::fs::OpenOptions::new()
-            .append(true)
-            .create_new(true)
-            .open(p)
-            .await;
-
-        if let Err(err) = open_result {
-            return Err(Error::WriteFileError(format!(
-                "Fail to open file for download document, even after retry: {err}"
-            )));
+    #[cfg(feature = "tokio-fs")]
+    async fn open_file_to_write(p: &Path) -> Result<tokio::fs::File> {
+        let write_error = |source| Error::WriteFileError {
+            path: p.to_path_buf(),
+            source,
+        };
+        let open = || async {
+            tokio::fs::OpenOptions::new()
+                .append(true)
+                .create_new(true)
+                .open(p)
+                .await
+        };
+
+        match open().await {
+            Ok(file) => return Ok(file),
+            Err(err) if err.kind() != std::io::ErrorKind::AlreadyExists => {
+                return Err(write_error(err))
+            }
+            Err(_) => {}
         }
 
-        Ok(open_result.unwrap())
+        // replace the existing file
+        tokio::fs::remove_file(p).await.map_err(write_error)?;
+        open().await.map_err(write_error)
     }
 
     /// Check the status of document, returning [`DocumentStatusResp`] if success.
@@ -248,44 +640,179 @@ impl DeepLApi {
         &self,
         ident: &UploadDocumentResp,
     ) -> Result<DocumentStatusResp> {
-        let form = [("document_key", ident.document_key.as_str())];
-        let url = self.get_endpoint(&format!("document/{}", ident.document_id));
-        let res = self
-            .post(url)
-            .form(&form)
-            .send()
-            .await
-            .map_err(|err| Error::RequestFail(err.to_string()))?;
+        self.call("check_document_status", None, async {
+            let res = self.document_status_request(ident).await?;
 
-        if !res.status().is_success() {
-            return super::extract_deepl_error(res).await;
-        }
+            if !res.status().is_success() {
+                return super::extract_deepl_error(res).await;
+            }
 
-        let status: DocumentStatusResp = res
-            .json()
-            .await
-            .map_err(|err| Error::InvalidResponse(format!("response is not JSON: {err}")))?;
+            let status: DocumentStatusResp = res
+                .json()
+                .await
+                .map_err(|err| Error::InvalidResponse(format!("response is not JSON: {err}")))?;
+            if let (true, Some(billed)) = (status.status.is_done(), status.billed_characters) {
+                self.record_billed(&status.document_id, billed);
+            }
+
+            Ok(status)
+        })
+        .await
+        .map_err(|err| err.with_id(&ident.document_id))
+    }
 
-        Ok(status)
+    /// Like [`check_document_status`](Self::check_document_status), but return the response
+    /// without decoding it, see [`RawResponse`].
+    pub async fn check_document_status_raw(
+        &self,
+        ident: &UploadDocumentResp,
+    ) -> Result<RawResponse> {
+        self.call("check_document_status", None, async {
+            self.document_status_request(ident)
+                .await
+                .map(RawResponse::new)
+        })
+        .await
+    }
+
+    async fn document_status_request(
+        &self,
+        ident: &UploadDocumentResp,
+    ) -> Result<reqwest::Response> {
+        let form = [("document_key", ident.document_key.as_str())];
+        let url = self.get_resource_endpoint("document", &ident.document_id, &[])?;
+        self.execute_retry(self.post(url).form(&form)).await
     }
 
     /// Download the possibly translated document. Downloaded document will store to the given
     /// `output` path.
     ///
     /// Return downloaded file's path if success
+    #[cfg(feature = "tokio-fs")]
     pub async fn download_document<O: AsRef<Path>>(
         &self,
         ident: &UploadDocumentResp,
         output: O,
     ) -> Result<PathBuf> {
-        let url = self.get_endpoint(&format!("document/{}/result", ident.document_id));
+        self.call("download_document", None, async {
+            let res = self.document_result(ident).await?;
+            let mut file = Self::open_file_to_write(output.as_ref()).await?;
+            // remove the partial file if the download fails or is cancelled
+            let mut partial = PartialFile(Some(output.as_ref()));
+
+            let mut stream = res.bytes_stream();
+
+            let write_error = |source| Error::WriteFileError {
+                path: output.as_ref().to_path_buf(),
+                source,
+            };
+
+            while let Some(chunk) = stream.next().await {
+                let chunk = chunk?;
+                self.record_bytes(chunk.len() as u64);
+                file.write_all(&chunk).await.map_err(write_error)?;
+                file.sync_all().await.map_err(write_error)?;
+            }
+            partial.0 = None;
+
+            Ok(output.as_ref().to_path_buf())
+        })
+        .await
+        .map_err(|err| err.with_id(&ident.document_id))
+    }
+
+    /// Download the possibly translated document into `writer`, e.g. a socket or an upload to
+    /// an object store, and return the number of bytes written. The writer is flushed but not
+    /// shut down. Also available on wasm32.
+    ///
+    /// A failed write fails with [`Error::WriteError`], holding the bytes written before.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let mut socket = tokio::net::TcpStream::connect("127.0.0.1:9000").await?;
+    /// let written = deepl.download_document_to(&ident, &mut socket).await?;
+    /// ```
+    pub async fn download_document_to<W: AsyncWrite + Unpin>(
+        &self,
+        ident: &UploadDocumentResp,
+        writer: &mut W,
+    ) -> Result<u64> {
+        use tokio::io::AsyncWriteExt as _;
+
+        self.call("download_document_to", None, async {
+            let mut res = self.document_result(ident).await?;
+            let mut written = 0;
+            let write_error = |written, source| Error::WriteError {
+                document_id: ident.document_id.clone(),
+                written,
+                source,
+            };
+
+            while let Some(chunk) = res.chunk().await? {
+                self.record_bytes(chunk.len() as u64);
+                writer
+                    .write_all(&chunk)
+                    .await
+                    .map_err(|source| write_error(written, source))?;
+                written += chunk.len() as u64;
+            }
+            writer
+                .flush()
+                .await
+                .map_err(|source| write_error(written, source))?;
+
+            Ok(written)
+        })
+        .await
+        .map_err(|err| err.with_id(&ident.document_id))
+    }
+
+    /// Download the possibly translated document into memory. Also available on wasm32.
+    pub async fn download_document_bytes(&self, ident: &UploadDocumentResp) -> Result<Vec<u8>> {
+        self.download_document_bytes_limited(ident, u64::MAX).await
+    }
+
+    /// Like [`download_document_bytes`](Self::download_document_bytes), but fail with
+    /// [`Error::DocumentTooLarge`] as soon as the document turns out to be larger than `limit`
+    /// bytes, without reading the rest.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let content = deepl
+    ///     .download_document_bytes_limited(&ident, 20 * 1024 * 1024)
+    ///     .await?;
+    /// ```
+    pub async fn download_document_bytes_limited(
+        &self,
+        ident: &UploadDocumentResp,
+        limit: u64,
+    ) -> Result<Vec<u8>> {
+        self.call("download_document_bytes", None, async {
+            let mut res = self.document_result(ident).await?;
+            if res.content_length().is_some_and(|len| len > limit) {
+                return Err(Error::DocumentTooLarge { limit });
+            }
+
+            let mut content = Vec::new();
+            while let Some(chunk) = res.chunk().await? {
+                self.record_bytes(chunk.len() as u64);
+                if (content.len() + chunk.len()) as u64 > limit {
+                    return Err(Error::DocumentTooLarge { limit });
+                }
+                content.extend_from_slice(&chunk);
+            }
+            Ok(content)
+        })
+        .await
+        .map_err(|err| err.with_id(&ident.document_id))
+    }
+
+    async fn document_result(&self, ident: &UploadDocumentResp) -> Result<reqwest::Response> {
+        let url = self.get_resource_endpoint("document", &ident.document_id, &["result"])?;
         let form = [("document_key", ident.document_key.as_str())];
-        let res = self
-            .post(url)
-            .form(&form)
-            .send()
-            .await
-            .map_err(|err| Error::RequestFail(err.to_string()))?;
+        let res = self.execute(self.post(url).form(&form)).await?;
 
         if res.status() == reqwest::StatusCode::NOT_FOUND {
             return Err(Error::NonExistDocument);
@@ -299,39 +826,22 @@ impl DeepLApi {
             return super::extract_deepl_error(res).await;
         }
 
-        let mut file = Self::open_file_to_write(output.as_ref()).await?;
-
-        let mut stream = res.bytes_stream();
-
-        #[inline]
-        fn mapper<E: std::error::Error>(s: &'static str) -> Box<dyn FnOnce(E) -> Error> {
-            Box::new(move |err: E| Error::WriteFileError(format!("{s}: {err}")))
-        }
-
-        while let Some(chunk) = stream.next().await {
-            let chunk = chunk.map_err(mapper("fail to download part of the document"))?;
-            file.write_all(&chunk)
-                .await
-                .map_err(mapper("fail to write downloaded part into file"))?;
-            file.sync_all()
-                .await
-                .map_err(mapper("fail to sync file content"))?;
-        }
-
-        Ok(output.as_ref().to_path_buf())
+        Ok(res)
     }
 }
 
+#[cfg(feature = "tokio-fs")]
 #[tokio::test]
 async fn test_upload_document() {
-    let key = std::env::var("DEEPL_API_KEY").unwrap();
-    let api = DeepLApi::with(&key).new();
+    let (api, _server) = crate::testing::mock_api().await;
 
     let raw_text = "Hello World";
 
-    tokio::fs::write("./test.txt", &raw_text).await.unwrap();
+    let dir = std::env::temp_dir().join(format!("deepl-upload-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let test_file = dir.join("test.txt");
+    tokio::fs::write(&test_file, &raw_text).await.unwrap();
 
-    let test_file = PathBuf::from("./test.txt");
     let response = api.upload_document(&test_file, Lang::DE).await.unwrap();
     let mut status = api.check_document_status(&response).await.unwrap();
 
@@ -350,23 +860,28 @@ async fn test_upload_document() {
     }
 
     let path = api
-        .download_document(&response, "test_translated.txt")
+        .download_document(&response, dir.join("test_translated.txt"))
         .await
         .unwrap();
 
     let content = tokio::fs::read_to_string(path).await.unwrap();
-    let expect = "Hallo Welt";
+    let expect = "[DE] Hello World";
     assert_eq!(content, expect);
+    std::fs::remove_dir_all(&dir).unwrap();
 }
 
+#[cfg(feature = "tokio-fs")]
 #[tokio::test]
+#[ignore = "needs DEEPL_API_KEY"]
 async fn test_upload_docx() {
     use docx_rs::{read_docx, DocumentChild, Docx, Paragraph, ParagraphChild, Run, RunChild};
 
     let key = std::env::var("DEEPL_API_KEY").unwrap();
     let api = DeepLApi::with(&key).new();
 
-    let test_file = PathBuf::from("./example.docx");
+    let dir = std::env::temp_dir().join(format!("deepl-docx-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let test_file = dir.join("example.docx");
     let file = std::fs::File::create(&test_file).expect("fail to create test asserts");
     Docx::new()
         .add_paragraph(
@@ -395,7 +910,7 @@ async fn test_upload_docx() {
     }
 
     let path = api
-        .download_document(&response, "translated.docx")
+        .download_document(&response, dir.join("translated.docx"))
         .await
         .unwrap();
     let get = tokio::fs::read(&path).await.unwrap();
@@ -438,4 +953,424 @@ async fn test_upload_docx() {
         .collect::<String>();
 
     assert_eq!(text, "Sein oder nicht sein, das ist hier die Frage");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_document_bytes() {
+    let server = crate::testing::FakeServer::start().await;
+    let api = server.api();
+
+    let content: Vec<u8> = (1..=3)
+        .flat_map(|n| format!("Line {n}\n").into_bytes())
+        .collect();
+    let resp = api
+        .upload_document_from_bytes(content, "generated.txt", Lang::DE)
+        .source_lang(Lang::EN)
+        .formality(Formality::Less)
+        .glossary_id("glossary")
+        .await
+        .unwrap();
+    let status = api.check_document_status(&resp).await.unwrap();
+    assert!(status.status.is_done());
+    let content = api.download_document_bytes(&resp).await.unwrap();
+    assert_eq!(content, b"[DE] Line 1\nLine 2\nLine 3\n");
+
+    let request = &server.requests()[0];
+    let body = String::from_utf8_lossy(request.body());
+    assert!(body.contains("filename=\"generated.txt\""));
+    for (name, value) in [
+        ("source_lang", "EN"),
+        ("formality", "less"),
+        ("glossary_id", "glossary"),
+    ] {
+        assert!(body.contains(&format!("name=\"{name}\"\r\n\r\n{value}\r\n")));
+    }
+
+    let err = api
+        .upload_document_from_bytes(b"Hello".to_vec(), "hello", Lang::DE)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(msg) if msg.contains("\"hello\"")));
+    server.assert_request_count(3);
+}
+
+#[tokio::test]
+async fn test_shared_upload() {
+    let server = crate::testing::FakeServer::start().await;
+    let api = server.api();
+
+    let mut template = api.upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE);
+    template.source_lang(Lang::EN);
+    let first = (&template).await.unwrap();
+    let second = (&template).await.unwrap();
+    assert_ne!(first.document_id, second.document_id);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    for request in &requests {
+        let body = String::from_utf8_lossy(request.body());
+        assert!(body.contains("filename=\"hello.txt\""));
+        assert!(body.contains("EN"));
+    }
+}
+
+#[tokio::test]
+async fn test_upload_template() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    let mut template = api.upload_document_from_bytes(Vec::new(), "template.txt", Lang::DE);
+    template.source_lang(Lang::EN).formality(Formality::Less);
+    for name in ["a.txt", "b.txt"] {
+        let resp = template.with_bytes(name.as_bytes(), name).await.unwrap();
+        let content = api.download_document_bytes(&resp).await.unwrap();
+        assert_eq!(content, format!("[DE] {name}").as_bytes());
+    }
+
+    let uploads: Vec<_> = server
+        .requests()
+        .into_iter()
+        .filter(|r| r.path() == "/v2/document")
+        .collect();
+    assert_eq!(uploads.len(), 2);
+    for (request, name) in uploads.iter().zip(["a.txt", "b.txt"]) {
+        let form = String::from_utf8_lossy(request.body());
+        assert!(form.contains(&format!("filename=\"{name}\"")));
+        assert!(form.contains("name=\"formality\"\r\n\r\nless\r\n"));
+    }
+}
+
+#[tokio::test]
+async fn test_upload_glossary() {
+    let (api, server) = crate::testing::mock_api().await;
+    let glossary = api
+        .create_glossary("greetings")
+        .source_lang(Lang::EN)
+        .target_lang(Lang::EN_GB)
+        .entries([("Hello", "Hi")])
+        .send()
+        .await
+        .unwrap();
+
+    // a regional variant of the language of the glossary
+    api.upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::EN_US)
+        .glossary(&glossary)
+        .await
+        .unwrap();
+    let form = String::from_utf8_lossy(server.requests()[1].body()).to_string();
+    let id = &glossary.glossary_id;
+    assert!(form.contains(&format!("name=\"glossary_id\"\r\n\r\n{id}\r\n")));
+    assert!(form.contains("name=\"source_lang\"\r\n\r\nEN\r\n"));
+
+    let err = api
+        .upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+        .glossary(&glossary)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(_)));
+    server.assert_request_count(2);
+}
+
+#[tokio::test]
+async fn test_document_options() {
+    let (api, server) = crate::testing::mock_api().await;
+    let profile = TranslateOptions {
+        source_lang: Some(Lang::EN),
+        formality: Some(Formality::Less),
+        glossary_id: Some("glossary".to_string()),
+        tag_handling: Some(crate::TagHandling::Html),
+        context: Some("greeting".to_string()),
+        ..Default::default()
+    };
+
+    api.translate_with("Hello", Lang::DE, &profile)
+        .await
+        .unwrap();
+    api.upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+        .with_options(&profile)
+        .await
+        .unwrap();
+
+    let requests = server.requests();
+    let json = requests[0].json().unwrap();
+    let form = String::from_utf8_lossy(requests[1].body());
+    for (name, value) in [
+        ("source_lang", "EN"),
+        ("formality", "less"),
+        ("glossary_id", "glossary"),
+    ] {
+        assert_eq!(json[name], value);
+        assert!(form.contains(&format!("name=\"{name}\"\r\n\r\n{value}\r\n")));
+    }
+    assert_eq!(json["tag_handling"], "html");
+    assert!(!form.contains("tag_handling"));
+    assert!(!form.contains("context"));
+}
+
+#[tokio::test]
+async fn test_upload_setter_arguments() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    let glossary_id = String::from("def3a26b-3e84-45b3-84ae-0c0aaf3525f7");
+    api.upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::ZH)
+        .source_lang(Lang::EN)
+        .filename("Hamlet.txt")
+        .formality(Formality::Default)
+        .glossary_id(&glossary_id)
+        .await
+        .unwrap();
+
+    let form = String::from_utf8_lossy(server.requests()[0].body()).into_owned();
+    for (name, value) in [
+        ("source_lang", "EN"),
+        ("target_lang", "ZH"),
+        ("formality", "default"),
+        ("glossary_id", glossary_id.as_str()),
+    ] {
+        assert!(
+            form.contains(&format!("name=\"{name}\"\r\n\r\n{value}\r\n")),
+            "{form}"
+        );
+    }
+    assert!(form.contains("filename=\"Hamlet.txt\""), "{form}");
+}
+
+#[tokio::test]
+async fn test_error_context() {
+    use crate::testing::{Fault, Route};
+
+    let (api, server) = crate::testing::mock_api().await;
+    let ident = api
+        .upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+        .await
+        .unwrap();
+
+    server.inject(Route::DocumentStatus, Fault::status(400));
+    let err = api.check_document_status(&ident).await.unwrap_err();
+    assert_eq!(err.endpoint(), Some("check_document_status"));
+    let context = err.context().unwrap();
+    assert_eq!(context.id.as_deref(), Some(ident.document_id.as_str()));
+
+    let message = err.to_string();
+    assert!(message.contains("check_document_status"), "{message}");
+    assert!(message.contains(&ident.document_id), "{message}");
+    assert!(!message.contains(&ident.document_key), "{message}");
+    assert!(
+        !message.contains(crate::testing::FAKE_AUTH_KEY),
+        "{message}"
+    );
+}
+
+#[cfg(all(feature = "tokio-fs", unix))]
+#[tokio::test]
+async fn test_file_errors() {
+    use std::error::Error as _;
+    use std::os::unix::fs::PermissionsExt;
+
+    let (api, _server) = crate::testing::mock_api().await;
+    let dir = std::env::temp_dir().join(format!("deepl-file-errors-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let missing = dir.join("missing.txt");
+    let err = api.upload_document(&missing, Lang::DE).await.unwrap_err();
+    assert!(matches!(&err, Error::ReadFileError { path, .. } if path == &missing));
+    assert_eq!(err.io_kind(), Some(std::io::ErrorKind::NotFound));
+    assert!(err.source().unwrap().is::<std::io::Error>());
+
+    let ident = api
+        .upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+        .await
+        .unwrap();
+    let output = dir.join("missing").join("out.txt");
+    let err = api.download_document(&ident, &output).await.unwrap_err();
+    assert!(matches!(&err, Error::WriteFileError { path, .. } if path == &output));
+    assert_eq!(err.io_kind(), Some(std::io::ErrorKind::NotFound));
+
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o555)).unwrap();
+    let result = api.download_document(&ident, dir.join("out.txt")).await;
+    std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    // root ignores the permission bits
+    if let Err(err) = result {
+        assert_eq!(err.io_kind(), Some(std::io::ErrorKind::PermissionDenied));
+        assert_eq!(err.kind(), crate::ErrorKind::Io);
+    }
+}
+
+#[tokio::test]
+async fn test_upload_extra_param() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    api.upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+        .formality(Formality::Less)
+        .extra_param("enable_beta", serde_json::json!(true))
+        .extra_param("note", serde_json::json!("draft"))
+        .extra_param("target_lang", serde_json::json!("FR"))
+        .extra_param("skipped", serde_json::Value::Null)
+        .await
+        .unwrap();
+
+    let form = String::from_utf8_lossy(server.requests()[0].body()).into_owned();
+    for (name, value) in [
+        ("target_lang", "DE"),
+        ("formality", "less"),
+        ("enable_beta", "true"),
+        ("note", "draft"),
+    ] {
+        assert!(form.contains(&format!("name=\"{name}\"\r\n\r\n{value}\r\n")));
+    }
+    assert!(!form.contains("FR") && !form.contains("skipped"));
+}
+
+#[tokio::test]
+async fn test_upload_reader() {
+    use tokio::io::AsyncWriteExt;
+
+    let server = crate::testing::FakeServer::start().await;
+    let api = server.api();
+
+    let (mut writer, reader) = tokio::io::duplex(64);
+    let content: Vec<u8> = (0..1000)
+        .flat_map(|n| format!("{n} ").into_bytes())
+        .collect();
+    let written = content.clone();
+    tokio::spawn(async move { writer.write_all(&written).await.unwrap() });
+
+    let mut requester = api.upload_document_from_reader(reader, "streamed.txt", Lang::DE);
+    requester.formality(Formality::Less);
+    let resp = (&requester).await.unwrap();
+    let body = server.requests()[0].body().to_vec();
+    assert!(body.windows(content.len()).any(|window| window == content));
+    assert!(String::from_utf8_lossy(&body).contains("filename=\"streamed.txt\""));
+    let translated = api.download_document_bytes(&resp).await.unwrap();
+    assert_eq!(translated, [b"[DE] ".as_slice(), &content].concat());
+
+    // the reader is gone after the first upload
+    let err = requester.await.unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(msg) if msg.contains("streamed.txt")));
+
+    struct Broken;
+    impl AsyncRead for Broken {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+            _: &mut tokio::io::ReadBuf<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            let err = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "bucket closed");
+            std::task::Poll::Ready(Err(err))
+        }
+    }
+    let reader = tokio::io::AsyncReadExt::chain(&b"Hello "[..], Broken);
+    let err = api
+        .upload_document_from_reader(reader, "broken.txt", Lang::DE)
+        .content_length(100)
+        .await
+        .unwrap_err();
+    assert!(matches!(&err, Error::ReadFileError { path, .. } if path.ends_with("broken.txt")));
+    assert_eq!(err.io_kind(), Some(std::io::ErrorKind::ConnectionReset));
+    server.assert_request_count(2);
+}
+
+#[cfg(feature = "tokio-fs")]
+#[tokio::test]
+async fn test_download_bytes() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    let content: Vec<u8> = (0..=255).cycle().take(100_000).collect();
+    let ident = api
+        .upload_document_from_bytes(content.clone(), "payload.txt", Lang::DE)
+        .await
+        .unwrap();
+    let output = std::env::temp_dir().join(format!("deepl-download-{}.txt", std::process::id()));
+    api.download_document(&ident, &output).await.unwrap();
+    let from_file = std::fs::read(&output).unwrap();
+    std::fs::remove_file(&output).unwrap();
+
+    let in_memory = api.download_document_bytes(&ident).await.unwrap();
+    assert_eq!(in_memory, from_file);
+    assert_eq!(in_memory, [b"[DE] ".as_slice(), &content].concat());
+    let limit = in_memory.len() as u64;
+    let limited = api
+        .download_document_bytes_limited(&ident, limit)
+        .await
+        .unwrap();
+    assert_eq!(limited, in_memory);
+
+    let err = api
+        .download_document_bytes_limited(&ident, limit - 1)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::DocumentTooLarge { limit: max } if max == limit - 1));
+    assert_eq!(err.kind(), crate::ErrorKind::Decode);
+
+    let unknown = UploadDocumentResp {
+        document_id: "unknown".to_string(),
+        document_key: ident.document_key.clone(),
+    };
+    let err = api
+        .download_document_bytes_limited(&unknown, limit)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::NonExistDocument));
+    server.assert_request_count(6);
+}
+
+#[tokio::test]
+async fn test_download_to_writer() {
+    let (api, _server) = crate::testing::mock_api().await;
+
+    let content: Vec<u8> = (0..=255).cycle().take(50_000).collect();
+    let ident = api
+        .upload_document_from_bytes(content, "payload.txt", Lang::DE)
+        .await
+        .unwrap();
+    let expected = api.download_document_bytes(&ident).await.unwrap();
+
+    let mut writer = tokio::io::BufWriter::new(Vec::new());
+    let written = api.download_document_to(&ident, &mut writer).await.unwrap();
+    assert_eq!(written, expected.len() as u64);
+    assert_eq!(writer.get_ref(), &expected);
+
+    // a pipe whose reading end is gone
+    let (mut sink, reader) = tokio::io::duplex(16);
+    drop(reader);
+    let err = api
+        .download_document_to(&ident, &mut sink)
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&err, Error::WriteError { document_id, written: 0, .. } if *document_id == ident.document_id)
+    );
+    assert_eq!(err.io_kind(), Some(std::io::ErrorKind::BrokenPipe));
+    assert_eq!(err.kind(), crate::ErrorKind::Io);
+}
+
+#[tokio::test]
+async fn test_output_format() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    let mut requester = api.upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE);
+    (&requester).await.unwrap();
+    requester.output_format(DocumentOutputFormat::Docx);
+    (&requester).await.unwrap();
+    requester.output_format(DocumentOutputFormat::Other("odt".to_string()));
+    (&requester).await.unwrap();
+
+    let forms: Vec<String> = server
+        .requests()
+        .iter()
+        .map(|request| String::from_utf8_lossy(request.body()).into_owned())
+        .collect();
+    assert!(!forms[0].contains("output_format"));
+    assert!(forms[1].contains("name=\"output_format\"\r\n\r\ndocx\r\n"));
+    assert!(forms[2].contains("name=\"output_format\"\r\n\r\nodt\r\n"));
+
+    for extension in ["", ".pdf", "my format"] {
+        let err = requester
+            .output_format(DocumentOutputFormat::Other(extension.to_string()))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(_)), "{extension}");
+    }
+    server.assert_request_count(3);
 }
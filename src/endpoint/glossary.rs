@@ -1,18 +1,54 @@
 use crate::{
-    endpoint::{Error, Result, REPO_URL},
-    DeepLApi, Lang,
+    endpoint::{DeepLApiError, Error, RequestOptions, Result, REPO_URL},
+    DeepLApi, Lang, RawResponse,
 };
 use core::future::IntoFuture;
-use std::borrow::Borrow;
+use std::borrow::{Borrow, Cow};
 use std::collections::HashMap;
 use typed_builder::TypedBuilder;
 
-use super::Pollable;
+use super::ResponseFuture;
 
 #[derive(Debug, TypedBuilder)]
-#[builder(build_method(name = send))]
+#[builder(
+    build_method(name = send),
+    mutators(
+        /// Set a header on this request only, replacing a header of the same name set by the
+        /// client. An invalid header or `Authorization` fails the request with
+        /// [`Error::InvalidConfig`].
+        pub fn header(&mut self, name: &str, value: &str) {
+            self.options.insert_header(name, value);
+        }
+
+        /// Fail the request with [`Error::DeadlineExceeded`] if it takes longer than `timeout`
+        pub fn timeout(&mut self, timeout: std::time::Duration) {
+            self.options.set_timeout(timeout);
+        }
+
+        /// Fail the request with [`Error::DeadlineExceeded`] if it isn't done at `deadline`
+        pub fn deadline(&mut self, deadline: std::time::Instant) {
+            self.options.set_deadline(deadline);
+        }
+
+        /// Abort the request with [`Error::Cancelled`] once `token` is cancelled
+        pub fn cancellation(&mut self, token: tokio_util::sync::CancellationToken) {
+            self.options.set_cancellation(token);
+        }
+
+        /// Retry with `config` instead of the policy of the client once enabled by
+        /// `retry(true)`, see [`DeepLApi::with_retry`]
+        pub fn retry_policy(&mut self, config: crate::RetryConfig) {
+            self.options.set_retry(Some(config));
+        }
+
+        /// Never retry this request, whatever the policy of the client
+        pub fn no_retry(&mut self) {
+            self.options.set_retry(None);
+        }
+    )
+)]
 pub struct CreateGlossary<'a> {
-    client: &'a DeepLApi,
+    client: Cow<'a, DeepLApi>,
 
     name: String,
 
@@ -24,10 +60,19 @@ pub struct CreateGlossary<'a> {
 
     #[builder(default = EntriesFormat::TSV)]
     format: EntriesFormat,
+
+    /// Retry the creation on transient failures, see
+    /// [`DeepLApiBuilder::retry`](crate::DeepLApiBuilder::retry). Disabled by default, as a
+    /// glossary may be created twice.
+    #[builder(default = false)]
+    retry: bool,
+
+    #[builder(via_mutators)]
+    options: RequestOptions,
 }
 
-#[allow(non_camel_case_types)]
-impl<'a, _c, _n, _s, _t, _f> CreateGlossaryBuilder<'a, (_c, _n, _s, _t, (), _f)> {
+#[allow(non_camel_case_types, clippy::type_complexity)]
+impl<'a, _c, _n, _s, _t, _f, _r, _h> CreateGlossaryBuilder<'a, (_c, _n, _s, _t, (), _f, _r, _h)> {
     /// The entries of the glossary.
     ///
     /// Entries parameter should be able to yield type (String, String).
@@ -51,7 +96,7 @@ impl<'a, _c, _n, _s, _t, _f> CreateGlossaryBuilder<'a, (_c, _n, _s, _t, (), _f)>
     pub fn entries<S, T, B, I>(
         self,
         iter: I,
-    ) -> CreateGlossaryBuilder<'a, (_c, _n, _s, _t, (Vec<(String, String)>,), _f)>
+    ) -> CreateGlossaryBuilder<'a, (_c, _n, _s, _t, (Vec<(String, String)>,), _f, _r, _h)>
     where
         S: ToString,
         T: ToString,
@@ -62,64 +107,108 @@ impl<'a, _c, _n, _s, _t, _f> CreateGlossaryBuilder<'a, (_c, _n, _s, _t, (), _f)>
             .into_iter()
             .map(|t| (t.borrow().0.to_string(), t.borrow().1.to_string()))
             .collect();
-        let (client, name, source_lang, target_lang, (), format) = self.fields;
+        let (client, name, source_lang, target_lang, (), format, retry, options) = self.fields;
         CreateGlossaryBuilder {
-            fields: (client, name, source_lang, target_lang, (entries,), format),
+            fields: (
+                client,
+                name,
+                source_lang,
+                target_lang,
+                (entries,),
+                format,
+                retry,
+                options,
+            ),
             phantom: self.phantom,
         }
     }
 }
 
-type CreateGlossaryBuilderStart<'a> =
-    CreateGlossaryBuilder<'a, ((&'a DeepLApi,), (String,), (), (), (), ())>;
+type CreateGlossaryBuilderStart<'a> = CreateGlossaryBuilder<
+    'a,
+    (
+        (Cow<'a, DeepLApi>,),
+        (String,),
+        (),
+        (),
+        (),
+        (),
+        (),
+        (RequestOptions,),
+    ),
+>;
+
+impl CreateGlossary<'_> {
+    /// Hold a clone of the client instead of borrowing it, so the request and its future are
+    /// `'static`, e.g. to await it in `tokio::spawn`
+    pub fn into_owned(self) -> CreateGlossary<'static> {
+        CreateGlossary {
+            client: Cow::Owned(self.client.into_owned()),
+            name: self.name,
+            source_lang: self.source_lang,
+            target_lang: self.target_lang,
+            entries: self.entries,
+            format: self.format,
+            retry: self.retry,
+            options: self.options,
+        }
+    }
+}
 
 impl<'a> IntoFuture for CreateGlossary<'a> {
     type Output = Result<GlossaryResp>;
-    type IntoFuture = Pollable<'a, Self::Output>;
+    type IntoFuture = ResponseFuture<'a, GlossaryResp>;
 
     fn into_future(self) -> Self::IntoFuture {
-        let client = self.client.clone();
+        let client = self.options.apply(&self.client);
+        let retry = self.retry;
         let fields = CreateGlossaryRequestParam::from(self);
         let fut = async move {
-            let resp = client
-                .post(client.get_endpoint("glossaries"))
-                .json(&fields)
-                .send()
+            let client = client?;
+            client
+                .call("create_glossary", None, async {
+                    let resp = client
+                        .post_json(client.get_endpoint("glossaries")?, &fields, retry)
+                        .await?;
+                    if !resp.status().is_success() {
+                        return super::extract_deepl_error(resp).await;
+                    }
+                    let resp = resp
+                        .json::<GlossaryPossibleResps>()
+                        .await
+                        .unwrap_or_else(|_| {
+                            panic!(
+                        "Unmatched response to CreateGlossaryResp, please open issue on {REPO_URL}."
+                    )
+                        });
+
+                    match resp {
+                        GlossaryPossibleResps::Fail { message } => Err(Error::InvalidResponse(
+                            format!("Fail to create request to glossary API: {message}"),
+                        )),
+                        GlossaryPossibleResps::Success {
+                            glossary_id,
+                            name,
+                            ready,
+                            source_lang,
+                            target_lang,
+                            creation_time,
+                            entry_count,
+                        } => Ok(GlossaryResp {
+                            glossary_id,
+                            name,
+                            ready,
+                            source_lang,
+                            target_lang,
+                            creation_time,
+                            entry_count,
+                        }),
+                    }
+                })
                 .await
-                .map_err(|err| Error::RequestFail(err.to_string()))?
-                .json::<GlossaryPossibleResps>()
-                .await
-                .unwrap_or_else(|_| {
-                    panic!(
-                    "Unmatched response to CreateGlossaryResp, please open issue on {REPO_URL}."
-                )
-                });
-
-            match resp {
-                GlossaryPossibleResps::Fail { message } => Err(Error::RequestFail(format!(
-                    "Fail to create request to glossary API: {message}"
-                ))),
-                GlossaryPossibleResps::Success {
-                    glossary_id,
-                    name,
-                    ready,
-                    source_lang,
-                    target_lang,
-                    creation_time,
-                    entry_count,
-                } => Ok(GlossaryResp {
-                    glossary_id,
-                    name,
-                    ready,
-                    source_lang,
-                    target_lang,
-                    creation_time,
-                    entry_count,
-                }),
-            }
         };
 
-        Box::pin(fut)
+        ResponseFuture::new(Box::pin(fut))
     }
 }
 
@@ -140,7 +229,7 @@ enum GlossaryPossibleResps {
     },
 }
 
-#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct GlossaryResp {
     /// A unique ID assigned to a glossary.
     pub glossary_id: String,
@@ -160,6 +249,27 @@ pub struct GlossaryResp {
     pub entry_count: u64,
 }
 
+impl GlossaryResp {
+    /// Check that the glossary can be used to translate into `target_lang`, regional variants
+    /// like `EN-US` match a glossary of `EN`
+    pub(crate) fn check_target(&self, target_lang: &Lang) -> std::result::Result<(), String> {
+        let base = |lang: &Lang| {
+            lang.as_ref()
+                .split('-')
+                .next()
+                .unwrap_or_default()
+                .to_string()
+        };
+        if base(&self.target_lang).eq_ignore_ascii_case(&base(target_lang)) {
+            return Ok(());
+        }
+        Err(format!(
+            "glossary {} translates to {}, not {target_lang}",
+            self.glossary_id, self.target_lang
+        ))
+    }
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 struct CreateGlossaryRequestParam {
     name: String,
@@ -194,22 +304,61 @@ impl<'a> From<CreateGlossary<'a>> for CreateGlossaryRequestParam {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum EntriesFormat {
     TSV,
     CSV,
 }
 
-impl ToString for EntriesFormat {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for EntriesFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            EntriesFormat::TSV => "tsv".to_string(),
-            EntriesFormat::CSV => "csv".to_string(),
+            EntriesFormat::TSV => write!(f, "tsv"),
+            EntriesFormat::CSV => write!(f, "csv"),
         }
     }
 }
 
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+/// Known reasons of a failed glossary creation, see [`Error::glossary_create_error`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GlossaryCreateError {
+    /// The language pair is not in [`DeepLApi::list_glossary_language_pairs`]
+    UnsupportedLanguagePair,
+    /// The entries are empty or can't be parsed in the given format
+    InvalidEntries,
+    /// A source term appears more than once
+    DuplicateSource,
+    /// Any other message of DeepL
+    Other(String),
+}
+
+/// Known messages of a failed glossary creation, the first one found in the message or
+/// detail of the error wins
+const GLOSSARY_CREATE_ERRORS: &[(&str, GlossaryCreateError)] = &[
+    ("duplicates key", GlossaryCreateError::DuplicateSource),
+    (
+        "Unsupported glossary source and target language pair",
+        GlossaryCreateError::UnsupportedLanguagePair,
+    ),
+    (
+        "Invalid glossary entries",
+        GlossaryCreateError::InvalidEntries,
+    ),
+    ("'entries'", GlossaryCreateError::InvalidEntries),
+];
+
+impl GlossaryCreateError {
+    pub(crate) fn classify(error: &DeepLApiError) -> Self {
+        let detail = error.detail.as_deref().unwrap_or_default();
+        GLOSSARY_CREATE_ERRORS
+            .iter()
+            .find(|(pattern, _)| error.message.contains(pattern) || detail.contains(pattern))
+            .map(|(_, reason)| reason.clone())
+            .unwrap_or_else(|| Self::Other(error.message.clone()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct GlossaryLanguagePair {
     pub source_lang: Lang,
     pub target_lang: Lang,
@@ -238,69 +387,102 @@ impl DeepLApi {
     ///     .await
     ///     .unwrap();
     /// ```
-    pub fn create_glossary(&self, name: impl ToString) -> CreateGlossaryBuilderStart {
+    pub fn create_glossary(&self, name: impl ToString) -> CreateGlossaryBuilderStart<'_> {
         CreateGlossary::builder()
-            .client(self)
+            .client(Cow::Borrowed(self))
             .name(name.to_string())
     }
 
     /// List all glossaries and their meta-information, but not the glossary entries.
     pub async fn list_all_glossaries(&self) -> Result<Vec<GlossaryResp>> {
-        Ok(
-            self.get(self.get_endpoint("glossaries"))
-                .send()
-                .await
-                .map_err(|e| Error::RequestFail(e.to_string()))?
-                .json::<HashMap<String, Vec<GlossaryResp>>>()
-                .await
-                .map_err(|err| Error::RequestFail(format!("Unexpected error when requesting list_all_glossaries, please open issue on {REPO_URL}: {err}")))?
+        self.call("list_all_glossaries", None, async {
+            let resp = self
+                .execute_retry(self.get(self.get_endpoint("glossaries")?))
+                .await?;
+            if !resp.status().is_success() {
+                return super::extract_deepl_error(resp).await;
+            }
+            resp.json::<HashMap<String, Vec<GlossaryResp>>>()
+                .await?
                 .remove("glossaries")
-                .ok_or(Error::RequestFail(format!("Unable to find key glossaries in response, please open issue on {REPO_URL}")))?
-        )
+                .ok_or(Error::InvalidResponse(format!(
+                    "Unable to find key glossaries in response, please open issue on {REPO_URL}"
+                )))
+        })
+        .await
     }
 
     /// Retrieve meta information for a single glossary, omitting the glossary entries.
     /// Require a unique ID assigned to the glossary.
     pub async fn retrieve_glossary_details(&self, id: impl ToString) -> Result<GlossaryResp> {
-        match self
-            .get(self.get_endpoint(&format!("glossaries/{}", id.to_string())))
-            .send()
-            .await
-            .map_err(|e| Error::RequestFail(e.to_string()))?
-            .json::<GlossaryPossibleResps>()
+        let id = id.to_string();
+        self.call("retrieve_glossary_details", None, async {
+            let resp = self.glossary_details_request(&id).await?;
+            if !resp.status().is_success() {
+                return glossary_error(&id, resp).await;
+            }
+            match resp.json::<GlossaryPossibleResps>().await.expect("") {
+                GlossaryPossibleResps::Fail { message } => Err(Error::InvalidResponse(format!(
+                    "fail to send request to glossary API: {message}"
+                ))),
+                GlossaryPossibleResps::Success {
+                    glossary_id,
+                    name,
+                    ready,
+                    source_lang,
+                    target_lang,
+                    creation_time,
+                    entry_count,
+                } => Ok(GlossaryResp {
+                    glossary_id,
+                    name,
+                    ready,
+                    source_lang,
+                    target_lang,
+                    creation_time,
+                    entry_count,
+                }),
+            }
+        })
+        .await
+        .map_err(|err| err.with_id(&id))
+    }
+
+    /// Like [`retrieve_glossary_details`](Self::retrieve_glossary_details), but return the
+    /// response without decoding it, see [`RawResponse`].
+    pub async fn retrieve_glossary_details_raw(&self, id: impl ToString) -> Result<RawResponse> {
+        self.call("retrieve_glossary_details", None, async {
+            self.glossary_details_request(&id.to_string())
+                .await
+                .map(RawResponse::new)
+        })
+        .await
+    }
+
+    async fn glossary_details_request(&self, id: &str) -> Result<reqwest::Response> {
+        self.execute_retry(self.get(self.get_resource_endpoint("glossaries", id, &[])?))
             .await
-            .expect("")
-        {
-            GlossaryPossibleResps::Fail { message } => Err(Error::RequestFail(format!(
-                "fail to send request to glossary API: {message}"
-            ))),
-            GlossaryPossibleResps::Success {
-                glossary_id,
-                name,
-                ready,
-                source_lang,
-                target_lang,
-                creation_time,
-                entry_count,
-            } => Ok(GlossaryResp {
-                glossary_id,
-                name,
-                ready,
-                source_lang,
-                target_lang,
-                creation_time,
-                entry_count,
-            }),
-        }
     }
 
     /// Deletes the specified glossary.
+    ///
+    /// # Error
+    ///
+    /// Return [`Error::GlossaryNotFound`] if there is no glossary with the ID, e.g. because it
+    /// was already deleted.
     pub async fn delete_glossary(&self, id: impl ToString) -> Result<()> {
-        self.del(self.get_endpoint(&format!("glossaries/{}", id.to_string())))
-            .send()
-            .await
-            .map_err(|e| Error::RequestFail(e.to_string()))
-            .map(|_| ())
+        let id = id.to_string();
+        self.call("delete_glossary", None, async {
+            let resp = self
+                .execute(self.del(self.get_resource_endpoint("glossaries", &id, &[])?))
+                .await?;
+            if !resp.status().is_success() {
+                return glossary_error(&id, resp).await;
+            }
+            Ok(())
+        })
+        .await
+        .map_err(|err| err.with_id(&id))
     }
 
     /// List the entries of a single glossary in the format specified by the Accept header.
@@ -309,57 +491,76 @@ impl DeepLApi {
         &self,
         id: impl ToString,
     ) -> Result<Vec<(String, String)>> {
-        Ok(self
-            .get(self.get_endpoint(&format!("glossaries/{}/entries", id.to_string())))
-            .header("Accept", "text/tab-separated-values")
-            .send()
-            .await
-            .map_err(|e| Error::RequestFail(e.to_string()))?
-            .text()
-            .await
-            .map(|resp| {
-                resp.split("\n")
-                    .map(|line| {
-                        let mut pair = line.split("\t");
-                        (
-                            pair.next().unwrap().to_string(),
-                            pair.next().unwrap().to_string(),
-                        )
-                    })
-                    .collect()
-            })
-            .map_err(|err| {
-                Error::RequestFail(format!("fail to retrieve glossary entries: {err}"))
-            }))?
+        let id = id.to_string();
+        self.call("retrieve_glossary_entries", None, async {
+            let resp = self
+                .execute_retry(
+                    self.get(self.get_resource_endpoint("glossaries", &id, &["entries"])?)
+                        .header("Accept", "text/tab-separated-values"),
+                )
+                .await?;
+            if !resp.status().is_success() {
+                return glossary_error(&id, resp).await;
+            }
+            parse_tsv_entries(&resp.text().await?)
+        })
+        .await
+        .map_err(|err| err.with_id(&id))
     }
 
     /// Retrieve the list of language pairs supported by the glossary feature.
     pub async fn list_glossary_language_pairs(&self) -> Result<Vec<GlossaryLanguagePair>> {
-        let pair = self
-            .get(self.get_endpoint("glossary-language-pairs"))
-            .send()
-            .await
-            .map_err(|e| Error::RequestFail(e.to_string()))?
-            .json::<HashMap<String, Vec<GlossaryLanguagePair>>>()
-            .await
-            .map_err(|err| {
-                Error::RequestFail(format!("fail to list glossary language pairs: {err}"))
-            })?
-            .remove("supported_languages")
-            .ok_or(Error::RequestFail(format!(
-                "Fail to get supported languages from glossary language pairs"
-            )))?;
-
-        Ok(pair)
+        self.call("list_glossary_language_pairs", None, async {
+            let resp = self
+                .execute_retry(self.get(self.get_endpoint("glossary-language-pairs")?))
+                .await?;
+            if !resp.status().is_success() {
+                return super::extract_deepl_error(resp).await;
+            }
+            let pair = resp
+                .json::<HashMap<String, Vec<GlossaryLanguagePair>>>()
+                .await?
+                .remove("supported_languages")
+                .ok_or(Error::InvalidResponse(
+                    "Fail to get supported languages from glossary language pairs".to_string(),
+                ))?;
+
+            Ok(pair)
+        })
+        .await
     }
 }
 
+/// Parse glossary entries in TSV, one `source\ttarget` pair per line
+fn parse_tsv_entries(tsv: &str) -> Result<Vec<(String, String)>> {
+    tsv.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(idx, line)| match line.split_once('\t') {
+            Some((source, target)) if !target.contains('\t') => {
+                Ok((source.to_string(), target.to_string()))
+            }
+            _ => Err(Error::InvalidResponse(format!(
+                "malformed glossary entry on line {}: {line:?}",
+                idx + 1
+            ))),
+        })
+        .collect()
+}
+
+/// Turn an error response about a single glossary into [`Error`]
+async fn glossary_error<T>(id: &str, resp: reqwest::Response) -> Result<T> {
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(Error::GlossaryNotFound(id.to_string()));
+    }
+    super::extract_deepl_error(resp).await
+}
+
 #[tokio::test]
 async fn test_glossary_api() {
-    use crate::{glossary::EntriesFormat, DeepLApi, Lang};
+    use crate::{glossary::EntriesFormat, Lang};
 
-    let key = std::env::var("DEEPL_API_KEY").unwrap();
-    let deepl = DeepLApi::with(&key).new();
+    let (deepl, server) = crate::testing::mock_api().await;
 
     assert_ne!(deepl.list_glossary_language_pairs().await.unwrap().len(), 0);
 
@@ -395,5 +596,124 @@ async fn test_glossary_api() {
     assert_eq!(entries["Hello"], "Guten Tag");
     assert_eq!(entries["Bye"], "Auf Wiedersehen");
 
-    deepl.delete_glossary(resp.glossary_id).await.unwrap();
+    deepl.delete_glossary(&resp.glossary_id).await.unwrap();
+    assert!(deepl.list_all_glossaries().await.unwrap().is_empty());
+    let deleted = deepl.delete_glossary(&resp.glossary_id).await;
+    assert!(matches!(deleted, Err(Error::GlossaryNotFound(id)) if id == resp.glossary_id));
+    server.assert_request_count(8);
+}
+
+#[tokio::test]
+async fn test_delete_glossary_status() {
+    use crate::testing::{Fault, Route};
+
+    let (deepl, server) = crate::testing::mock_api().await;
+    let id = "00000000-0000-0000-0000-000000000001";
+
+    server.inject(Route::Glossaries, Fault::status(204).body("").times(1));
+    deepl.delete_glossary(id).await.unwrap();
+
+    assert!(matches!(
+        deepl.delete_glossary(id).await,
+        Err(Error::GlossaryNotFound(_))
+    ));
+    assert!(matches!(
+        deepl.retrieve_glossary_entries(id).await,
+        Err(Error::GlossaryNotFound(_))
+    ));
+
+    server.inject(Route::Glossaries, Fault::status(403));
+    assert!(matches!(
+        deepl.delete_glossary(id).await,
+        Err(Error::AuthorizationFailed(_))
+    ));
+}
+
+#[test]
+fn test_parse_tsv_entries() {
+    let entries = |pairs: &[(&str, &str)]| {
+        pairs
+            .iter()
+            .map(|(s, t)| (s.to_string(), t.to_string()))
+            .collect::<Vec<_>>()
+    };
+
+    let expected = entries(&[("Hello", "Hallo"), ("Bye", "Tschüss")]);
+    assert_eq!(
+        parse_tsv_entries("Hello\tHallo\nBye\tTschüss").unwrap(),
+        expected
+    );
+    assert_eq!(
+        parse_tsv_entries("Hello\tHallo\nBye\tTschüss\n").unwrap(),
+        expected
+    );
+    assert_eq!(
+        parse_tsv_entries("Hello\tHallo\r\nBye\tTschüss\r\n").unwrap(),
+        expected
+    );
+    assert!(parse_tsv_entries("").unwrap().is_empty());
+    assert!(parse_tsv_entries("\n").unwrap().is_empty());
+
+    let err = parse_tsv_entries("Hello\tHallo\njunk\n").unwrap_err();
+    assert!(matches!(err, Error::InvalidResponse(ref msg) if msg.contains("line 2")));
+    let err = parse_tsv_entries("Hello\tHallo\tHi").unwrap_err();
+    assert!(matches!(err, Error::InvalidResponse(ref msg) if msg.contains("line 1")));
+}
+
+#[test]
+fn test_classify_glossary_create_error() {
+    use GlossaryCreateError::*;
+
+    let samples = [
+        (
+            r#"{"message":"Unsupported glossary source and target language pair"}"#,
+            UnsupportedLanguagePair,
+        ),
+        (
+            r#"{"message":"Invalid glossary entries provided","detail":"Key with the index 1 (starting at position 13) duplicates key with the index 0 (starting at position 0)"}"#,
+            DuplicateSource,
+        ),
+        (
+            r#"{"message":"Invalid glossary entries provided","detail":"Missing tab separator in line 1"}"#,
+            InvalidEntries,
+        ),
+        (
+            r#"{"message":"Parameter 'entries' not specified."}"#,
+            InvalidEntries,
+        ),
+        (
+            r#"{"message":"Value for 'name' not supported."}"#,
+            Other("Value for 'name' not supported.".to_string()),
+        ),
+    ];
+    for (body, expected) in samples {
+        let error: DeepLApiError = serde_json::from_str(body).unwrap();
+        assert_eq!(GlossaryCreateError::classify(&error), expected, "{body}");
+    }
+}
+
+#[tokio::test]
+async fn test_glossary_create_error() {
+    use crate::testing::{Fault, Route};
+
+    let (deepl, server) = crate::testing::mock_api().await;
+    let body = r#"{"message":"Unsupported glossary source and target language pair"}"#;
+    server.inject(Route::Glossaries, Fault::status(400).body(body));
+
+    let err = deepl
+        .create_glossary("My Glossary")
+        .source_lang(Lang::EN)
+        .target_lang(Lang::JA)
+        .entries([("Hello", "こんにちは")])
+        .send()
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.glossary_create_error(),
+        Some(GlossaryCreateError::UnsupportedLanguagePair)
+    );
+
+    // only for glossary creation
+    let err = deepl.list_all_glossaries().await.unwrap_err();
+    assert_eq!(err.glossary_create_error(), None);
 }
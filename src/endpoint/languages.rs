@@ -1,9 +1,9 @@
-use super::{Error, Result};
-use crate::DeepLApi;
-use serde::Deserialize;
+use super::{Error, Formality, Result};
+use crate::{DeepLApi, Lang};
+use serde::{Deserialize, Serialize};
 
 /// Information about a supported language
-#[derive(Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct LangInfo {
     /// Language code
     pub language: String,
@@ -46,37 +46,77 @@ impl DeepLApi {
     /// println!("{}", lang.name); // Bulgarian
     /// ```
     pub async fn languages(&self, lang_type: LangType) -> Result<Vec<LangInfo>> {
-        let q = vec![("type", lang_type.as_ref())];
+        self.call("languages", None, async {
+            let q = vec![("type", lang_type.as_ref())];
 
-        let resp = self
-            .get(self.get_endpoint("languages"))
-            .query(&q)
-            .send()
-            .await
-            .map_err(|err| Error::RequestFail(err.to_string()))?;
+            let resp = self
+                .execute_retry(self.get(self.get_endpoint("languages")?).query(&q))
+                .await?;
 
-        if !resp.status().is_success() {
-            return super::extract_deepl_error(resp).await;
-        }
+            if !resp.status().is_success() {
+                return super::extract_deepl_error(resp).await;
+            }
 
-        resp.json().await.map_err(|err| {
-            Error::InvalidResponse(format!("convert json bytes to Rust type: {err}"))
+            resp.json().await.map_err(|err| {
+                Error::InvalidResponse(format!("convert json bytes to Rust type: {err}"))
+            })
         })
+        .await
+    }
+
+    /// Whether the target language supports formality, `None` if DeepL doesn't tell or the
+    /// language is unknown. The target languages are fetched once per client.
+    pub(crate) async fn supports_formality(&self, target_lang: &Lang) -> Result<Option<bool>> {
+        let langs = self
+            .inner
+            .target_languages
+            .get_or_try_init(|| self.languages(LangType::Target))
+            .await?;
+        Ok(langs
+            .iter()
+            .find(|info| info.language.eq_ignore_ascii_case(target_lang.as_ref()))
+            .and_then(|info| info.supports_formality))
+    }
+
+    /// Fail with [`Error::InvalidInput`] if `formality` is [`Formality::More`] or
+    /// [`Formality::Less`] and the target language doesn't support formality
+    pub(crate) async fn check_formality(
+        &self,
+        target_lang: &Lang,
+        formality: Option<&Formality>,
+    ) -> Result<()> {
+        if !matches!(formality, Some(Formality::More | Formality::Less)) {
+            return Ok(());
+        }
+        // unknown languages are left to DeepL
+        if self.supports_formality(target_lang).await? == Some(false) {
+            return Err(Error::InvalidInput(format!(
+                "{target_lang} doesn't support formality {}, use prefer_more or prefer_less \
+                 to fall back to the default",
+                formality.expect("checked above").as_ref()
+            )));
+        }
+        Ok(())
     }
 }
 
 #[tokio::test]
 async fn test_get_languages() {
-    let deepl = DeepLApi::with(&std::env::var("DEEPL_API_KEY").unwrap()).new();
+    let (deepl, server) = crate::testing::mock_api().await;
 
     let langs = deepl.languages(LangType::Target).await.unwrap();
     assert!(!langs.is_empty());
+    assert!(langs.iter().any(|l| l.supports_formality.is_some()));
+    server.assert_request_count(1);
 }
 
 #[tokio::test]
+#[ignore = "needs DEEPL_API_KEY"]
 async fn test_generate_langs() {
     use crate::Lang;
-    let deepl = DeepLApi::with(&std::env::var("DEEPL_API_KEY").unwrap()).new();
+    // checks the languages of the real API, the fake only knows a few
+    let key = std::env::var("DEEPL_API_KEY").unwrap();
+    let deepl = DeepLApi::with(&key).new();
 
     // fetch source langs
     let source_langs = deepl.languages(LangType::Source).await.unwrap();
@@ -1,6 +1,19 @@
+use crate::{
+    AttemptOutcome, Attempts, DeepLApi, EmptyTextPolicy, Lang, LangConvertError, RetryConfig,
+};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
 use serde::{Deserialize, Serialize};
-use std::{future::Future, pin::Pin};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
 use thiserror::Error;
+use tokio_util::sync::CancellationToken;
 
 pub mod document;
 pub mod glossary;
@@ -9,37 +22,871 @@ pub mod translate;
 pub mod usage;
 
 /// Representing error during interaction with DeepL
+///
+/// New variants may be added in minor releases, match on [`Error::kind`] for a stable
+/// classification.
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum Error {
     #[error("invalid response: {0}")]
     InvalidResponse(String),
 
+    /// The HTTP client failed after connecting, e.g. the connection was lost
     #[error("request fail: {0}")]
-    RequestFail(String),
+    Http(#[source] reqwest::Error),
 
-    #[error("fail to read file {0}: {1}")]
-    ReadFileError(String, tokio::io::Error),
+    /// DeepL couldn't be reached, e.g. a DNS failure, a refused connection or a failed TLS
+    /// handshake. The request wasn't sent.
+    #[error("connection failed: {0}")]
+    Connectivity(#[source] reqwest::Error),
+
+    /// A middleware of [`client_with_middleware`](crate::DeepLApiBuilder::client_with_middleware)
+    /// failed the request, the message has the context chain of its error
+    #[cfg(feature = "reqwest-middleware")]
+    #[error("middleware failed: {0:#}")]
+    Middleware(#[source] reqwest_middleware::Error),
+
+    /// The request or reading the response timed out, see
+    /// [`DeepLApiBuilder::timeout`](crate::DeepLApiBuilder::timeout). `elapsed` is the time
+    /// waited for the response, if known, `source` the error of the HTTP client, if any. DeepL
+    /// may have processed and billed the request.
+    #[error("request timed out{}", elapsed.map(|d| format!(" after {d:?}")).unwrap_or_default())]
+    Timeout {
+        elapsed: Option<Duration>,
+        #[source]
+        source: Option<reqwest::Error>,
+    },
+
+    /// DeepL answered with an error status. `error` is the decoded JSON body, `body` the raw
+    /// body otherwise, truncated to 1 KiB.
+    #[error("request fail: {}{}", api_error_detail(*.status, .error.as_ref(), .body.as_deref()), context_suffix(.context))]
+    Api {
+        status: reqwest::StatusCode,
+        error: Option<DeepLApiError>,
+        body: Option<String>,
+        context: Option<Box<ErrorContext>>,
+    },
+
+    #[error("fail to read file {}: {source}", path.display())]
+    ReadFileError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
 
     #[error(
         "trying to download a document using a non-existing document ID or the wrong document key"
     )]
     NonExistDocument,
 
+    #[error("no glossary with the ID {0}")]
+    GlossaryNotFound(String),
+
     #[error("tries to download a translated document that is currently being processed and is not yet ready for download")]
     TranslationNotDone,
 
-    #[error("fail to write file: {0}")]
-    WriteFileError(String),
+    /// The translated document is larger than the limit of
+    /// [`download_document_bytes_limited`](crate::DeepLApi::download_document_bytes_limited)
+    #[error("the translated document exceeds {limit} bytes")]
+    DocumentTooLarge { limit: u64 },
+
+    #[error("fail to write file {}: {source}", path.display())]
+    WriteFileError {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// Writing the document to the writer of
+    /// [`download_document_to`](crate::DeepLApi::download_document_to) failed after `written`
+    /// bytes
+    #[error("fail to write document {document_id} after {written} bytes: {source}")]
+    WriteError {
+        document_id: String,
+        written: u64,
+        source: std::io::Error,
+    },
+
+    #[error("invalid api key: {0}")]
+    InvalidApiKey(String),
+
+    #[error("invalid client configuration: {0}")]
+    InvalidConfig(String),
+
+    #[error("no text to translate")]
+    EmptyInput,
+
+    /// The input was rejected before sending, e.g. by
+    /// [`TranslateHtmlRequester::strict`](translate::TranslateHtmlRequester::strict)
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+
+    /// A language code passed as string is not supported, see [`IntoParam`]
+    #[error("{0}")]
+    InvalidLanguage(#[from] LangConvertError),
+
+    /// The request body exceeds the limit of DeepL, either detected before sending with the
+    /// size and limit in bytes, or by a 413 Payload Too Large response.
+    #[error("request too large{}{}", request_size_detail(*.size, *.limit), context_suffix(.context))]
+    RequestTooLarge {
+        size: Option<usize>,
+        limit: Option<usize>,
+        context: Option<Box<ErrorContext>>,
+    },
+
+    #[error("invalid endpoint: {0}")]
+    InvalidEndpoint(String),
+
+    #[error("insecure endpoint {0}: the auth key would be sent in cleartext, use HTTPS or allow_insecure_http")]
+    InsecureEndpoint(String),
+
+    /// 429 Too Many Requests or 503 Service Unavailable. `retry_after` is the delay requested
+    /// by the `Retry-After` header, if any.
+    #[error("request throttled: {status}{}{}", retry_after.map(|d| format!(", retry after {d:?}")).unwrap_or_default(), context_suffix(.context))]
+    TooManyRequests {
+        status: reqwest::StatusCode,
+        retry_after: Option<Duration>,
+        context: Option<Box<ErrorContext>>,
+    },
+
+    /// 5xx server error of DeepL, except 503 which is [`Error::TooManyRequests`]. The body is
+    /// ignored, DeepL sends HTML or nothing during incidents.
+    #[error("server error: {status}{}", context_suffix(.context))]
+    ServerError {
+        status: reqwest::StatusCode,
+        context: Option<Box<ErrorContext>>,
+    },
+
+    #[error("authorization failed: {0}")]
+    AuthorizationFailed(String),
+
+    #[error("quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    #[error("the quota of every api key is exceeded")]
+    KeysExhausted,
+
+    #[error("the request was cancelled")]
+    Cancelled,
+
+    #[error("the deadline of the request was exceeded")]
+    DeadlineExceeded,
+
+    /// A chunk of a translate request with more than 50 texts failed. `translated` holds the
+    /// translations of the chunks before it, in the order of the texts.
+    #[error("translating chunk {chunk} of the texts failed: {source}")]
+    ChunkFailed {
+        chunk: usize,
+        translated: Box<translate::TranslateTextResp>,
+        source: Box<Error>,
+    },
+
+    /// A batch of [`DeepLApi::translate_many`](crate::DeepLApi::translate_many) failed,
+    /// `range` holds the indices of its texts. The source is shared by every text of the batch
+    /// with [`continue_on_error`](translate::TranslateManyRequester::continue_on_error).
+    #[error("translating texts {}..{} failed: {source}", range.start, range.end)]
+    BatchFailed {
+        range: std::ops::Range<usize>,
+        source: std::sync::Arc<Error>,
+    },
+
+    #[error("request failed after {} attempts: {source}", attempts.len())]
+    RetriesExhausted {
+        source: Box<Error>,
+        attempts: Box<Attempts>,
+    },
+}
+
+impl Error {
+    /// History of the attempts of the failed request, see
+    /// [`DeepLApiBuilder::retry`](crate::DeepLApiBuilder::retry). A request that wasn't
+    /// retried made a single attempt, whose elapsed time isn't recorded. `None` if no request
+    /// was sent or the error doesn't tell, e.g. for [`Error::InvalidInput`].
+    pub fn attempts(&self) -> Option<Cow<'_, Attempts>> {
+        let outcome = match self {
+            Self::RetriesExhausted { attempts, .. } => return Some(Cow::Borrowed(attempts)),
+            Self::ChunkFailed { source, .. } => return source.attempts(),
+            Self::BatchFailed { source, .. } => return source.attempts(),
+            Self::Http(_) | Self::Connectivity(_) | Self::Timeout { .. } => {
+                AttemptOutcome::RequestFail
+            }
+            _ => AttemptOutcome::Status(self.status()?.as_u16()),
+        };
+        let mut attempts = Attempts::default();
+        attempts.push(outcome, None);
+        Some(Cow::Owned(attempts))
+    }
+
+    /// Coarse category of the error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Api { .. }
+            | Self::NonExistDocument
+            | Self::TranslationNotDone
+            | Self::GlossaryNotFound(_)
+            | Self::RequestTooLarge { .. }
+            | Self::ServerError { .. } => ErrorKind::Api,
+            Self::TooManyRequests { .. } => ErrorKind::RateLimited,
+            Self::QuotaExceeded(_) | Self::KeysExhausted => ErrorKind::Quota,
+            Self::AuthorizationFailed(_) | Self::InvalidApiKey(_) => ErrorKind::Auth,
+            Self::ReadFileError { .. } | Self::WriteFileError { .. } | Self::WriteError { .. } => {
+                ErrorKind::Io
+            }
+            Self::InvalidResponse(_) | Self::DocumentTooLarge { .. } => ErrorKind::Decode,
+            Self::Http(err) if err.is_decode() => ErrorKind::Decode,
+            Self::Http(err) if err.is_builder() => ErrorKind::Config,
+            Self::Http(_) | Self::Connectivity(_) | Self::Timeout { .. } => ErrorKind::Connectivity,
+            #[cfg(feature = "reqwest-middleware")]
+            Self::Middleware(_) => ErrorKind::Config,
+            Self::InvalidConfig(_)
+            | Self::InvalidEndpoint(_)
+            | Self::InsecureEndpoint(_)
+            | Self::InvalidLanguage(_)
+            | Self::EmptyInput
+            | Self::InvalidInput(_) => ErrorKind::Config,
+            Self::Cancelled | Self::DeadlineExceeded => ErrorKind::Cancelled,
+            Self::RetriesExhausted { source, .. } | Self::ChunkFailed { source, .. } => {
+                source.kind()
+            }
+            Self::BatchFailed { source, .. } => source.kind(),
+        }
+    }
+
+    /// Kind of the I/O error of [`Error::ReadFileError`] and [`Error::WriteFileError`], e.g.
+    /// to tell [`PermissionDenied`](std::io::ErrorKind::PermissionDenied) from
+    /// [`StorageFull`](std::io::ErrorKind::StorageFull)
+    pub fn io_kind(&self) -> Option<std::io::ErrorKind> {
+        match self {
+            Self::ReadFileError { source, .. }
+            | Self::WriteFileError { source, .. }
+            | Self::WriteError { source, .. } => Some(source.kind()),
+            _ => None,
+        }
+    }
+
+    /// HTTP status of the error response of DeepL, if one was received
+    pub fn status(&self) -> Option<reqwest::StatusCode> {
+        match self {
+            Self::Api { status, .. }
+            | Self::TooManyRequests { status, .. }
+            | Self::ServerError { status, .. } => Some(*status),
+            Self::AuthorizationFailed(_) => Some(reqwest::StatusCode::FORBIDDEN),
+            Self::QuotaExceeded(_) => reqwest::StatusCode::from_u16(456).ok(),
+            Self::RetriesExhausted { source, .. } | Self::ChunkFailed { source, .. } => {
+                source.status()
+            }
+            Self::BatchFailed { source, .. } => source.status(),
+            _ => None,
+        }
+    }
+
+    /// Delay requested by the `Retry-After` header of [`Error::TooManyRequests`]
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::TooManyRequests { retry_after, .. } => *retry_after,
+            Self::RetriesExhausted { source, .. } | Self::ChunkFailed { source, .. } => {
+                source.retry_after()
+            }
+            Self::BatchFailed { source, .. } => source.retry_after(),
+            _ => None,
+        }
+    }
+
+    fn path(&self) -> Option<&std::path::Path> {
+        match self {
+            Self::ReadFileError { path, .. } | Self::WriteFileError { path, .. } => Some(path),
+            Self::RetriesExhausted { source, .. } | Self::ChunkFailed { source, .. } => {
+                source.path()
+            }
+            Self::BatchFailed { source, .. } => source.path(),
+            _ => None,
+        }
+    }
+
+    /// Where an error response of DeepL was received, see [`ErrorContext`]
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Self::Api { context, .. }
+            | Self::RequestTooLarge { context, .. }
+            | Self::TooManyRequests { context, .. }
+            | Self::ServerError { context, .. } => context.as_deref(),
+            Self::RetriesExhausted { source, .. } | Self::ChunkFailed { source, .. } => {
+                source.context()
+            }
+            Self::BatchFailed { source, .. } => source.context(),
+            _ => None,
+        }
+    }
+
+    /// The method that received the error response, e.g. `check_document_status`
+    pub fn endpoint(&self) -> Option<&'static str> {
+        self.context().map(|context| context.endpoint)
+    }
+
+    fn context_mut(&mut self) -> Option<&mut Option<Box<ErrorContext>>> {
+        match self {
+            Self::Api { context, .. }
+            | Self::RequestTooLarge { context, .. }
+            | Self::TooManyRequests { context, .. }
+            | Self::ServerError { context, .. } => Some(context),
+            Self::RetriesExhausted { source, .. } | Self::ChunkFailed { source, .. } => {
+                source.context_mut()
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn with_endpoint(mut self, endpoint: &'static str) -> Self {
+        if let Some(context) = self.context_mut() {
+            let (id, trace_id) = context
+                .take()
+                .map_or((None, None), |context| (context.id, context.trace_id));
+            *context = Some(Box::new(ErrorContext {
+                endpoint,
+                id,
+                trace_id,
+            }));
+        }
+        self
+    }
+
+    pub(crate) fn with_trace_id(mut self, trace_id: Option<String>) -> Self {
+        if let (Some(Some(context)), Some(trace_id)) = (self.context_mut(), trace_id) {
+            context.trace_id = Some(trace_id);
+        }
+        self
+    }
+
+    /// The `x-trace-id` header of the error response, to hand to DeepL support
+    pub fn trace_id(&self) -> Option<&str> {
+        self.context()?.trace_id.as_deref()
+    }
+
+    /// Name the document or glossary of the request, never pass a key
+    pub(crate) fn with_id(mut self, id: &str) -> Self {
+        if let Some(Some(context)) = self.context_mut() {
+            context.id = Some(id.to_string());
+        }
+        self
+    }
+
+    /// Why [`DeepLApi::create_glossary`] was rejected, for a 400 Bad Request of that method
+    pub fn glossary_create_error(&self) -> Option<glossary::GlossaryCreateError> {
+        match self {
+            Self::Api {
+                status,
+                error: Some(error),
+                context: Some(context),
+                ..
+            } if *status == reqwest::StatusCode::BAD_REQUEST
+                && context.endpoint == "create_glossary" =>
+            {
+                Some(glossary::GlossaryCreateError::classify(error))
+            }
+            Self::RetriesExhausted { source, .. } | Self::ChunkFailed { source, .. } => {
+                source.glossary_create_error()
+            }
+            Self::BatchFailed { source, .. } => source.glossary_create_error(),
+            _ => None,
+        }
+    }
+
+    /// The error body sent by DeepL, see [`Error::Api`]
+    pub fn api_error(&self) -> Option<&DeepLApiError> {
+        match self {
+            Self::Api { error, .. } => error.as_ref(),
+            Self::RetriesExhausted { source, .. } | Self::ChunkFailed { source, .. } => {
+                source.api_error()
+            }
+            Self::BatchFailed { source, .. } => source.api_error(),
+            _ => None,
+        }
+    }
+
+    /// Whether sending the request again later may succeed:
+    ///
+    /// - retryable: 429 Too Many Requests, 5xx like 503 Service Unavailable, connectivity errors
+    ///   like a refused connection or a timeout, and a document not yet translated. Check
+    ///   [`is_timeout`](Self::is_timeout) before retrying requests that shouldn't be billed twice
+    /// - permanent: everything else, like 400 Bad Request, 403 Forbidden, 456 Quota Exceeded,
+    ///   invalid configuration and file I/O errors
+    ///
+    /// For [`Error::RetriesExhausted`], the classification of the last error.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Api { status, .. } => crate::retry::is_retryable_status(*status),
+            Self::TooManyRequests { .. } | Self::ServerError { .. } | Self::TranslationNotDone => {
+                true
+            }
+            Self::RetriesExhausted { source, .. } | Self::ChunkFailed { source, .. } => {
+                source.is_retryable()
+            }
+            Self::BatchFailed { source, .. } => source.is_retryable(),
+            _ => self.is_connectivity(),
+        }
+    }
+
+    /// 429 Too Many Requests
+    pub fn is_rate_limited(&self) -> bool {
+        match self {
+            Self::TooManyRequests { status, .. } => {
+                *status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            Self::RetriesExhausted { source, .. } | Self::ChunkFailed { source, .. } => {
+                source.is_rate_limited()
+            }
+            Self::BatchFailed { source, .. } => source.is_rate_limited(),
+            _ => false,
+        }
+    }
+
+    /// 456 Quota Exceeded, or every key of the rotation exhausted
+    pub fn is_quota_exceeded(&self) -> bool {
+        matches!(self, Self::QuotaExceeded(_) | Self::KeysExhausted)
+    }
+
+    /// 403 Forbidden, or an auth key rejected before sending
+    pub fn is_auth_error(&self) -> bool {
+        matches!(self, Self::AuthorizationFailed(_) | Self::InvalidApiKey(_))
+    }
+
+    /// [`Error::Timeout`], DeepL may have processed the request
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            Self::Timeout { .. } => true,
+            Self::RetriesExhausted { source, .. } | Self::ChunkFailed { source, .. } => {
+                source.is_timeout()
+            }
+            Self::BatchFailed { source, .. } => source.is_timeout(),
+            _ => false,
+        }
+    }
+
+    /// No response was received, e.g. DNS, connect or TLS failures and timeouts
+    pub fn is_connectivity(&self) -> bool {
+        match self {
+            Self::Connectivity(_) | Self::Timeout { .. } => true,
+            Self::Http(err) => {
+                err.is_connect() || err.is_timeout() || err.is_request() || err.is_body()
+            }
+            Self::RetriesExhausted { source, .. } | Self::ChunkFailed { source, .. } => {
+                source.is_connectivity()
+            }
+            Self::BatchFailed { source, .. } => source.is_connectivity(),
+            _ => false,
+        }
+    }
+}
+
+/// A flat structure for structured logging, only the fields that apply are present:
+///
+/// ```json
+/// { "kind": "rate_limited", "status": 429, "message": "...", "retryable": true, "retry_after_secs": 3 }
+/// ```
+///
+/// Besides, `endpoint`, `id` and `trace_id` of the [`ErrorContext`], `path` of file errors and
+/// the number of [`attempts`](Error::attempts). Auth keys are never included.
+impl Serialize for Error {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("kind", self.kind().as_str())?;
+        if let Some(status) = self.status() {
+            map.serialize_entry("status", &status.as_u16())?;
+        }
+        map.serialize_entry("message", &self.to_string())?;
+        map.serialize_entry("retryable", &self.is_retryable())?;
+        if let Some(retry_after) = self.retry_after() {
+            map.serialize_entry("retry_after_secs", &retry_after.as_secs())?;
+        }
+        if let Some(context) = self.context() {
+            map.serialize_entry("endpoint", context.endpoint)?;
+            if let Some(id) = &context.id {
+                map.serialize_entry("id", id)?;
+            }
+            if let Some(trace_id) = &context.trace_id {
+                map.serialize_entry("trace_id", trace_id)?;
+            }
+        }
+        if let Some(path) = self.path() {
+            map.serialize_entry("path", &path.to_string_lossy())?;
+        }
+        if let Some(attempts) = self.attempts() {
+            map.serialize_entry("attempts", &attempts.len())?;
+        }
+        map.end()
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Self::Timeout {
+                elapsed: None,
+                source: Some(err),
+            }
+        } else if err.is_connect() {
+            Self::Connectivity(err)
+        } else {
+            Self::Http(err)
+        }
+    }
+}
+
+/// Where an error response of DeepL was received, see [`Error::context`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// The method of [`DeepLApi`] that sent the request, e.g. `check_document_status`
+    pub endpoint: &'static str,
+    /// The document or glossary of the request, if any
+    pub id: Option<String>,
+    /// The `x-trace-id` header of the response, if sent by DeepL
+    pub trace_id: Option<String>,
+}
+
+impl std::fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.id {
+            Some(id) => write!(f, "{} of {id}", self.endpoint),
+            None => f.write_str(self.endpoint),
+        }
+    }
+}
+
+fn context_suffix(context: &Option<Box<ErrorContext>>) -> String {
+    context
+        .as_ref()
+        .map(|context| format!(" (in {context})"))
+        .unwrap_or_default()
+}
+
+/// Category of an [`Error`], see [`Error::kind`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// DeepL rejected the request, e.g. 400 Bad Request or 404 Not Found
+    Api,
+    /// 429 Too Many Requests or 503 Service Unavailable
+    RateLimited,
+    /// 456 Quota Exceeded
+    Quota,
+    /// 403 Forbidden or an invalid auth key
+    Auth,
+    /// Reading or writing a local file failed
+    Io,
+    /// The response couldn't be decoded
+    Decode,
+    /// No response was received
+    Connectivity,
+    /// Invalid settings or input, detected before sending, or a request rejected by a
+    /// middleware
+    Config,
+    /// The request was cancelled or its deadline exceeded
+    Cancelled,
+}
+
+impl ErrorKind {
+    /// Name of the kind in snake case, e.g. as a metrics label
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Api => "api",
+            Self::RateLimited => "rate_limited",
+            Self::Quota => "quota",
+            Self::Auth => "auth",
+            Self::Io => "io",
+            Self::Decode => "decode",
+            Self::Connectivity => "connectivity",
+            Self::Config => "config",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+fn request_size_detail(size: Option<usize>, limit: Option<usize>) -> String {
+    match (size, limit) {
+        (Some(size), Some(limit)) => format!(": {size} bytes, the limit is {limit} bytes"),
+        _ => String::new(),
+    }
+}
+
+/// Longest raw body kept by [`Error::Api`]
+const MAX_ERROR_BODY_LEN: usize = 1024;
+
+fn api_error_detail(
+    status: reqwest::StatusCode,
+    error: Option<&DeepLApiError>,
+    body: Option<&str>,
+) -> String {
+    match error.map(ToString::to_string).or(body.map(String::from)) {
+        Some(detail) => format!("{status}: {detail}"),
+        None => status.to_string(),
+    }
 }
 
-const REPO_URL: &'static str = "https://github.com/Avimitin/deepl-rs";
+const REPO_URL: &str = "https://github.com/Avimitin/deepl-rs";
 
 /// Alias Result<T, E> to Result<T, [`Error`]>
-type Result<T, E = Error> = std::result::Result<T, E>;
+pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
 
 /// Pollable alias to a Pin<Box<dyn Future<...>>>. A convenient type for impl
 /// [`IntoFuture`](std::future::IntoFuture) trait
-type Pollable<'poll, T> = Pin<Box<dyn Future<Output = T> + Send + Sync + 'poll>>;
+#[cfg(not(target_arch = "wasm32"))]
+type Pollable<'poll, T> = Pin<Box<dyn Future<Output = T> + Send + 'poll>>;
+
+/// Futures of the wasm backend of reqwest are not `Send`
+#[cfg(target_arch = "wasm32")]
+type Pollable<'poll, T> = Pin<Box<dyn Future<Output = T> + 'poll>>;
+
+/// Future of a request, returned by awaiting a requester like the one of
+/// [`DeepLApi::translate_text`] or by calling `into_future` on it.
+///
+/// The future is `Send`, so it can be stored in a struct and spawned on a multi-threaded runtime
+/// with `tokio::spawn`. It isn't `Sync`, which futures don't need as they are only polled through
+/// `&mut`. On wasm32 it isn't `Send` either, like the futures of the browser.
+///
+/// # Example
+///
+/// ```rust
+/// use std::future::IntoFuture;
+/// use deepl::{ResponseFuture, TranslateTextResp};
+///
+/// struct Pending<'a> {
+///     fut: ResponseFuture<'a, TranslateTextResp>,
+/// }
+///
+/// let pending = Pending {
+///     fut: deepl.translate_text("Hello World", Lang::DE).into_future(),
+/// };
+/// let resp = pending.fut.await?;
+/// ```
+#[must_use = "futures do nothing unless awaited"]
+pub struct ResponseFuture<'a, T>(Pollable<'a, Result<T>>);
+
+impl<'a, T> ResponseFuture<'a, T> {
+    pub(crate) fn new(fut: Pollable<'a, Result<T>>) -> Self {
+        Self(fut)
+    }
+}
+
+impl<T> Future for ResponseFuture<'_, T> {
+    type Output = Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.0.as_mut().poll(cx)
+    }
+}
+
+impl<T> std::fmt::Debug for ResponseFuture<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ResponseFuture")
+    }
+}
+
+/// Settings of a single request, set by the setters of the requesters like `header` and
+/// `timeout`
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    headers: HeaderMap,
+    /// First invalid header or language code, reported when the request is sent
+    error: Option<InvalidArgument>,
+    timeout: Option<Duration>,
+    deadline: Option<Instant>,
+    cancel: Option<CancellationToken>,
+    /// See [`DeepLApi::with_retry`]
+    retry: Option<Option<RetryConfig>>,
+    /// Send bodies exceeding the documented limit of DeepL anyway
+    pub(crate) skip_size_check: bool,
+    /// Check the formality against the target languages before sending
+    pub(crate) validate_formality: bool,
+    /// Send the translate body as form instead of JSON
+    pub(crate) form_encoded: bool,
+    pub(crate) empty_texts: EmptyTextPolicy,
+    /// Replace a hard formality by the preferred one if the target language doesn't support it
+    pub(crate) formality_fallback: bool,
+    /// Parameters without a setter, sent unless a typed parameter has the same name
+    pub(crate) extra_params: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone)]
+enum InvalidArgument {
+    Header(String),
+    Lang(LangConvertError),
+    Input(String),
+}
+
+impl RequestOptions {
+    pub(crate) fn insert_header(&mut self, name: &str, value: &str) {
+        match parse_header(name, value) {
+            Ok((name, value)) => {
+                self.headers.insert(name, value);
+            }
+            Err(err) => {
+                self.error
+                    .get_or_insert(InvalidArgument::Header(err.to_string()));
+            }
+        }
+    }
+
+    pub(crate) fn set_invalid_lang(&mut self, err: LangConvertError) {
+        self.error.get_or_insert(InvalidArgument::Lang(err));
+    }
+
+    pub(crate) fn set_invalid_input(&mut self, err: String) {
+        self.error.get_or_insert(InvalidArgument::Input(err));
+    }
+
+    pub(crate) fn insert_extra_param(&mut self, key: &str, value: serde_json::Value) {
+        match key.is_empty() {
+            true => self.set_invalid_input("the key of an extra parameter is empty".to_string()),
+            false => {
+                self.extra_params.insert(key.to_string(), value);
+            }
+        }
+    }
+
+    pub(crate) fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = Some(timeout);
+    }
+
+    pub(crate) fn set_deadline(&mut self, deadline: Instant) {
+        self.deadline = Some(deadline);
+    }
+
+    pub(crate) fn set_cancellation(&mut self, token: CancellationToken) {
+        self.cancel = Some(token);
+    }
+
+    pub(crate) fn set_retry(&mut self, config: Option<RetryConfig>) {
+        self.retry = Some(config);
+    }
+
+    /// Clone of the client with these options applied
+    pub(crate) fn apply(&self, client: &DeepLApi) -> Result<DeepLApi> {
+        match &self.error {
+            Some(InvalidArgument::Header(err)) => return Err(Error::InvalidConfig(err.clone())),
+            Some(InvalidArgument::Lang(err)) => return Err(Error::InvalidLanguage(err.clone())),
+            Some(InvalidArgument::Input(err)) => return Err(Error::InvalidInput(err.clone())),
+            None => {}
+        }
+
+        let mut client = client.clone();
+        for (name, value) in &self.headers {
+            client.headers.insert(name, value.clone());
+        }
+        if let Some(timeout) = self.timeout {
+            client = client.with_timeout(timeout);
+        }
+        if let Some(deadline) = self.deadline {
+            client = client.with_deadline(deadline);
+        }
+        if let Some(token) = &self.cancel {
+            client = client.with_cancellation(token.clone());
+        }
+        match &self.retry {
+            Some(Some(config)) => client = client.with_retry(config.clone()),
+            Some(None) => client = client.without_retry(),
+            None => {}
+        }
+
+        Ok(client)
+    }
+}
+
+/// Validate a header set by the user. `Authorization` is rejected, as it would replace the
+/// key of the client.
+pub(crate) fn parse_header(name: &str, value: &str) -> Result<(HeaderName, HeaderValue)> {
+    let name = HeaderName::try_from(name)
+        .map_err(|err| Error::InvalidConfig(format!("invalid header name {name:?}: {err}")))?;
+    if name == AUTHORIZATION {
+        return Err(Error::InvalidConfig(
+            "the authorization header can't be overridden, use DeepLApi::with_key instead"
+                .to_string(),
+        ));
+    }
+    let value = HeaderValue::try_from(value)
+        .map_err(|err| Error::InvalidConfig(format!("invalid value of header {name}: {err}")))?;
+
+    Ok((name, value))
+}
+
+/// Argument types accepted by the setters of the requesters, e.g. a `&str` for a `String`
+/// parameter, an array of `&str` for a `Vec<String>` one, or a language code like `"pt-br"`
+/// for a [`Lang`](crate::Lang) one. An invalid language code fails the request with
+/// [`Error::InvalidLanguage`] when it's sent.
+pub trait IntoParam<T> {
+    fn into_param(self) -> std::result::Result<T, LangConvertError>;
+}
+
+impl<S: Into<String>> IntoParam<String> for S {
+    fn into_param(self) -> std::result::Result<String, LangConvertError> {
+        Ok(self.into())
+    }
+}
+
+impl<I, S> IntoParam<Vec<String>> for I
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    fn into_param(self) -> std::result::Result<Vec<String>, LangConvertError> {
+        Ok(self.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Language codes are case insensitive, like in the responses of DeepL
+fn parse_lang(code: &str) -> std::result::Result<Lang, LangConvertError> {
+    Lang::try_from(code.to_uppercase().as_str())
+        .map_err(|_| LangConvertError::InvalidLang(code.to_string()))
+}
+
+impl IntoParam<Lang> for &str {
+    fn into_param(self) -> std::result::Result<Lang, LangConvertError> {
+        parse_lang(self)
+    }
+}
+
+impl IntoParam<Lang> for String {
+    fn into_param(self) -> std::result::Result<Lang, LangConvertError> {
+        parse_lang(&self)
+    }
+}
+
+impl IntoParam<Lang> for &String {
+    fn into_param(self) -> std::result::Result<Lang, LangConvertError> {
+        parse_lang(self)
+    }
+}
+
+macro_rules! impl_into_param {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl IntoParam<$ty> for $ty {
+                fn into_param(self) -> std::result::Result<$ty, LangConvertError> {
+                    Ok(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_into_param!(
+    bool,
+    Lang,
+    Formality,
+    translate::SplitSentences,
+    translate::PreserveFormatting,
+    translate::TagHandling,
+    translate::ModelType,
+    document::DocumentOutputFormat,
+);
 
 /// A self implemented Type Builder
 #[macro_export]
@@ -59,38 +906,110 @@ macro_rules! impl_requester {
 
         paste! {
             #[doc = "Builder type for `" $name "`"]
-            #[derive(Debug, serde::Serialize)]
+            #[derive(Debug, Clone, serde::Serialize)]
             pub struct $name<'a> {
                 #[serde(skip)]
-                client: &'a DeepLApi,
+                client: std::borrow::Cow<'a, DeepLApi>,
+                #[serde(skip)]
+                options: $crate::endpoint::RequestOptions,
 
                 $($must_field: $must_type,)+
                 $($opt_field: Option<$opt_type>,)*
             }
 
             impl<'a> $name<'a> {
+                /// Names of the parameters, in the order of the invocation
+                #[allow(dead_code)]
+                const FIELDS: &'static [&'static str] = &[
+                    $(stringify!($must_field),)+
+                    $(stringify!($opt_field),)*
+                ];
+
                 pub fn new(client: &'a DeepLApi, $($must_field: $must_type,)+) -> Self {
                     Self {
-                        client,
+                        client: std::borrow::Cow::Borrowed(client),
+                        options: Default::default(),
                         $($must_field,)+
                         $($opt_field: None,)*
                     }
                 }
 
+                /// Hold a clone of the client instead of borrowing it, so the requester and
+                /// its future are `'static`, e.g. to await it in `tokio::spawn`. Cloning the
+                /// client is cheap.
+                pub fn into_owned(self) -> $name<'static> {
+                    $name {
+                        client: std::borrow::Cow::Owned(self.client.into_owned()),
+                        options: self.options,
+                        $($must_field: self.$must_field,)+
+                        $($opt_field: self.$opt_field,)*
+                    }
+                }
+
                 $(
                     #[doc = "Setter for `" $opt_field "`"]
-                    pub fn $opt_field(&mut self, $opt_field: $opt_type) -> &mut Self {
-                        self.$opt_field = Some($opt_field);
+                    pub fn $opt_field(
+                        &mut self,
+                        $opt_field: impl $crate::endpoint::IntoParam<$opt_type>,
+                    ) -> &mut Self {
+                        match $opt_field.into_param() {
+                            Ok(value) => self.$opt_field = Some(value),
+                            Err(err) => self.options.set_invalid_lang(err),
+                        }
                         self
                     }
                 )*
+
+                /// Set a header on this request only, replacing a header of the same name set
+                /// by the client. An invalid header or `Authorization` fails the request with
+                /// [`Error::InvalidConfig`].
+                pub fn header(&mut self, name: &str, value: &str) -> &mut Self {
+                    self.options.insert_header(name, value);
+                    self
+                }
+
+                /// Fail the request with [`Error::DeadlineExceeded`] if it takes longer than
+                /// `timeout`, including retries and waiting for the limits of the client
+                pub fn timeout(&mut self, timeout: std::time::Duration) -> &mut Self {
+                    self.options.set_timeout(timeout);
+                    self
+                }
+
+                /// Fail the request with [`Error::DeadlineExceeded`] if it isn't done at
+                /// `deadline`, see [`timeout`](Self::timeout)
+                pub fn deadline(&mut self, deadline: std::time::Instant) -> &mut Self {
+                    self.options.set_deadline(deadline);
+                    self
+                }
+
+                /// Abort the request with [`Error::Cancelled`] once `token` is cancelled
+                pub fn cancellation(
+                    &mut self,
+                    token: tokio_util::sync::CancellationToken,
+                ) -> &mut Self {
+                    self.options.set_cancellation(token);
+                    self
+                }
+
+                /// Retry this request with `config` instead of the policy of the client, see
+                /// [`DeepLApi::with_retry`]
+                pub fn retry_policy(&mut self, config: $crate::RetryConfig) -> &mut Self {
+                    self.options.set_retry(Some(config));
+                    self
+                }
+
+                /// Never retry this request, whatever the policy of the client
+                pub fn no_retry(&mut self) -> &mut Self {
+                    self.options.set_retry(None);
+                    self
+                }
             }
         }
     };
 }
 
 /// Formality preference for translation
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Formality {
     Default,
@@ -118,17 +1037,92 @@ impl std::fmt::Display for Formality {
     }
 }
 
-// detail message of the API error
-#[derive(Deserialize)]
-struct DeepLErrorResp {
-    message: String,
+/// JSON body of an error response of DeepL, e.g.
+/// `{"message": "Unsupported glossary source and target language pair"}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeepLApiError {
+    pub message: String,
+    /// Explanation of the message, sent for some bad requests
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl std::fmt::Display for DeepLApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.detail {
+            Some(detail) => write!(f, "{}: {detail}", self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// Turn an error response of any endpoint into [`Error`], keeping the status. The body is
+/// decoded as [`DeepLApiError`], or kept as raw text otherwise.
+pub(crate) async fn extract_deepl_error<T>(res: reqwest::Response) -> Result<T> {
+    let status = res.status();
+    if matches!(status.as_u16(), 429 | 503) {
+        return Err(Error::TooManyRequests {
+            status,
+            retry_after: crate::retry::retry_after(&res),
+            context: None,
+        });
+    }
+    if status.is_server_error() {
+        return Err(Error::ServerError {
+            status,
+            context: None,
+        });
+    }
+    if status == reqwest::StatusCode::PAYLOAD_TOO_LARGE {
+        return Err(Error::RequestTooLarge {
+            size: None,
+            limit: None,
+            context: None,
+        });
+    }
+    let bytes = res.bytes().await?;
+    let error = serde_json::from_slice::<DeepLApiError>(&bytes).ok();
+
+    // the body of these is not guaranteed to be JSON
+    let message = |fallback: &str| {
+        error
+            .as_ref()
+            .map_or_else(|| fallback.to_string(), |error| error.message.clone())
+    };
+    match status.as_u16() {
+        403 => {
+            let message = message("the auth key is wrong or revoked");
+            return Err(Error::AuthorizationFailed(message));
+        }
+        456 => {
+            let message = message("the character limit has been reached");
+            return Err(Error::QuotaExceeded(message));
+        }
+        _ => {}
+    }
+
+    let body = match error {
+        Some(_) => None,
+        None => truncate_body(&bytes),
+    };
+    Err(Error::Api {
+        status,
+        error,
+        body,
+        context: None,
+    })
 }
 
-/// Turn DeepL API error message into [`Error`]
-async fn extract_deepl_error<T>(res: reqwest::Response) -> Result<T> {
-    let resp = res
-        .json::<DeepLErrorResp>()
-        .await
-        .map_err(|err| Error::InvalidResponse(format!("invalid error response: {err}")))?;
-    Err(Error::RequestFail(resp.message))
+/// The body as text, cut at `MAX_ERROR_BODY_LEN` on a char boundary
+fn truncate_body(bytes: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    let mut end = text.len().min(MAX_ERROR_BODY_LEN);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    Some(text[..end].to_string())
 }
@@ -1,37 +1,213 @@
-use std::future::IntoFuture;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    future::{poll_fn, Future, IntoFuture},
+    hash::Hash,
+    ops::Range,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
 
 use crate::{
-    endpoint::{Formality, Pollable, Result},
-    impl_requester, Lang,
+    endpoint::{Formality, IntoParam, ResponseFuture, Result},
+    impl_requester, Lang, RawResponse,
 };
 
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use tokio_stream::Stream;
 
 /// Response from basic translation API
-#[derive(Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct TranslateTextResp {
     pub translations: Vec<Sentence>,
 }
 
+impl TranslateTextResp {
+    /// The translation of a request with a single text
+    ///
+    /// # Error
+    ///
+    /// Return [`Error::InvalidResponse`] if there is no translation, or more than one.
+    pub fn into_single(self) -> Result<Sentence> {
+        let count = self.translations.len();
+        let mut translations = self.translations.into_iter();
+        match (translations.next(), count) {
+            (Some(sentence), 1) => Ok(sentence),
+            _ => Err(Error::InvalidResponse(format!(
+                "expected a single translation, got {count}"
+            ))),
+        }
+    }
+
+    /// Sum of [`Sentence::billed_characters`], `None` unless requested with
+    /// [`show_billed_characters`](TranslateRequester::show_billed_characters)
+    pub fn total_billed_characters(&self) -> Option<u64> {
+        self.translations
+            .iter()
+            .map(|sent| sent.billed_characters)
+            .sum()
+    }
+
+    /// The translated texts, in the order of the input
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let texts: Vec<&str> = resp.texts().collect();
+    /// ```
+    pub fn texts(&self) -> impl Iterator<Item = &str> {
+        self.translations.iter().map(|sent| sent.text.as_str())
+    }
+
+    /// Take the translated texts, in the order of the input
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let joined = resp.into_texts().join("\n");
+    /// ```
+    pub fn into_texts(self) -> Vec<String> {
+        self.translations
+            .into_iter()
+            .map(|sent| sent.text)
+            .collect()
+    }
+
+    /// The translated texts separated by `separator`. [`Display`](std::fmt::Display) separates
+    /// them by `\n`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let line = resp.join(", ");
+    /// ```
+    pub fn join(&self, separator: &str) -> String {
+        self.texts().collect::<Vec<_>>().join(separator)
+    }
+
+    /// The first translation, if any
+    pub fn first(&self) -> Option<&Sentence> {
+        self.translations.first()
+    }
+
+    /// Number of translations
+    pub fn len(&self) -> usize {
+        self.translations.len()
+    }
+
+    /// Whether there is no translation
+    pub fn is_empty(&self) -> bool {
+        self.translations.is_empty()
+    }
+
+    /// Pair the translations with the inputs in order, stopping at the shorter of both. See
+    /// [`try_iter_with`](Self::try_iter_with) to check the lengths.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// for (input, sentence) in resp.iter_with(&texts) {
+    ///     println!("{input} -> {}", sentence.text);
+    /// }
+    /// ```
+    pub fn iter_with<I: IntoIterator>(
+        &self,
+        inputs: I,
+    ) -> impl Iterator<Item = (I::Item, &Sentence)> {
+        inputs.into_iter().zip(&self.translations)
+    }
+
+    /// Pair the translations with the inputs in order
+    ///
+    /// # Error
+    ///
+    /// Return [`Error::InvalidResponse`] if there are not as many translations as inputs.
+    pub fn try_iter_with<I: IntoIterator>(
+        &self,
+        inputs: I,
+    ) -> Result<impl Iterator<Item = (I::Item, &Sentence)>> {
+        let inputs: Vec<_> = inputs.into_iter().collect();
+        self.check_count(inputs.len())?;
+        Ok(inputs.into_iter().zip(&self.translations))
+    }
+
+    fn check_count(&self, inputs: usize) -> Result<()> {
+        match self.translations.len() {
+            count if count == inputs => Ok(()),
+            count => Err(Error::InvalidResponse(format!(
+                "{count} translations for {inputs} inputs"
+            ))),
+        }
+    }
+
+    /// Map each input to its translated text, see [`try_iter_with`](Self::try_iter_with). Of
+    /// repeated inputs, the last translation is kept.
+    ///
+    /// # Error
+    ///
+    /// Return [`Error::InvalidResponse`] if there are not as many translations as inputs.
+    pub fn into_map<I>(self, inputs: I) -> Result<HashMap<String, String>>
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let inputs: Vec<String> = inputs.into_iter().map(Into::into).collect();
+        self.check_count(inputs.len())?;
+        Ok(inputs.into_iter().zip(self.into_texts()).collect())
+    }
+}
+
+impl IntoIterator for TranslateTextResp {
+    type Item = Sentence;
+    type IntoIter = std::vec::IntoIter<Sentence>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.translations.into_iter()
+    }
+}
+
+/// ```rust
+/// for sentence in &resp {
+///     println!("{}: {}", sentence.detected_source_language, sentence.text);
+/// }
+/// ```
+impl<'a> IntoIterator for &'a TranslateTextResp {
+    type Item = &'a Sentence;
+    type IntoIter = std::slice::Iter<'a, Sentence>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.translations.iter()
+    }
+}
+
+/// The translated texts, one per line
 impl std::fmt::Display for TranslateTextResp {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            self.translations
-                .iter()
-                .map(|sent| sent.text.to_string())
-                .collect::<String>()
-        )
+        for (index, text) in self.texts().enumerate() {
+            if index > 0 {
+                f.write_str("\n")?;
+            }
+            f.write_str(text)?;
+        }
+        Ok(())
     }
 }
 
 /// Translated result for a sentence
-#[derive(Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Sentence {
     pub detected_source_language: Lang,
     pub text: String,
+    /// Characters billed for this text, only sent by DeepL if requested with
+    /// [`show_billed_characters`](TranslateRequester::show_billed_characters)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub billed_characters: Option<u64>,
+    /// The model that translated this text, e.g. `quality_optimized`, only sent by DeepL if a
+    /// [`model_type`](TranslateRequester::model_type) was requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_type_used: Option<String>,
 }
 
 ///
@@ -41,14 +217,34 @@ pub struct Sentence {
 /// - Punctuation at the beginning and end of the sentence
 /// - Upper/lower case at the beginning of the sentence
 ///
-#[derive(Debug, Serialize)]
+/// Sent as a JSON boolean.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PreserveFormatting {
-    #[serde(rename = "1")]
     Preserve,
-    #[serde(rename = "0")]
     DontPreserve,
 }
 
+impl Serialize for PreserveFormatting {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_bool(matches!(self, Self::Preserve))
+    }
+}
+
+impl<'de> Deserialize<'de> for PreserveFormatting {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        Ok(match bool::deserialize(deserializer)? {
+            true => Self::Preserve,
+            false => Self::DontPreserve,
+        })
+    }
+}
+
 ///
 /// Sets whether the translation engine should first split the input into sentences
 ///
@@ -56,7 +252,7 @@ pub enum PreserveFormatting {
 /// in order to prevent the engine from splitting the sentence unintentionally.
 /// Please note that newlines will split sentences. You should therefore clean files to avoid breaking sentences or set this to `PunctuationOnly`.
 ///
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SplitSentences {
     /// Perform no splitting at all, whole input is treated as one sentence
     #[serde(rename = "0")]
@@ -72,7 +268,7 @@ pub enum SplitSentences {
 ///
 /// Sets which kind of tags should be handled. Options currently available
 ///
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TagHandling {
     /// Enable XML tag handling
@@ -83,6 +279,86 @@ pub enum TagHandling {
     Html,
 }
 
+///
+/// Sets which kind of model DeepL should use, trading translation quality for latency
+///
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelType {
+    /// Use the model with the lowest latency
+    LatencyOptimized,
+    /// Use the model with the highest quality, fail if it's not available for the language pair
+    QualityOptimized,
+    /// Use the model with the highest quality if available for the language pair
+    PreferQualityOptimized,
+}
+
+/// How [`TranslateRequester`] handles texts that are empty or only whitespace, see
+/// [`TranslateRequester::empty_text_policy`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EmptyTextPolicy {
+    /// Fail with [`Error::InvalidInput`] before sending anything
+    Error,
+    /// Don't send them, their translations are empty strings at the same index. Nothing is
+    /// sent if all texts are empty.
+    SkipAndPreserveIndices,
+    /// Send them to DeepL like the other texts
+    #[default]
+    SendAsIs,
+}
+
+/// XML handling of [`DeepLApi::translate_xml`], setting `tag_handling`, `ignore_tags`,
+/// `splitting_tags`, `non_splitting_tags` and `outline_detection` together
+///
+/// # Example
+///
+/// ```rust
+/// let tags = XmlTagHandling {
+///     ignore: vec!["code".to_string()],
+///     splitting: vec!["p".to_string()],
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlTagHandling {
+    /// Tags whose content is not translated
+    pub ignore: Vec<String>,
+    /// Tags that always split sentences
+    pub splitting: Vec<String>,
+    /// Tags that never split sentences
+    pub non_splitting: Vec<String>,
+    /// Let DeepL detect the structure of the document, enabled by default. Disabled, only the
+    /// `splitting` tags split sentences.
+    pub outline_detection: bool,
+}
+
+impl Default for XmlTagHandling {
+    fn default() -> Self {
+        Self {
+            ignore: Vec::new(),
+            splitting: Vec::new(),
+            non_splitting: Vec::new(),
+            outline_detection: true,
+        }
+    }
+}
+
+/// Trim the tag names and drop duplicates, keeping the first. `param` names the list in the
+/// error.
+fn normalize_tags(param: &str, tags: &[String]) -> std::result::Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let name = tag.trim();
+        if name.is_empty() || name.contains(['<', '>']) || name.contains(char::is_whitespace) {
+            return Err(format!("invalid tag name {tag:?} in {param}"));
+        }
+        if !normalized.iter().any(|seen| seen == name) {
+            normalized.push(name.to_string());
+        }
+    }
+    Ok(normalized)
+}
+
 impl_requester! {
     TranslateRequester {
         @required{
@@ -100,13 +376,16 @@ impl_requester! {
             non_splitting_tags: Vec<String>,
             splitting_tags: Vec<String>,
             ignore_tags: Vec<String>,
+            show_billed_characters: bool,
+            model_type: ModelType,
+            outline_detection: bool,
         };
     } -> Result<TranslateTextResp, Error>;
 }
 
 impl<'a> IntoFuture for TranslateRequester<'a> {
     type Output = Result<TranslateTextResp>;
-    type IntoFuture = Pollable<'a, Self::Output>;
+    type IntoFuture = ResponseFuture<'a, TranslateTextResp>;
 
     fn into_future(self) -> Self::IntoFuture {
         self.send()
@@ -115,179 +394,3323 @@ impl<'a> IntoFuture for TranslateRequester<'a> {
 
 impl<'a> IntoFuture for &mut TranslateRequester<'a> {
     type Output = Result<TranslateTextResp>;
-    type IntoFuture = Pollable<'a, Self::Output>;
+    type IntoFuture = ResponseFuture<'a, TranslateTextResp>;
 
     fn into_future(self) -> Self::IntoFuture {
         self.send()
     }
 }
 
-impl<'a> TranslateRequester<'a> {
-    fn send(&self) -> Pollable<'a, Result<TranslateTextResp>> {
-        let client = self.client.clone();
-        let obj = json!(self);
+/// Send the current parameters, so a configured requester can be awaited more than once
+impl<'a> IntoFuture for &TranslateRequester<'a> {
+    type Output = Result<TranslateTextResp>;
+    type IntoFuture = ResponseFuture<'a, TranslateTextResp>;
 
-        let fut = async move {
-            let response = client
-                .post(client.inner.endpoint.join("translate").unwrap())
-                .json(&obj)
-                .send()
-                .await
-                .map_err(|err| Error::RequestFail(err.to_string()))?;
+    fn into_future(self) -> Self::IntoFuture {
+        self.send()
+    }
+}
 
-            if !response.status().is_success() {
-                return super::extract_deepl_error(response).await;
-            }
+/// The optional parameters of [`DeepLApi::translate_text`], to apply the same options to many
+/// requests with [`TranslateRequester::with_options`] or [`DeepLApi::translate_with`]. The fields
+/// are named like the parameters of DeepL, so the options can be loaded from a config file.
+///
+/// Document uploads take the same options with
+/// [`UploadDocumentRequester::with_options`](crate::endpoint::document::UploadDocumentRequester::with_options),
+/// using only `source_lang`, `formality` and `glossary_id`.
+///
+/// # Example
+///
+/// ```rust
+/// let options = TranslateOptions {
+///     formality: Some(Formality::Less),
+///     tag_handling: Some(TagHandling::Html),
+///     ..Default::default()
+/// };
+/// let resp = deepl.translate_with("<p>Hello</p>", Lang::DE, &options).await?;
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TranslateOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_lang: Option<Lang>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub split_sentences: Option<SplitSentences>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preserve_formatting: Option<PreserveFormatting>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub formality: Option<Formality>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub glossary_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag_handling: Option<TagHandling>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub non_splitting_tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub splitting_tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ignore_tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub show_billed_characters: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_type: Option<ModelType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outline_detection: Option<bool>,
+}
 
-            let response: TranslateTextResp = response.json().await.map_err(|err| {
-                Error::InvalidResponse(format!("convert json bytes to Rust type: {err}"))
-            })?;
+/// Limit of the request body size of the translate endpoint
+const MAX_BODY_LEN: usize = 128 * 1024;
 
-            Ok(response)
+/// Size of the parts of an HTML file once escaped in JSON, leaves room for the other
+/// parameters within [`MAX_BODY_LEN`]
+#[cfg(feature = "tokio-fs")]
+const HTML_PART_LEN: usize = 64 * 1024;
+
+/// Split HTML into parts of at most `max` bytes once escaped in a JSON string. A part ends
+/// before a tag of the shallowest element in reach that follows some text, so an element, like
+/// one with `translate="no"`, is only cut if it doesn't fit in a part on its own.
+#[cfg(feature = "tokio-fs")]
+fn split_html(html: &str, max: usize) -> Vec<&str> {
+    let boundaries = tag_boundaries(html);
+    let mut parts = Vec::new();
+    let mut start = 0;
+    loop {
+        // the first character that doesn't fit, a part holds at least one
+        let mut len = 0;
+        let overflow = html[start..].char_indices().find_map(|(at, c)| {
+            len += escaped_len(c);
+            let at = if at == 0 { c.len_utf8() } else { at };
+            (len > max).then_some(start + at)
+        });
+        let Some(end) = overflow.filter(|&end| end < html.len()) else {
+            break;
         };
+        let in_reach: Vec<_> = boundaries
+            .iter()
+            .filter(|boundary| boundary.at > start && boundary.at <= end)
+            .collect();
+        // a part made of opening tags only, like `<html><body>`, isn't worth translating
+        let after_text = in_reach
+            .iter()
+            .filter(|boundary| boundary.text.is_some_and(|text| text > start));
+        let cut = shallowest(after_text)
+            .or_else(|| shallowest(in_reach.iter()))
+            .or_else(|| {
+                let at = html[start..end].rfind(char::is_whitespace)?;
+                (at > 0).then_some(start + at)
+            })
+            .unwrap_or(end);
+        parts.push(&html[start..cut]);
+        start = cut;
+    }
+    parts.push(&html[start..]);
+    parts
+}
 
-        Box::pin(fut)
+/// Size of `c` escaped in a JSON string, like `\"` or `\u0001`
+#[cfg(feature = "tokio-fs")]
+fn escaped_len(c: char) -> usize {
+    match c {
+        '"' | '\\' | '\n' | '\r' | '\t' | '\u{8}' | '\u{c}' => 2,
+        '\0'..='\u{1f}' => 6,
+        c => c.len_utf8(),
     }
 }
 
-impl DeepLApi {
-    /// Translate the given text with specific target language.
+/// Position of the last of the boundaries with the fewest elements open around them
+#[cfg(feature = "tokio-fs")]
+fn shallowest<'b>(boundaries: impl Iterator<Item = &'b &'b TagBoundary>) -> Option<usize> {
+    boundaries
+        .min_by_key(|boundary| (boundary.depth, std::cmp::Reverse(boundary.at)))
+        .map(|boundary| boundary.at)
+}
+
+/// Start of a tag or comment in an HTML file
+#[cfg(feature = "tokio-fs")]
+#[derive(Debug, PartialEq)]
+struct TagBoundary {
+    at: usize,
+    /// Number of elements open around the tag, a closing tag counts as inside its element
+    depth: usize,
+    /// End of the last text before the tag
+    text: Option<usize>,
+}
+
+/// Find the tags and comments of an HTML file
+#[cfg(feature = "tokio-fs")]
+fn tag_boundaries(html: &str) -> Vec<TagBoundary> {
+    const VOID: [&str; 14] = [
+        "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param",
+        "source", "track", "wbr",
+    ];
+    // ASCII lowercase keeps the byte offsets
+    let lower = html.to_ascii_lowercase();
+    let mut boundaries = Vec::new();
+    let mut depth: usize = 0;
+    let mut text = None;
+    let mut at = 0;
+    while let Some(offset) = lower[at..].find('<') {
+        let start = at + offset;
+        if !lower[at..start].trim().is_empty() {
+            text = Some(start);
+        }
+        let rest = &lower[start..];
+        let boundary = TagBoundary {
+            at: start,
+            depth,
+            text,
+        };
+        if rest.starts_with("<!--") {
+            boundaries.push(boundary);
+            at = start + rest.find("-->").map_or(rest.len(), |end| end + 3);
+            continue;
+        }
+        let closing = rest.starts_with("</");
+        let name: String = rest[1 + closing as usize..]
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+            .collect();
+        let len = tag_len(rest);
+        at = start + len;
+        match (closing, name.is_empty()) {
+            // text like `a < b`
+            (_, true) if !rest[1..].starts_with(['!', '?']) => {
+                text = Some(at);
+                continue;
+            }
+            (_, true) => boundaries.push(boundary),
+            (true, false) => {
+                boundaries.push(boundary);
+                depth = depth.saturating_sub(1);
+            }
+            (false, false) => {
+                boundaries.push(boundary);
+                if VOID.contains(&name.as_str()) || rest[..len].ends_with("/>") {
+                    continue;
+                }
+                depth += 1;
+                // the content of a script or style is code, even if it looks like tags
+                if name == "script" || name == "style" {
+                    let close = format!("</{name}");
+                    at = lower[at..].find(&close).map_or(html.len(), |end| at + end);
+                }
+            }
+        }
+    }
+    boundaries
+}
+
+/// Length of the tag at the start of `html` up to its `>`, which may be quoted in attributes
+#[cfg(feature = "tokio-fs")]
+fn tag_len(html: &str) -> usize {
+    let mut quote = None;
+    for (at, c) in html.char_indices().skip(1) {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if open == c => quote = None,
+            (None, '>') => return at + 1,
+            _ => {}
+        }
+    }
+    html.len()
+}
+
+/// Limit of the number of texts per request of the translate endpoint
+const MAX_TEXTS: usize = 50;
+
+/// Number of chunks of a request with more than [`MAX_TEXTS`] texts sent at once
+const MAX_CONCURRENT_CHUNKS: usize = 4;
+
+/// Body of a translate request as sent, see [`TranslateRequester::form_encoded`]
+#[derive(Debug)]
+enum Body {
+    Json(Vec<u8>),
+    Form(String),
+}
+
+impl Body {
+    /// The size of the body as sent, i.e. in bytes of UTF-8 after escaping
+    fn len(&self) -> usize {
+        match self {
+            Self::Json(json) => json.len(),
+            Self::Form(form) => form.len(),
+        }
+    }
+}
+
+/// The parameters of a request with some of the texts, serialized without copying them. Like
+/// a `serde_json::Value`, the fields are in alphabetical order and unset options are `null`,
+/// followed by the extra parameters, except those named like a field.
+#[derive(Serialize)]
+struct Params<'r> {
+    context: &'r Option<String>,
+    formality: &'r Option<Formality>,
+    glossary_id: &'r Option<String>,
+    ignore_tags: &'r Option<Vec<String>>,
+    model_type: &'r Option<ModelType>,
+    non_splitting_tags: &'r Option<Vec<String>>,
+    outline_detection: &'r Option<bool>,
+    preserve_formatting: &'r Option<PreserveFormatting>,
+    show_billed_characters: &'r Option<bool>,
+    source_lang: &'r Option<Lang>,
+    split_sentences: &'r Option<SplitSentences>,
+    splitting_tags: &'r Option<Vec<String>>,
+    tag_handling: &'r Option<TagHandling>,
+    target_lang: &'r Lang,
+    text: &'r [String],
+    #[serde(flatten)]
+    extra: BTreeMap<&'r str, &'r serde_json::Value>,
+}
+
+impl<'a> TranslateRequester<'a> {
+    fn params<'r>(&'r self, text: &'r [String]) -> Params<'r> {
+        Params {
+            context: &self.context,
+            formality: &self.formality,
+            glossary_id: &self.glossary_id,
+            ignore_tags: &self.ignore_tags,
+            model_type: &self.model_type,
+            non_splitting_tags: &self.non_splitting_tags,
+            outline_detection: &self.outline_detection,
+            preserve_formatting: &self.preserve_formatting,
+            show_billed_characters: &self.show_billed_characters,
+            source_lang: &self.source_lang,
+            split_sentences: &self.split_sentences,
+            splitting_tags: &self.splitting_tags,
+            tag_handling: &self.tag_handling,
+            target_lang: &self.target_lang,
+            text,
+            extra: self
+                .options
+                .extra_params
+                .iter()
+                .filter(|(key, _)| !Self::FIELDS.contains(&key.as_str()))
+                .map(|(key, value)| (key.as_str(), value))
+                .collect(),
+        }
+    }
+
+    /// The body with the given texts, checked against the limits of DeepL
+    fn encode(&self, text: &[String]) -> Result<Body> {
+        let params = self.params(text);
+        let body = match self.options.form_encoded {
+            true => Body::Form(form_body(&json!(params))),
+            false => Body::Json(
+                serde_json::to_vec(&params)
+                    .map_err(|err| Error::InvalidInput(format!("fail to serialize body: {err}")))?,
+            ),
+        };
+
+        let size = body.len();
+        if !self.options.skip_size_check && size > MAX_BODY_LEN {
+            return Err(Error::RequestTooLarge {
+                size: Some(size),
+                limit: Some(MAX_BODY_LEN),
+                context: None,
+            });
+        }
+        Ok(body)
+    }
+
+    /// The body with all texts
+    fn body(&self) -> Result<Body> {
+        if self.text.is_empty() {
+            return Err(Error::EmptyInput);
+        }
+        self.encode(&self.text)
+    }
+
+    /// Send the request and return the translated text, see [`TranslateTextResp::into_single`]
+    pub fn collect_string(&self) -> ResponseFuture<'a, String> {
+        let fut = self.send();
+        ResponseFuture::new(Box::pin(async move { Ok(fut.await?.into_single()?.text) }))
+    }
+
+    /// Use the glossary, setting `glossary_id` and the `source_lang` of the glossary
     ///
     /// # Error
     ///
-    /// Return [`Error`] if the http request fail
+    /// Sending fails with [`Error::InvalidInput`] if the glossary isn't for the target language.
+    pub fn glossary(&mut self, glossary: &crate::glossary::GlossaryResp) -> &mut Self {
+        if let Err(err) = glossary.check_target(&self.target_lang) {
+            self.options.set_invalid_input(err);
+        }
+        self.glossary_id = Some(glossary.glossary_id.clone());
+        self.source_lang = Some(glossary.source_lang.clone());
+        self
+    }
+
+    /// A clone of this requester with other texts, to use a configured requester as a template
     ///
     /// # Example
     ///
-    /// * Simple translation
-    ///
     /// ```rust
-    /// use deepl::{DeepLApi, Lang};
-    ///
-    /// let key = std::env::var("DEEPL_API_KEY").unwrap();
-    /// let deepl = DeepLApi::with(&key).new();
-    ///
-    /// let response = deepl.translate_text("Hello World", Lang::ZH).await.unwrap();
-    /// assert!(!response.translations.is_empty());
+    /// let mut template = deepl.translate_text(Vec::<String>::new(), Lang::DE);
+    /// template.formality(Formality::Less).glossary_id(id);
+    /// for texts in batches {
+    ///     let resp = template.with_texts(texts).await?;
+    /// }
     /// ```
+    pub fn with_texts(&self, texts: impl ToTranslatable) -> Self {
+        let mut requester = self.clone();
+        requester.text = texts.to_translatable();
+        requester
+    }
+
+    /// Append a text to translate
+    pub fn push_text(&mut self, text: impl Into<String>) -> &mut Self {
+        self.text.push(text.into());
+        self
+    }
+
+    /// Append several texts to translate
+    pub fn extend_texts<S: Into<String>>(
+        &mut self,
+        texts: impl IntoIterator<Item = S>,
+    ) -> &mut Self {
+        self.text.extend(texts.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set every option given in `options`, keeping the others. Setters called afterwards
+    /// override single options.
+    pub fn with_options(&mut self, options: &TranslateOptions) -> &mut Self {
+        let TranslateOptions {
+            context,
+            source_lang,
+            split_sentences,
+            preserve_formatting,
+            formality,
+            glossary_id,
+            tag_handling,
+            non_splitting_tags,
+            splitting_tags,
+            ignore_tags,
+            show_billed_characters,
+            model_type,
+            outline_detection,
+        } = options.clone();
+
+        self.context = context.or(self.context.take());
+        self.source_lang = source_lang.or(self.source_lang.take());
+        self.split_sentences = split_sentences.or(self.split_sentences.take());
+        self.preserve_formatting = preserve_formatting.or(self.preserve_formatting.take());
+        self.formality = formality.or(self.formality.take());
+        self.glossary_id = glossary_id.or(self.glossary_id.take());
+        self.tag_handling = tag_handling.or(self.tag_handling.take());
+        self.non_splitting_tags = non_splitting_tags.or(self.non_splitting_tags.take());
+        self.splitting_tags = splitting_tags.or(self.splitting_tags.take());
+        self.ignore_tags = ignore_tags.or(self.ignore_tags.take());
+        self.show_billed_characters = show_billed_characters.or(self.show_billed_characters);
+        self.model_type = model_type.or(self.model_type.take());
+        self.outline_detection = outline_detection.or(self.outline_detection);
+        self
+    }
+
+    /// Number of texts to translate so far
+    pub fn text_count(&self) -> usize {
+        self.text.len()
+    }
+
+    /// Estimate the characters billed for this request, see [`crate::estimate_characters`].
+    /// With [`tag_handling`](Self::tag_handling), the markup tags aren't counted.
+    pub fn estimated_characters(&self) -> u64 {
+        match self.tag_handling {
+            Some(_) => self
+                .text
+                .iter()
+                .map(|text| characters_without_tags(text))
+                .sum(),
+            None => estimate_characters(&self.text),
+        }
+    }
+
+    /// Send the request even if the body exceeds the 128 KiB limit of DeepL, e.g. for a
+    /// deployment with a different limit
+    pub fn skip_size_check(&mut self) -> &mut Self {
+        self.options.skip_size_check = true;
+        self
+    }
+
+    /// Send a parameter without a setter, e.g. one added to DeepL after this release. The
+    /// typed parameters win, an extra parameter named like one of them, e.g. `target_lang`, is
+    /// not sent. An empty key fails the request with [`Error::InvalidInput`].
     ///
-    /// * Translation with XML tag enabled
+    /// # Example
     ///
     /// ```rust
-    /// use deepl::{DeepLApi, Lang};
-    ///
-    /// let key = std::env::var("DEEPL_API_KEY").unwrap();
-    /// let deepl = DeepLApi::with(&key).new();
-    ///
-    /// let str = "Hello World <keep>This will stay exactly the way it was</keep>";
-    /// let response = deepl
-    ///     .translate_text(str, Lang::DE)
-    ///     .source_lang(Lang::EN)
-    ///     .ignore_tags(vec!["keep".to_owned()])
-    ///     .tag_handling(TagHandling::Xml)
-    ///     .await
-    ///     .unwrap();
-    ///
-    /// let translated_results = response.translations;
-    /// let should = "Hallo Welt <keep>This will stay exactly the way it was</keep>";
-    /// assert_eq!(translated_results[0].text, should);
+    /// let resp = deepl
+    ///     .translate_text("Hello", Lang::DE)
+    ///     .extra_param("custom_instructions", json!(["Use British spelling"]))
+    ///     .await?;
     /// ```
-    pub fn translate_text(&self, text: impl ToString, target_lang: Lang) -> TranslateRequester {
-        TranslateRequester::new(self, vec![text.to_string()], target_lang)
+    pub fn extra_param(&mut self, key: &str, value: serde_json::Value) -> &mut Self {
+        self.options.insert_extra_param(key, value);
+        self
     }
-}
 
-#[tokio::test]
-async fn test_translate_text() {
-    let key = std::env::var("DEEPL_API_KEY").unwrap();
-    let api = DeepLApi::with(&key).new();
-    let response = api.translate_text("Hello World", Lang::ZH).await.unwrap();
+    /// Send the body as `application/x-www-form-urlencoded` instead of JSON, e.g. for a proxy
+    /// rejecting JSON bodies. DeepL handles both the same, the response doesn't change.
+    pub fn form_encoded(&mut self, enabled: bool) -> &mut Self {
+        self.options.form_encoded = enabled;
+        self
+    }
 
-    assert!(!response.translations.is_empty());
+    /// Set the formality, sent as [`Formality::PreferMore`] or [`Formality::PreferLess`]
+    /// instead of [`Formality::More`] or [`Formality::Less`] if the target language doesn't
+    /// support formality, so the translation falls back to the default formality instead of
+    /// failing. The body sent holds the formality used. The target languages are fetched once
+    /// per client.
+    pub fn formality_with_fallback(&mut self, formality: Formality) -> &mut Self {
+        self.formality = Some(formality);
+        self.options.formality_fallback = true;
+        self
+    }
 
-    let translated_results = response.translations;
-    assert_eq!(translated_results[0].text, "你好，世界");
-    assert_eq!(translated_results[0].detected_source_language, Lang::EN);
-}
+    /// Fail with [`Error::InvalidInput`] before sending the texts if the formality is
+    /// [`Formality::More`] or [`Formality::Less`] and the target language doesn't support
+    /// formality, instead of a 400 Bad Request of DeepL. [`Formality::PreferMore`] and
+    /// [`Formality::PreferLess`] always pass. The target languages are fetched once per client.
+    pub fn validate_formality(&mut self) -> &mut Self {
+        self.options.validate_formality = true;
+        self
+    }
 
-#[tokio::test]
-async fn test_advanced_translate() {
-    let key = std::env::var("DEEPL_API_KEY").unwrap();
-    let api = DeepLApi::with(&key).new();
+    /// The bodies of chunks of at most `size` texts with their number of characters, each
+    /// checked against the limits of DeepL
+    fn chunks(&self, size: usize) -> Result<Vec<(Body, usize)>> {
+        if self.text.is_empty() {
+            return Err(Error::EmptyInput);
+        }
+        self.text
+            .chunks(size)
+            .map(|texts| Ok((self.encode(texts)?, characters(texts))))
+            .collect()
+    }
 
-    let response = api.translate_text(
-            "Hello World <keep additionalarg=\"test0\">This will stay exactly the way it was</keep>",
-            Lang::DE
-        )
-        .source_lang(Lang::EN)
-        .ignore_tags(vec!["keep".to_string()])
-        .tag_handling(TagHandling::Xml)
-        .await
-        .unwrap();
+    /// Fail with [`Error::EmptyInput`] before sending anything if there is no text. Empty
+    /// strings among other texts are sent as is, and translated to empty strings, unless set
+    /// otherwise by [`empty_text_policy`](Self::empty_text_policy).
+    ///
+    /// More than 50 texts are split into chunks of 50, sent as separate requests with the same
+    /// options, a few at once. The translations keep the order of the texts. If a chunk fails,
+    /// the error is [`Error::ChunkFailed`], holding the translations of the chunks before it.
+    ///
+    /// Fail with [`Error::RequestTooLarge`] before sending anything if the body of a
+    /// request exceeds the 128 KiB limit of DeepL.
+    ///
+    /// The tag lists are trimmed and deduplicated. A tag name that is empty or has angle
+    /// brackets or whitespace, or a tag list without `tag_handling`, fails with
+    /// [`Error::InvalidInput`].
+    fn send(&self) -> ResponseFuture<'a, TranslateTextResp> {
+        match self.normalized_tags() {
+            Ok(Some(requester)) => return requester.send(),
+            Ok(None) => {}
+            Err(err) => {
+                let err = Error::InvalidInput(err);
+                return ResponseFuture::new(Box::pin(std::future::ready(Err(err))));
+            }
+        }
+        if self.options.formality_fallback {
+            let mut requester = self.clone();
+            requester.options.formality_fallback = false;
+            return ResponseFuture::new(Box::pin(async move {
+                let soft = match requester.formality {
+                    Some(Formality::More) => Formality::PreferMore,
+                    Some(Formality::Less) => Formality::PreferLess,
+                    _ => return requester.send().await,
+                };
+                let client = requester.options.apply(&requester.client)?;
+                if client.supports_formality(&requester.target_lang).await? == Some(false) {
+                    requester.formality = Some(soft);
+                }
+                requester.send().await
+            }));
+        }
 
-    assert!(!response.translations.is_empty());
+        let blank: Vec<usize> = match self.options.empty_texts {
+            EmptyTextPolicy::SendAsIs => Vec::new(),
+            _ => self
+                .text
+                .iter()
+                .enumerate()
+                .filter(|(_, text)| text.trim().is_empty())
+                .map(|(index, _)| index)
+                .collect(),
+        };
+        if blank.is_empty() {
+            return self.send_texts();
+        }
+        if self.options.empty_texts == EmptyTextPolicy::Error {
+            let err = Error::InvalidInput(format!("text {} is empty", blank[0]));
+            return ResponseFuture::new(Box::pin(std::future::ready(Err(err))));
+        }
 
-    let translated_results = response.translations;
-    assert_eq!(
-        translated_results[0].text,
-        "Hallo Welt <keep additionalarg=\"test0\">This will stay exactly the way it was</keep>"
-    );
-    assert_eq!(translated_results[0].detected_source_language, Lang::EN);
-}
+        let mut sendable = self.clone();
+        sendable.text = self
+            .text
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| blank.binary_search(index).is_err())
+            .map(|(_, text)| text.clone())
+            .collect();
+        let fut = (!sendable.text.is_empty()).then(|| sendable.send_texts());
+        let len = self.text.len();
+        let source_lang = self.source_lang.clone();
+        let billed = self.show_billed_characters.unwrap_or_default().then_some(0);
 
-#[tokio::test]
-async fn test_advanced_translator_html() {
-    let key = std::env::var("DEEPL_API_KEY").unwrap();
-    let api = DeepLApi::with(&key).new();
+        ResponseFuture::new(Box::pin(async move {
+            let mut sent = match fut {
+                Some(fut) => fut.await?.translations.into_iter(),
+                None => Vec::new().into_iter(),
+            };
+            // the skipped texts are in the language given or detected for the others
+            let lang = source_lang
+                .or_else(|| {
+                    sent.as_slice()
+                        .first()
+                        .map(|s| s.detected_source_language.clone())
+                })
+                .unwrap_or(Lang::EN);
 
-    let response = api
-        .translate_text(
-            "Hello World <keep translate=\"no\">This will stay exactly the way it was</keep>",
-            Lang::DE,
-        )
-        .tag_handling(TagHandling::Html)
-        .await
-        .unwrap();
+            let mut translations = Vec::with_capacity(len);
+            for index in 0..len {
+                let sentence = match blank.binary_search(&index) {
+                    Ok(_) => Sentence {
+                        detected_source_language: lang.clone(),
+                        text: String::new(),
+                        billed_characters: billed,
+                        model_type_used: None,
+                    },
+                    Err(_) => sent.next().ok_or_else(|| {
+                        Error::InvalidResponse("fewer translations than texts".to_string())
+                    })?,
+                };
+                translations.push(sentence);
+            }
+            Ok(TranslateTextResp { translations })
+        }))
+    }
 
-    assert!(!response.translations.is_empty());
+    /// A copy with the tag lists normalized, or `None` if they already are
+    fn normalized_tags(&self) -> std::result::Result<Option<Self>, String> {
+        let lists = [
+            ("ignore_tags", &self.ignore_tags),
+            ("splitting_tags", &self.splitting_tags),
+            ("non_splitting_tags", &self.non_splitting_tags),
+        ];
+        let mut normalized = Vec::with_capacity(lists.len());
+        for (param, tags) in lists {
+            let Some(tags) = tags else {
+                normalized.push(None);
+                continue;
+            };
+            if self.tag_handling.is_none() {
+                return Err(format!("{param} is set without tag_handling"));
+            }
+            normalized.push(Some(normalize_tags(param, tags)?));
+        }
 
-    let translated_results = response.translations;
-    assert_eq!(
-        translated_results[0].text,
-        "Hallo Welt <keep translate=\"no\">This will stay exactly the way it was</keep>"
-    );
-    assert_eq!(translated_results[0].detected_source_language, Lang::EN);
+        let [ignore, splitting, non_splitting] = <[_; 3]>::try_from(normalized).unwrap();
+        if ignore == self.ignore_tags
+            && splitting == self.splitting_tags
+            && non_splitting == self.non_splitting_tags
+        {
+            return Ok(None);
+        }
+        let mut requester = self.clone();
+        requester.ignore_tags = ignore;
+        requester.splitting_tags = splitting;
+        requester.non_splitting_tags = non_splitting;
+        Ok(Some(requester))
+    }
+
+    /// Handle empty texts with `policy`, [`EmptyTextPolicy::SendAsIs`] by default. Only
+    /// awaiting the requester applies the policy, not [`send_raw`](Self::send_raw).
+    pub fn empty_text_policy(&mut self, policy: EmptyTextPolicy) -> &mut Self {
+        self.options.empty_texts = policy;
+        self
+    }
+
+    fn send_texts(&self) -> ResponseFuture<'a, TranslateTextResp> {
+        let client = self.options.apply(&self.client);
+        let chunks = self.chunks(MAX_TEXTS);
+        let formality = self
+            .options
+            .validate_formality
+            .then(|| (self.target_lang.clone(), self.formality.clone()));
+
+        let fut = async move {
+            let mut chunks = chunks?;
+            let client = client?;
+            if let Some((target_lang, formality)) = formality {
+                client
+                    .check_formality(&target_lang, formality.as_ref())
+                    .await?;
+            }
+            if chunks.len() == 1 {
+                let (body, characters) = chunks.pop().expect("one chunk");
+                return translate(&client, body, characters).await;
+            }
+            // the timeout and deadline apply to all chunks together
+            client.bounded(translate_chunks(&client, chunks)).await
+        };
+
+        ResponseFuture::new(Box::pin(fut))
+    }
+
+    /// Send the request and return the response along with its status, `x-trace-id` header
+    /// and elapsed time, see [`ResponseMeta::capture`](crate::ResponseMeta::capture)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let (resp, meta) = deepl
+    ///     .translate_text("Hello World", Lang::DE)
+    ///     .send_with_meta()
+    ///     .await?;
+    /// log::debug!("translated in {:?}, trace id {:?}", meta.elapsed, meta.trace_id);
+    /// ```
+    pub fn send_with_meta(&self) -> ResponseFuture<'a, (TranslateTextResp, crate::ResponseMeta)> {
+        ResponseFuture::new(Box::pin(crate::ResponseMeta::capture(self.send())))
+    }
+
+    /// Send the same request as awaiting the requester, but return the response without
+    /// decoding it. Error responses are not turned into [`Error`] either, see
+    /// [`RawResponse::error_for_status`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let resp = deepl
+    ///     .translate_text("Hello World", Lang::DE)
+    ///     .send_raw()
+    ///     .await?;
+    /// println!("{}: {}", resp.status(), resp.text().await?);
+    /// ```
+    ///
+    /// Unlike awaiting the requester, more than 50 texts are sent in a single request, which
+    /// DeepL rejects.
+    pub fn send_raw(&self) -> ResponseFuture<'a, RawResponse> {
+        let client = self.options.apply(&self.client);
+        let obj = self.body();
+        let characters = characters(&self.text);
+
+        let fut = async move {
+            let obj = obj?;
+            let client = client?;
+            client
+                .call("translate_text", Some(characters), async {
+                    post_translate(&client, obj).await.map(RawResponse::new)
+                })
+                .await
+        };
+
+        ResponseFuture::new(Box::pin(fut))
+    }
+}
+
+/// Texts accepted by [`DeepLApi::translate_text`]: a single string, or a collection of strings
+/// translated in one request. Use [`DeepLApi::translate_texts`] for any iterator.
+pub trait ToTranslatable {
+    fn to_translatable(self) -> Vec<String>;
+}
+
+macro_rules! impl_translatable {
+    (single: $($ty:ty),*; many: $($many:ty),* $(,)?) => {
+        $(
+            impl ToTranslatable for $ty {
+                fn to_translatable(self) -> Vec<String> {
+                    vec![self.to_string()]
+                }
+            }
+        )*
+        $(
+            impl ToTranslatable for $many {
+                fn to_translatable(self) -> Vec<String> {
+                    self.into_iter().map(|text| text.to_string()).collect()
+                }
+            }
+        )*
+    };
+}
+
+impl_translatable! {
+    single: &str, String, &String, std::borrow::Cow<'_, str>;
+    many: Vec<String>, Vec<&str>, &Vec<String>, &Vec<&str>, &[String], &[&str],
+}
+
+impl<const N: usize> ToTranslatable for [String; N] {
+    fn to_translatable(self) -> Vec<String> {
+        self.into()
+    }
+}
+
+impl<const N: usize> ToTranslatable for [&str; N] {
+    fn to_translatable(self) -> Vec<String> {
+        self.map(String::from).into()
+    }
+}
+
+fn characters(texts: &[String]) -> usize {
+    texts.iter().map(|text| text.chars().count()).sum()
+}
+
+/// Estimate the characters DeepL bills for translating the texts as plain text, i.e. the number
+/// of Unicode scalar values. Whitespace counts, combining characters and each code point of an
+/// emoji count on their own. The `context` of a request isn't billed.
+///
+/// # Example
+///
+/// ```rust
+/// assert_eq!(deepl::estimate_characters(&["Hello", "你好"]), 7);
+/// ```
+pub fn estimate_characters<S: AsRef<str>>(texts: &[S]) -> u64 {
+    texts
+        .iter()
+        .map(|text| text.as_ref().chars().count() as u64)
+        .sum()
+}
+
+/// Characters of the text outside of markup tags, which aren't billed with tag handling
+fn characters_without_tags(text: &str) -> u64 {
+    let mut in_tag = false;
+    let mut count = 0;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => count += 1,
+            _ => {}
+        }
+    }
+    count
+}
+
+async fn translate(client: &DeepLApi, body: Body, characters: usize) -> Result<TranslateTextResp> {
+    client
+        .call("translate_text", Some(characters), async {
+            let response = post_translate(client, body).await?;
+
+            if !response.status().is_success() {
+                return super::extract_deepl_error(response).await;
+            }
+
+            let response: TranslateTextResp = response.json().await.map_err(|err| {
+                Error::InvalidResponse(format!("convert json bytes to Rust type: {err}"))
+            })?;
+
+            Ok(response)
+        })
+        .await
+}
+
+/// The parameters as `application/x-www-form-urlencoded`: every text is a `text` parameter, tag
+/// lists are joined by commas and booleans are `1` or `0`
+fn form_body(body: &serde_json::Value) -> String {
+    use serde_json::Value;
+
+    let mut form = form_urlencoded::Serializer::new(String::new());
+    for (name, value) in body.as_object().into_iter().flatten() {
+        match value {
+            Value::Null => {}
+            Value::Array(texts) if name == "text" => {
+                for text in texts {
+                    form.append_pair(name, text.as_str().unwrap_or_default());
+                }
+            }
+            Value::Array(tags) => {
+                let tags: Vec<_> = tags.iter().filter_map(Value::as_str).collect();
+                form.append_pair(name, &tags.join(","));
+            }
+            Value::Bool(value) => {
+                form.append_pair(name, if *value { "1" } else { "0" });
+            }
+            Value::String(value) => {
+                form.append_pair(name, value);
+            }
+            value => {
+                form.append_pair(name, &value.to_string());
+            }
+        }
+    }
+    form.finish()
+}
+
+async fn post_translate(client: &DeepLApi, body: Body) -> Result<reqwest::Response> {
+    let url = client.get_endpoint("translate")?;
+    match body {
+        Body::Json(json) => client.post_json_bytes(url, json, true).await,
+        Body::Form(form) => client.post_form(url, form, true).await,
+    }
+}
+
+/// Translate the chunks with at most [`MAX_CONCURRENT_CHUNKS`] requests at once. After a chunk
+/// failed, no more chunks are sent, but the ones in flight are awaited.
+async fn translate_chunks(
+    client: &DeepLApi,
+    chunks: Vec<(Body, usize)>,
+) -> Result<TranslateTextResp> {
+    let results = run_chunks(client, chunks, MAX_CONCURRENT_CHUNKS, true).await;
+
+    let mut translations = Vec::new();
+    for (chunk, result) in results.into_iter().enumerate() {
+        match result.expect("every chunk before a failed one is sent") {
+            Ok(resp) => translations.extend(resp.translations),
+            Err(err) => {
+                return Err(Error::ChunkFailed {
+                    chunk,
+                    translated: Box::new(TranslateTextResp { translations }),
+                    source: Box::new(err),
+                })
+            }
+        }
+    }
+    Ok(TranslateTextResp { translations })
+}
+
+/// Send the chunks with at most `concurrency` requests at once, the results are in the order of
+/// the chunks. With `stop_on_error`, no more chunks are sent after one failed and the chunks
+/// never sent are `None`.
+async fn run_chunks(
+    client: &DeepLApi,
+    chunks: Vec<(Body, usize)>,
+    concurrency: usize,
+    stop_on_error: bool,
+) -> Vec<Option<Result<TranslateTextResp>>> {
+    let mut results: Vec<Option<Result<TranslateTextResp>>> =
+        std::iter::repeat_with(|| None).take(chunks.len()).collect();
+    let mut pending = chunks.into_iter().enumerate();
+    let mut running = Vec::new();
+    let mut failed = false;
+
+    poll_fn(|cx| loop {
+        while !(stop_on_error && failed) && running.len() < concurrency {
+            let Some((index, (body, characters))) = pending.next() else {
+                break;
+            };
+            let fut: ResponseFuture<'_, _> =
+                ResponseFuture::new(Box::pin(translate(client, body, characters)));
+            running.push((index, fut));
+        }
+        if running.is_empty() {
+            return Poll::Ready(());
+        }
+
+        let mut progressed = false;
+        running.retain_mut(|(index, fut)| match Pin::new(fut).poll(cx) {
+            Poll::Ready(result) => {
+                failed |= result.is_err();
+                results[*index] = Some(result);
+                progressed = true;
+                false
+            }
+            Poll::Pending => true,
+        });
+        if !progressed {
+            return Poll::Pending;
+        }
+    })
+    .await;
+
+    results
+}
+
+/// The sentences of a batch of [`DeepLApi::translate_many`], or its range of texts and error
+type BatchResult = std::result::Result<Vec<Sentence>, (Range<usize>, Error)>;
+
+/// Builder type for [`DeepLApi::translate_many`], resolving to one sentence per text
+#[derive(Debug)]
+pub struct TranslateManyRequester<'a> {
+    inner: TranslateRequester<'a>,
+    concurrency: usize,
+    batch_size: usize,
+}
+
+impl<'a> TranslateManyRequester<'a> {
+    /// Setter for `source_lang`
+    pub fn source_lang(&mut self, source_lang: impl IntoParam<Lang>) -> &mut Self {
+        self.inner.source_lang(source_lang);
+        self
+    }
+
+    /// Setter for `formality`
+    pub fn formality(&mut self, formality: impl IntoParam<Formality>) -> &mut Self {
+        self.inner.formality(formality);
+        self
+    }
+
+    /// Setter for `glossary_id`
+    pub fn glossary_id(&mut self, glossary_id: impl IntoParam<String>) -> &mut Self {
+        self.inner.glossary_id(glossary_id);
+        self
+    }
+
+    /// Number of requests sent at once, 4 by default
+    pub fn concurrency(&mut self, requests: usize) -> &mut Self {
+        self.concurrency = requests.max(1);
+        self
+    }
+
+    /// Number of texts per request, at most and by default 50
+    pub fn batch_size(&mut self, texts: usize) -> &mut Self {
+        self.batch_size = texts.clamp(1, MAX_TEXTS);
+        self
+    }
+
+    /// Send every batch, and return the result of each text in the order of the texts. The
+    /// texts of a failed batch share its error as [`Error::BatchFailed`].
+    pub fn continue_on_error(&self) -> ResponseFuture<'a, Vec<Result<Sentence>>> {
+        let fut = self.run(false);
+        ResponseFuture::new(Box::pin(async move {
+            let mut sentences = Vec::new();
+            for batch in fut.await? {
+                match batch {
+                    Ok(batch) => sentences.extend(batch.into_iter().map(Ok)),
+                    Err((range, err)) => {
+                        let source = Arc::new(err);
+                        sentences.extend(range.clone().map(|_| {
+                            Err(Error::BatchFailed {
+                                range: range.clone(),
+                                source: source.clone(),
+                            })
+                        }))
+                    }
+                }
+            }
+            Ok(sentences)
+        }))
+    }
+
+    /// Fail with [`Error::BatchFailed`] of the first failed batch, no more batches are sent
+    /// after a failure
+    fn send(&self) -> ResponseFuture<'a, Vec<Sentence>> {
+        let fut = self.run(true);
+        ResponseFuture::new(Box::pin(async move {
+            let mut sentences = Vec::new();
+            for batch in fut.await? {
+                match batch {
+                    Ok(batch) => sentences.extend(batch),
+                    Err((range, err)) => {
+                        return Err(Error::BatchFailed {
+                            range,
+                            source: Arc::new(err),
+                        })
+                    }
+                }
+            }
+            Ok(sentences)
+        }))
+    }
+
+    /// The result of each batch sent, in the order of the texts
+    fn run(&self, stop_on_error: bool) -> ResponseFuture<'a, Vec<BatchResult>> {
+        let client = self.inner.options.apply(&self.inner.client);
+        let chunks = self.inner.chunks(self.batch_size);
+        let (concurrency, batch_size, len) =
+            (self.concurrency, self.batch_size, self.inner.text.len());
+
+        let fut = async move {
+            let chunks = chunks?;
+            let client = client?;
+            let results = client
+                .bounded(async {
+                    Ok(run_chunks(&client, chunks, concurrency, stop_on_error).await)
+                })
+                .await?;
+
+            let mut batches = Vec::new();
+            for (index, result) in results.into_iter().enumerate() {
+                let range = index * batch_size..len.min((index + 1) * batch_size);
+                match result {
+                    Some(Ok(resp)) if resp.translations.len() == range.len() => {
+                        batches.push(Ok(resp.translations))
+                    }
+                    Some(Ok(resp)) => {
+                        let err = Error::InvalidResponse(format!(
+                            "expected {} translations, got {}",
+                            range.len(),
+                            resp.translations.len()
+                        ));
+                        batches.push(Err((range, err)));
+                    }
+                    Some(Err(err)) => batches.push(Err((range, err))),
+                    None => break,
+                }
+            }
+            Ok(batches)
+        };
+
+        ResponseFuture::new(Box::pin(fut))
+    }
+}
+
+impl<'a> IntoFuture for TranslateManyRequester<'a> {
+    type Output = Result<Vec<Sentence>>;
+    type IntoFuture = ResponseFuture<'a, Vec<Sentence>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        self.send()
+    }
+}
+
+impl<'a> IntoFuture for &mut TranslateManyRequester<'a> {
+    type Output = Result<Vec<Sentence>>;
+    type IntoFuture = ResponseFuture<'a, Vec<Sentence>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        self.send()
+    }
+}
+
+/// What a [`TranslateStream`] does after a batch failed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StreamErrorPolicy {
+    /// Yield the error and end the stream
+    #[default]
+    Terminate,
+    /// Yield the error for each text of the batch, as [`Error::BatchFailed`], and go on
+    Skip,
+}
+
+/// Stream of translations returned by [`DeepLApi::translate_stream`], one sentence per text
+/// in the order of the input
+#[derive(Debug)]
+pub struct TranslateStream<'a, S> {
+    client: &'a DeepLApi,
+    input: Pin<Box<S>>,
+    target_lang: Lang,
+    options: TranslateOptions,
+    batch_size: usize,
+    linger: Duration,
+    concurrency: usize,
+    policy: StreamErrorPolicy,
+    buffer: Vec<String>,
+    /// Started by the first text of the buffer
+    timer: Option<Pin<Box<tokio::time::Sleep>>>,
+    input_done: bool,
+    in_flight: VecDeque<StreamBatch<'a>>,
+    ready: VecDeque<Result<Sentence>>,
+    /// Index of the next text to send
+    sent: usize,
+    done: bool,
+}
+
+#[derive(Debug)]
+struct StreamBatch<'a> {
+    range: Range<usize>,
+    fut: ResponseFuture<'a, TranslateTextResp>,
+    result: Option<Result<TranslateTextResp>>,
+}
+
+impl<S> TranslateStream<'_, S> {
+    /// Number of texts per request, at most and by default 50
+    pub fn batch_size(mut self, texts: usize) -> Self {
+        self.batch_size = texts.clamp(1, MAX_TEXTS);
+        self
+    }
+
+    /// How long a text waits for more texts to fill its batch, 100 ms by default
+    pub fn linger(mut self, linger: Duration) -> Self {
+        self.linger = linger;
+        self
+    }
+
+    /// Number of requests sent at once, 4 by default. No more texts are read from the input
+    /// while all requests are in flight and the next batch is full.
+    pub fn concurrency(mut self, requests: usize) -> Self {
+        self.concurrency = requests.max(1);
+        self
+    }
+
+    /// What to do after a batch failed, [`StreamErrorPolicy::Terminate`] by default
+    pub fn on_error(mut self, policy: StreamErrorPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    fn flush(&mut self) {
+        let texts = std::mem::take(&mut self.buffer);
+        self.timer = None;
+        let range = self.sent..self.sent + texts.len();
+        self.sent = range.end;
+        let fut = self
+            .client
+            .translate_with(texts, self.target_lang.clone(), &self.options)
+            .into_future();
+        self.in_flight.push_back(StreamBatch {
+            range,
+            fut,
+            result: None,
+        });
+    }
+
+    fn finish(&mut self, batch: StreamBatch<'_>) {
+        let StreamBatch { range, result, .. } = batch;
+        let err = match result.expect("the batch is finished") {
+            Ok(resp) if resp.translations.len() == range.len() => {
+                self.ready.extend(resp.translations.into_iter().map(Ok));
+                return;
+            }
+            Ok(resp) => Error::InvalidResponse(format!(
+                "expected {} translations, got {}",
+                range.len(),
+                resp.translations.len()
+            )),
+            Err(err) => err,
+        };
+
+        let source = Arc::new(err);
+        match self.policy {
+            StreamErrorPolicy::Terminate => {
+                self.ready
+                    .push_back(Err(Error::BatchFailed { range, source }));
+                self.done = true;
+                self.in_flight.clear();
+                self.buffer.clear();
+                self.timer = None;
+            }
+            StreamErrorPolicy::Skip => {
+                self.ready.extend(range.clone().map(|_| {
+                    Err(Error::BatchFailed {
+                        range: range.clone(),
+                        source: source.clone(),
+                    })
+                }));
+            }
+        }
+    }
+}
+
+impl<S: Stream<Item = String>> Stream for TranslateStream<'_, S> {
+    type Item = Result<Sentence>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(item) = this.ready.pop_front() {
+                return Poll::Ready(Some(item));
+            }
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            while !this.input_done && this.buffer.len() < this.batch_size {
+                match this.input.as_mut().poll_next(cx) {
+                    Poll::Ready(Some(text)) => {
+                        if this.buffer.is_empty() {
+                            this.timer = Some(Box::pin(tokio::time::sleep(this.linger)));
+                        }
+                        this.buffer.push(text);
+                    }
+                    Poll::Ready(None) => this.input_done = true,
+                    Poll::Pending => break,
+                }
+            }
+
+            // send the batch once full, lingered long enough, or at the end of the input
+            if !this.buffer.is_empty() && this.in_flight.len() < this.concurrency {
+                let due = this.buffer.len() >= this.batch_size
+                    || this.input_done
+                    || this
+                        .timer
+                        .as_mut()
+                        .is_some_and(|timer| timer.as_mut().poll(cx).is_ready());
+                if due {
+                    this.flush();
+                    continue;
+                }
+            }
+
+            for batch in &mut this.in_flight {
+                if batch.result.is_none() {
+                    if let Poll::Ready(result) = Pin::new(&mut batch.fut).poll(cx) {
+                        batch.result = Some(result);
+                    }
+                }
+            }
+            if this
+                .in_flight
+                .front()
+                .is_some_and(|batch| batch.result.is_some())
+            {
+                let batch = this.in_flight.pop_front().expect("a finished batch");
+                this.finish(batch);
+                continue;
+            }
+
+            if this.input_done && this.buffer.is_empty() && this.in_flight.is_empty() {
+                return Poll::Ready(None);
+            }
+            return Poll::Pending;
+        }
+    }
+}
+
+/// Builder type for [`DeepLApi::translate_html`], resolving to the translated HTML
+#[derive(Debug)]
+pub struct TranslateHtmlRequester<'a> {
+    inner: TranslateRequester<'a>,
+    strict: bool,
+}
+
+impl<'a> TranslateHtmlRequester<'a> {
+    /// Setter for `source_lang`
+    pub fn source_lang(&mut self, source_lang: impl IntoParam<Lang>) -> &mut Self {
+        self.inner.source_lang(source_lang);
+        self
+    }
+
+    /// Setter for `formality`
+    pub fn formality(&mut self, formality: impl IntoParam<Formality>) -> &mut Self {
+        self.inner.formality(formality);
+        self
+    }
+
+    /// Setter for `glossary_id`
+    pub fn glossary_id(&mut self, glossary_id: impl IntoParam<String>) -> &mut Self {
+        self.inner.glossary_id(glossary_id);
+        self
+    }
+
+    /// Fail with [`Error::InvalidInput`] before sending anything if the input has no HTML tag
+    pub fn strict(&mut self) -> &mut Self {
+        self.strict = true;
+        self
+    }
+
+    fn send(&self) -> ResponseFuture<'a, String> {
+        if self.strict && !self.inner.text.iter().any(|html| has_tag(html)) {
+            let err = Error::InvalidInput("the input has no HTML tag".to_string());
+            return ResponseFuture::new(Box::pin(std::future::ready(Err(err))));
+        }
+        self.inner.collect_string()
+    }
+}
+
+/// Whether the text contains something like `<p>`, `</p>` or `<!-- -->`
+fn has_tag(text: &str) -> bool {
+    text.match_indices('<').any(|(i, _)| {
+        let rest = &text[i + 1..];
+        rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '!')
+            && rest.contains('>')
+    })
+}
+
+impl<'a> IntoFuture for TranslateHtmlRequester<'a> {
+    type Output = Result<String>;
+    type IntoFuture = ResponseFuture<'a, String>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        self.send()
+    }
+}
+
+impl<'a> IntoFuture for &mut TranslateHtmlRequester<'a> {
+    type Output = Result<String>;
+    type IntoFuture = ResponseFuture<'a, String>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        self.send()
+    }
+}
+
+/// Characters of the input sent by [`DeepLApi::detect_language`] unless configured otherwise
+pub const DETECT_LANGUAGE_PREFIX: usize = 200;
+
+/// Builder type for [`DeepLApi::detect_language`], resolving to the detected source language
+#[derive(Debug)]
+pub struct DetectLanguageRequester<'a> {
+    client: &'a DeepLApi,
+    text: String,
+    prefix: usize,
+}
+
+impl<'a> DetectLanguageRequester<'a> {
+    /// Number of characters sent for detection, [`DETECT_LANGUAGE_PREFIX`] by default
+    pub fn prefix(&mut self, chars: usize) -> &mut Self {
+        self.prefix = chars;
+        self
+    }
+
+    fn send(&self) -> ResponseFuture<'a, Lang> {
+        let text = truncate_chars(&self.text, self.prefix).to_string();
+        if text.trim().is_empty() {
+            let err = Error::InvalidInput("no text to detect the language of".to_string());
+            return ResponseFuture::new(Box::pin(std::future::ready(Err(err))));
+        }
+        let fut = self.client.translate_text(text, Lang::DE).send();
+        ResponseFuture::new(Box::pin(async move {
+            Ok(fut.await?.into_single()?.detected_source_language)
+        }))
+    }
+}
+
+/// The longest prefix of `text` with at most `chars` characters
+fn truncate_chars(text: &str, chars: usize) -> &str {
+    match text.char_indices().nth(chars) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+impl<'a> IntoFuture for DetectLanguageRequester<'a> {
+    type Output = Result<Lang>;
+    type IntoFuture = ResponseFuture<'a, Lang>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        self.send()
+    }
+}
+
+impl<'a> IntoFuture for &mut DetectLanguageRequester<'a> {
+    type Output = Result<Lang>;
+    type IntoFuture = ResponseFuture<'a, Lang>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        self.send()
+    }
+}
+
+impl DeepLApi {
+    /// Translate the given text with specific target language.
+    ///
+    /// # Error
+    ///
+    /// Return [`Error`] if the http request fail. Without sending anything, return
+    /// [`Error::EmptyInput`] if there is no text to translate, and [`Error::RequestTooLarge`]
+    /// if the request exceeds the 128 KiB limit of DeepL.
+    ///
+    /// # Example
+    ///
+    /// * Simple translation
+    ///
+    /// ```rust
+    /// use deepl::{DeepLApi, Lang};
+    ///
+    /// let key = std::env::var("DEEPL_API_KEY").unwrap();
+    /// let deepl = DeepLApi::with(&key).new();
+    ///
+    /// let response = deepl.translate_text("Hello World", Lang::ZH).await.unwrap();
+    /// assert!(!response.translations.is_empty());
+    /// ```
+    ///
+    /// * Translation with XML tag enabled
+    ///
+    /// ```rust
+    /// use deepl::{DeepLApi, Lang};
+    ///
+    /// let key = std::env::var("DEEPL_API_KEY").unwrap();
+    /// let deepl = DeepLApi::with(&key).new();
+    ///
+    /// let str = "Hello World <keep>This will stay exactly the way it was</keep>";
+    /// let response = deepl
+    ///     .translate_text(str, Lang::DE)
+    ///     .source_lang(Lang::EN)
+    ///     .ignore_tags(["keep"])
+    ///     .tag_handling(TagHandling::Xml)
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// let translated_results = response.translations;
+    /// let should = "Hallo Welt <keep>This will stay exactly the way it was</keep>";
+    /// assert_eq!(translated_results[0].text, should);
+    /// ```
+    pub fn translate_text(
+        &self,
+        text: impl ToTranslatable,
+        target_lang: impl IntoParam<Lang>,
+    ) -> TranslateRequester<'_> {
+        let text = text.to_translatable();
+        match target_lang.into_param() {
+            Ok(target_lang) => TranslateRequester::new(self, text, target_lang),
+            Err(err) => {
+                // the language is only reported by sending the request
+                let mut requester = TranslateRequester::new(self, text, Lang::EN);
+                requester.options.set_invalid_lang(err);
+                requester
+            }
+        }
+    }
+
+    /// Translate the texts with the given options, see [`TranslateOptions`]
+    pub fn translate_with(
+        &self,
+        text: impl ToTranslatable,
+        target_lang: impl IntoParam<Lang>,
+        options: &TranslateOptions,
+    ) -> TranslateRequester<'_> {
+        let mut requester = self.translate_text(text, target_lang);
+        requester.with_options(options);
+        requester
+    }
+
+    /// Translate a single text and return the translated text. Use
+    /// [`collect_string`](TranslateRequester::collect_string) to set options first.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let text = deepl.translate_text_to_string("Hello World", Lang::DE).await?;
+    /// assert_eq!(text, "Hallo Welt");
+    /// ```
+    pub fn translate_text_to_string(
+        &self,
+        text: impl ToTranslatable,
+        target_lang: impl IntoParam<Lang>,
+    ) -> ResponseFuture<'_, String> {
+        self.translate_text(text, target_lang).collect_string()
+    }
+
+    /// Translate HTML and return the translated HTML. Elements marked with `translate="no"`
+    /// are kept as is.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let html = deepl
+    ///     .translate_html(r#"<p>Run <code translate="no">cargo build</code></p>"#, Lang::DE)
+    ///     .source_lang(Lang::EN)
+    ///     .await?;
+    /// ```
+    pub fn translate_html(
+        &self,
+        html: impl Into<String>,
+        target_lang: impl IntoParam<Lang>,
+    ) -> TranslateHtmlRequester<'_> {
+        let mut inner = self.translate_text(html.into(), target_lang);
+        inner.tag_handling(TagHandling::Html);
+        TranslateHtmlRequester {
+            inner,
+            strict: false,
+        }
+    }
+
+    /// Translate an HTML file and write the result to `output`, see
+    /// [`translate_html`](Self::translate_html). A file larger than a request allows is
+    /// translated in parts cut before a tag. The output is written to a temporary file next to
+    /// it first, so it is either complete or left untouched.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// deepl
+    ///     .translate_html_file("index.html", "index.de.html", Lang::DE, &TranslateOptions::default())
+    ///     .await?;
+    /// ```
+    #[cfg(feature = "tokio-fs")]
+    pub async fn translate_html_file(
+        &self,
+        input: impl AsRef<std::path::Path>,
+        output: impl AsRef<std::path::Path>,
+        target_lang: Lang,
+        options: &TranslateOptions,
+    ) -> Result<()> {
+        let (input, output) = (input.as_ref(), output.as_ref());
+        let content = tokio::fs::read(input)
+            .await
+            .map_err(|source| Error::ReadFileError {
+                path: input.to_path_buf(),
+                source,
+            })?;
+        let html = String::from_utf8(content).map_err(|err| {
+            Error::InvalidInput(format!("{} is not valid UTF-8: {err}", input.display()))
+        })?;
+
+        let mut translated = String::with_capacity(html.len());
+        for part in split_html(&html, HTML_PART_LEN) {
+            if part.trim().is_empty() {
+                translated.push_str(part);
+                continue;
+            }
+            let mut requester = self.translate_with(part, target_lang.clone(), options);
+            requester.tag_handling(TagHandling::Html);
+            translated.push_str(&requester.collect_string().await?);
+        }
+
+        let mut partial = output.as_os_str().to_owned();
+        partial.push(".part");
+        let partial = std::path::PathBuf::from(partial);
+        let written = match tokio::fs::write(&partial, translated).await {
+            Ok(()) => tokio::fs::rename(&partial, output).await,
+            Err(err) => Err(err),
+        };
+        written.map_err(|source| {
+            let _ = std::fs::remove_file(&partial);
+            Error::WriteFileError {
+                path: output.to_path_buf(),
+                source,
+            }
+        })
+    }
+
+    /// Translate XML with the tags configured by [`XmlTagHandling`]. An invalid tag name fails
+    /// the request with [`Error::InvalidInput`] before sending.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let tags = XmlTagHandling {
+    ///     ignore: vec!["keep".to_string()],
+    ///     ..Default::default()
+    /// };
+    /// let response = deepl
+    ///     .translate_xml("Hello <keep>World</keep>", Lang::DE, tags)
+    ///     .await?;
+    /// ```
+    pub fn translate_xml(
+        &self,
+        text: impl ToTranslatable,
+        target_lang: impl IntoParam<Lang>,
+        tags: XmlTagHandling,
+    ) -> TranslateRequester<'_> {
+        let mut requester = self.translate_text(text, target_lang);
+        requester.tag_handling(TagHandling::Xml);
+        if !tags.ignore.is_empty() {
+            requester.ignore_tags(tags.ignore);
+        }
+        if !tags.splitting.is_empty() {
+            requester.splitting_tags(tags.splitting);
+        }
+        if !tags.non_splitting.is_empty() {
+            requester.non_splitting_tags(tags.non_splitting);
+        }
+        if !tags.outline_detection {
+            requester.outline_detection(false);
+        }
+        requester
+    }
+
+    /// Translate several texts in one request, see [`translate_text`](Self::translate_text).
+    /// The translations are in the order of the texts.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let texts: HashSet<String> = ...;
+    /// let response = deepl.translate_texts(&texts, Lang::DE).await?;
+    /// ```
+    pub fn translate_texts<S: ToString>(
+        &self,
+        texts: impl IntoIterator<Item = S>,
+        target_lang: impl IntoParam<Lang>,
+    ) -> TranslateRequester<'_> {
+        let texts: Vec<String> = texts.into_iter().map(|text| text.to_string()).collect();
+        self.translate_text(texts, target_lang)
+    }
+
+    /// Translate the values of a map, keeping the keys. Every distinct value is translated
+    /// once, empty values are kept as is. The values are sent sorted in chunks of 50 texts, so
+    /// the same map always results in the same requests.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let messages: HashMap<&str, String> = load_messages();
+    /// let translated = deepl
+    ///     .translate_map(&messages, Lang::DE, &TranslateOptions::default())
+    ///     .await?;
+    /// ```
+    pub async fn translate_map<K: Eq + Hash + Clone>(
+        &self,
+        map: &HashMap<K, String>,
+        target_lang: Lang,
+        options: &TranslateOptions,
+    ) -> Result<HashMap<K, String>> {
+        let texts: BTreeSet<&str> = map
+            .values()
+            .map(String::as_str)
+            .filter(|text| !text.is_empty())
+            .collect();
+        let translated: HashMap<&str, String> = match texts.is_empty() {
+            true => HashMap::new(),
+            false => {
+                let resp = self
+                    .translate_with(
+                        texts.iter().copied().collect::<Vec<_>>(),
+                        target_lang,
+                        options,
+                    )
+                    .await?;
+                resp.check_count(texts.len())?;
+                texts.into_iter().zip(resp.into_texts()).collect()
+            }
+        };
+
+        Ok(map
+            .iter()
+            .map(|(key, text)| {
+                let text = translated.get(text.as_str()).unwrap_or(text);
+                (key.clone(), text.clone())
+            })
+            .collect())
+    }
+
+    /// Detect the language of a text by translating its first [`DETECT_LANGUAGE_PREFIX`]
+    /// characters, see [`DetectLanguageRequester::prefix`]. DeepL has no detection endpoint,
+    /// so the sent characters are billed like any translation. If they are empty or only
+    /// whitespace, fail with [`Error::InvalidInput`] without sending them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let lang = deepl.detect_language("Bonjour tout le monde").await?;
+    /// assert_eq!(lang, Lang::FR);
+    /// ```
+    pub fn detect_language(&self, text: impl Into<String>) -> DetectLanguageRequester<'_> {
+        DetectLanguageRequester {
+            client: self,
+            text: text.into(),
+            prefix: DETECT_LANGUAGE_PREFIX,
+        }
+    }
+
+    /// Translate any number of independent texts in batches of at most 50 texts, a few
+    /// requests at once, see [`TranslateManyRequester`]. The sentences are in the order of the
+    /// texts. An empty list fails with [`Error::EmptyInput`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let sentences = deepl
+    ///     .translate_many(texts, Lang::DE)
+    ///     .concurrency(8)
+    ///     .await?;
+    ///
+    /// // or keep going after a failed batch
+    /// let results = deepl.translate_many(texts, Lang::DE).continue_on_error().await?;
+    /// ```
+    pub fn translate_many(
+        &self,
+        texts: Vec<String>,
+        target_lang: impl IntoParam<Lang>,
+    ) -> TranslateManyRequester<'_> {
+        TranslateManyRequester {
+            inner: self.translate_text(texts, target_lang),
+            concurrency: MAX_CONCURRENT_CHUNKS,
+            batch_size: MAX_TEXTS,
+        }
+    }
+
+    /// Translate a stream of texts, e.g. messages from a channel, without buffering all of
+    /// them. Texts are sent in batches once a batch is full or its first text waited for the
+    /// [`linger`](TranslateStream::linger) duration, a few requests at once.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let (tx, rx) = tokio::sync::mpsc::channel(100);
+    /// let mut translated = deepl
+    ///     .translate_stream(ReceiverStream::new(rx), Lang::DE, TranslateOptions::default())
+    ///     .batch_size(20)
+    ///     .linger(Duration::from_millis(50));
+    /// while let Some(sentence) = translated.next().await {
+    ///     println!("{}", sentence?.text);
+    /// }
+    /// ```
+    pub fn translate_stream<S: Stream<Item = String>>(
+        &self,
+        texts: S,
+        target_lang: Lang,
+        options: TranslateOptions,
+    ) -> TranslateStream<'_, S> {
+        TranslateStream {
+            client: self,
+            input: Box::pin(texts),
+            target_lang,
+            options,
+            batch_size: MAX_TEXTS,
+            linger: Duration::from_millis(100),
+            concurrency: MAX_CONCURRENT_CHUNKS,
+            policy: StreamErrorPolicy::default(),
+            buffer: Vec::new(),
+            timer: None,
+            input_done: false,
+            in_flight: VecDeque::new(),
+            ready: VecDeque::new(),
+            sent: 0,
+            done: false,
+        }
+    }
+}
+
+#[cfg(test)]
+impl Body {
+    fn json(&self) -> serde_json::Value {
+        match self {
+            Self::Json(json) => serde_json::from_slice(json).unwrap(),
+            Self::Form(_) => panic!("not a JSON body"),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_translate_text() {
+    let (api, server) = crate::testing::mock_api().await;
+    let response = api.translate_text("Hello World", Lang::ZH).await.unwrap();
+
+    assert!(!response.translations.is_empty());
+
+    let translated_results = response.translations;
+    assert_eq!(translated_results[0].text, "[ZH] Hello World");
+    assert_eq!(translated_results[0].detected_source_language, Lang::EN);
+    server.assert_request_count(1);
+}
+
+#[tokio::test]
+async fn test_advanced_translate() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    let response = api.translate_text(
+            "Hello World <keep additionalarg=\"test0\">This will stay exactly the way it was</keep>",
+            Lang::DE
+        )
+        .source_lang(Lang::EN)
+        .ignore_tags(vec!["keep".to_string()])
+        .tag_handling(TagHandling::Xml)
+        .await
+        .unwrap();
+
+    assert!(!response.translations.is_empty());
+
+    let translated_results = response.translations;
+    assert_eq!(
+        translated_results[0].text,
+        "[DE] Hello World <keep additionalarg=\"test0\">This will stay exactly the way it was</keep>"
+    );
+    assert_eq!(translated_results[0].detected_source_language, Lang::EN);
+
+    let body = server.requests()[0].json().unwrap();
+    assert_eq!(body["ignore_tags"], json!(["keep"]));
+    assert_eq!(body["tag_handling"], "xml");
+    assert_eq!(body["source_lang"], "EN");
+}
+
+#[tokio::test]
+async fn test_advanced_translator_html() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    let response = api
+        .translate_text(
+            "Hello World <keep translate=\"no\">This will stay exactly the way it was</keep>",
+            Lang::DE,
+        )
+        .tag_handling(TagHandling::Html)
+        .await
+        .unwrap();
+
+    assert!(!response.translations.is_empty());
+
+    let translated_results = response.translations;
+    assert_eq!(
+        translated_results[0].text,
+        "[DE] Hello World <keep translate=\"no\">This will stay exactly the way it was</keep>"
+    );
+    assert_eq!(translated_results[0].detected_source_language, Lang::EN);
+
+    let body = server.requests()[0].json().unwrap();
+    assert_eq!(body["tag_handling"], "html");
+}
+
+#[tokio::test]
+#[ignore = "needs DEEPL_API_KEY"]
+async fn test_billed_characters() {
+    let key = std::env::var("DEEPL_API_KEY").unwrap();
+    let api = DeepLApi::with(&key).new();
+
+    let response = api.translate_text("Hello World", Lang::DE).await.unwrap();
+    assert_eq!(response.total_billed_characters(), None);
+
+    let response = api
+        .translate_text("Hello World", Lang::DE)
+        .show_billed_characters(true)
+        .await
+        .unwrap();
+    assert_eq!(response.translations[0].billed_characters, Some(11));
+
+    let texts = ["Hello World", "你好，世界", "Café 👍🏽", "e\u{301}te\u{301}"];
+    let mut requester = api.translate_text(texts, Lang::DE);
+    requester.show_billed_characters(true);
+    let response = requester.clone().await.unwrap();
+    assert_eq!(
+        response.total_billed_characters(),
+        Some(requester.estimated_characters())
+    );
+}
+
+#[tokio::test]
+#[ignore = "needs DEEPL_API_KEY"]
+async fn test_preserve_formatting() {
+    let key = std::env::var("DEEPL_API_KEY").unwrap();
+    let api = DeepLApi::with(&key).new();
+
+    let response = api
+        .translate_text("hello world", Lang::DE)
+        .preserve_formatting(PreserveFormatting::Preserve)
+        .await
+        .unwrap();
+    assert!(response.translations[0].text.starts_with('h'));
+}
+
+#[tokio::test]
+async fn test_preserve_formatting_body() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    for (preserve, sent) in [
+        (PreserveFormatting::Preserve, true),
+        (PreserveFormatting::DontPreserve, false),
+    ] {
+        api.translate_text("hello world", Lang::DE)
+            .preserve_formatting(preserve)
+            .await
+            .unwrap();
+        let body = server.requests().last().unwrap().json().unwrap();
+        assert_eq!(body["preserve_formatting"], json!(sent));
+    }
+
+    let mut requester = api.translate_text("Hello", Lang::DE);
+    assert_eq!(
+        requester.body().unwrap().json()["preserve_formatting"],
+        json!(null)
+    );
+    requester.preserve_formatting(PreserveFormatting::Preserve);
+    assert_eq!(
+        requester.body().unwrap().json()["preserve_formatting"],
+        json!(true)
+    );
+    requester.preserve_formatting(PreserveFormatting::DontPreserve);
+    assert_eq!(
+        requester.body().unwrap().json()["preserve_formatting"],
+        json!(false)
+    );
+}
+
+#[tokio::test]
+async fn test_setter_arguments() {
+    let (api, _server) = crate::testing::mock_api().await;
+
+    let context = String::from("A greeting");
+    let mut requester = api.translate_text("Hello", Lang::DE);
+    requester
+        .context(&context)
+        .glossary_id("def3a26b")
+        .ignore_tags(["keep"])
+        .splitting_tags(vec!["p".to_string()])
+        .non_splitting_tags(Vec::<&str>::new())
+        .source_lang(Lang::EN);
+    let body = requester.body().unwrap().json();
+    assert_eq!(body["context"], "A greeting");
+    assert_eq!(body["glossary_id"], "def3a26b");
+    assert_eq!(body["ignore_tags"], json!(["keep"]));
+    assert_eq!(body["splitting_tags"], json!(["p"]));
+    assert_eq!(body["non_splitting_tags"], json!([]));
+    assert_eq!(body["source_lang"], "EN");
+}
+
+#[tokio::test]
+async fn test_formality() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    // can specify a formality
+    let text = "How are you?";
+    let src = Lang::EN;
+    let trg = Lang::ES;
+    let more = Formality::More;
+
+    let response = api
+        .translate_text(text, trg)
+        .source_lang(src)
+        .formality(more)
+        .await
+        .unwrap();
+    assert!(!response.translations.is_empty());
+
+    // response ok, despite target lang not supporting formality
+    let text = "¿Cómo estás?";
+    let src = Lang::ES;
+    let trg = Lang::EN_US;
+    let less = Formality::PreferLess;
+
+    let response = api
+        .translate_text(text, trg)
+        .source_lang(src)
+        .formality(less)
+        .await
+        .unwrap();
+    assert!(!response.translations.is_empty());
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].json().unwrap()["formality"], "more");
+    assert_eq!(requests[1].json().unwrap()["formality"], "prefer_less");
+}
+
+#[tokio::test]
+async fn test_translate_text_offline() {
+    let server = crate::testing::FakeServer::start().await;
+    let api = server.api();
+
+    let response = api
+        .translate_text("Hello World", Lang::ZH)
+        .source_lang(Lang::EN)
+        .await
+        .unwrap();
+
+    let translated_results = response.translations;
+    assert_eq!(translated_results[0].text, "[ZH] Hello World");
+    assert_eq!(translated_results[0].detected_source_language, Lang::EN);
+}
+
+#[tokio::test]
+async fn test_billed_characters_offline() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    let response = api.translate_text("Hello", Lang::DE).await.unwrap();
+    assert_eq!(response.translations[0].billed_characters, None);
+    assert_eq!(response.total_billed_characters(), None);
+
+    let response = TranslateRequester::new(
+        &api,
+        vec!["Hello".to_string(), "World!".to_string()],
+        Lang::DE,
+    )
+    .show_billed_characters(true)
+    .await
+    .unwrap();
+    assert_eq!(response.translations[0].billed_characters, Some(5));
+    assert_eq!(response.total_billed_characters(), Some(11));
+
+    let body = server.requests().last().unwrap().json().unwrap();
+    assert_eq!(body["show_billed_characters"], true);
+}
+
+#[tokio::test]
+async fn test_chunked_translate() {
+    use crate::testing::{FakeServer, Fault, Route};
+
+    let server = FakeServer::start().await;
+    let api = server.api();
+    let texts: Vec<String> = (0..120).map(|i| format!("text {i}")).collect();
+
+    let response = TranslateRequester::new(&api, texts.clone(), Lang::DE)
+        .await
+        .unwrap();
+    let translated: Vec<&str> = response
+        .translations
+        .iter()
+        .map(|t| t.text.as_str())
+        .collect();
+    let expected: Vec<String> = texts.iter().map(|text| format!("[DE] {text}")).collect();
+    assert_eq!(translated, expected);
+
+    let mut chunks: Vec<Vec<String>> = server
+        .requests()
+        .iter()
+        .map(|req| serde_json::from_value(req.json().unwrap()["text"].clone()).unwrap())
+        .collect();
+    chunks.sort_by_key(|chunk| chunk[0][5..].parse::<usize>().unwrap());
+    assert_eq!(chunks, texts.chunks(50).collect::<Vec<_>>());
+
+    // one request at a time, so the chunks are sent in order
+    let api = DeepLApi::with("key")
+        .endpoint(server.endpoint())
+        .max_concurrent_requests(1)
+        .new();
+    server.inject(Route::Translate, Fault::status(400).after(1).times(1));
+    let err = TranslateRequester::new(&api, texts, Lang::DE)
+        .await
+        .unwrap_err();
+    let Error::ChunkFailed {
+        chunk,
+        translated,
+        source,
+    } = &err
+    else {
+        panic!("expected a failed chunk: {err:?}");
+    };
+    assert_eq!(*chunk, 1);
+    assert_eq!(translated.translations.len(), 50);
+    assert_eq!(translated.translations[49].text, "[DE] text 49");
+    assert!(matches!(**source, Error::Api { .. }));
+    assert_eq!(err.status(), Some(reqwest::StatusCode::BAD_REQUEST));
+}
+
+#[tokio::test]
+async fn test_lang_codes() {
+    use crate::LangConvertError;
+
+    let (api, server) = crate::testing::mock_api().await;
+
+    let response = api
+        .translate_text("Hello", "pt-br")
+        .source_lang("en")
+        .await
+        .unwrap();
+    assert_eq!(response.translations[0].text, "[PT-BR] Hello");
+    let body = server.requests()[0].json().unwrap();
+    assert_eq!(body["target_lang"], "PT-BR");
+    assert_eq!(body["source_lang"], "EN");
+
+    let err = api.translate_text("Hello", "xx").await.unwrap_err();
+    assert!(matches!(
+        &err,
+        Error::InvalidLanguage(LangConvertError::InvalidLang(code)) if code == "xx"
+    ));
+    assert_eq!(err.kind(), crate::ErrorKind::Config);
+    let err = api
+        .translate_text("Hello", Lang::DE)
+        .source_lang(String::from("klingon"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidLanguage(_)));
+    server.assert_request_count(1);
+}
+
+#[tokio::test]
+async fn test_translatable() {
+    use std::borrow::Cow;
+
+    let (api, _server) = crate::testing::mock_api().await;
+    let count = |requester: TranslateRequester| requester.text.len();
+
+    let owned = String::from("Hello");
+    let slices = vec!["Hello", "World"];
+    let strings = vec![owned.clone(), owned.clone()];
+    assert_eq!(count(api.translate_text("Hello", Lang::DE)), 1);
+    assert_eq!(count(api.translate_text(owned.clone(), Lang::DE)), 1);
+    assert_eq!(count(api.translate_text(&owned, Lang::DE)), 1);
+    assert_eq!(count(api.translate_text(Cow::from("Hello"), Lang::DE)), 1);
+    assert_eq!(count(api.translate_text(["Hello", "World"], Lang::DE)), 2);
+    assert_eq!(count(api.translate_text([owned.clone()], Lang::DE)), 1);
+    assert_eq!(count(api.translate_text(&slices, Lang::DE)), 2);
+    assert_eq!(count(api.translate_text(&slices[..1], Lang::DE)), 1);
+    assert_eq!(count(api.translate_text(&strings, Lang::DE)), 2);
+    assert_eq!(count(api.translate_text(&strings[..], Lang::DE)), 2);
+    assert_eq!(count(api.translate_text(slices.clone(), Lang::DE)), 2);
+    assert_eq!(count(api.translate_text(strings, Lang::DE)), 2);
+
+    let set: std::collections::BTreeSet<String> = ["b", "a"].map(String::from).into();
+    let response = api.translate_texts(&set, Lang::DE).await.unwrap();
+    assert_eq!(response.to_string(), "[DE] a\n[DE] b");
+    assert_eq!(response.join(", "), "[DE] a, [DE] b");
+    let response = api
+        .translate_texts(slices.iter().map(|text| text.to_uppercase()), Lang::DE)
+        .await
+        .unwrap();
+    assert_eq!(response.translations[1].text, "[DE] WORLD");
+}
+
+#[tokio::test]
+async fn test_push_text() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    let mut requester = api.translate_text(Vec::<String>::new(), Lang::DE);
+    requester.source_lang(Lang::EN);
+    for i in 0..8 {
+        requester.push_text(format!("text {i}"));
+    }
+    requester.extend_texts(["text 8", "text 9"]);
+    assert_eq!(requester.text_count(), 10);
+
+    let response = (&mut requester).await.unwrap();
+    assert_eq!(response.translations.len(), 10);
+    assert_eq!(response.translations[9].text, "[DE] text 9");
+    let body = server.requests()[0].json().unwrap();
+    assert_eq!(body["source_lang"], "EN");
+
+    let response = requester.push_text("text 10").await.unwrap();
+    assert_eq!(response.translations.len(), 11);
+}
+
+#[tokio::test]
+async fn test_collect_string() {
+    use crate::testing::{Fault, Route};
+
+    let (api, server) = crate::testing::mock_api().await;
+
+    let text = api
+        .translate_text_to_string("Hello", Lang::DE)
+        .await
+        .unwrap();
+    assert_eq!(text, "[DE] Hello");
+    let text = api
+        .translate_text("Hello", Lang::DE)
+        .source_lang(Lang::EN)
+        .collect_string()
+        .await
+        .unwrap();
+    assert_eq!(text, "[DE] Hello");
+
+    server.inject(
+        Route::Translate,
+        Fault::status(200).body(r#"{"translations":[]}"#),
+    );
+    let err = api
+        .translate_text_to_string("Hello", Lang::DE)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidResponse(_)));
+    server.clear_faults();
+
+    let resp = api.translate_text(["a", "b"], Lang::DE).await.unwrap();
+    assert!(resp.into_single().is_err());
+}
+
+#[tokio::test]
+async fn test_translate_html() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    let html = r#"<p>Run <code translate="no">cargo build --release</code> first</p>"#;
+    let translated = api
+        .translate_html(html, Lang::DE)
+        .source_lang(Lang::EN)
+        .formality(Formality::Less)
+        .strict()
+        .await
+        .unwrap();
+    assert!(translated.contains(r#"<code translate="no">cargo build --release</code>"#));
+    let body = server.requests()[0].json().unwrap();
+    assert_eq!(body["tag_handling"], "html");
+    assert_eq!(body["text"], json!([html]));
+    assert_eq!(body["formality"], "less");
+
+    assert!(api.translate_html("plain text", Lang::DE).await.is_ok());
+    let err = api
+        .translate_html("1 < 2 > 0", Lang::DE)
+        .strict()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(_)));
+    server.assert_request_count(2);
+}
+
+#[tokio::test]
+async fn test_translate_xml() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    let tags = XmlTagHandling {
+        ignore: vec!["keep".to_string()],
+        splitting: vec!["p".to_string(), "br".to_string()],
+        outline_detection: false,
+        ..Default::default()
+    };
+    api.translate_xml("<p>Hello <keep>World</keep></p>", Lang::DE, tags)
+        .await
+        .unwrap();
+    let body = server.requests()[0].json().unwrap();
+    let configured: serde_json::Map<_, _> = body
+        .as_object()
+        .unwrap()
+        .iter()
+        .filter(|(key, value)| !value.is_null() && !["text", "target_lang"].contains(&key.as_str()))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    assert_eq!(
+        serde_json::Value::Object(configured),
+        json!({
+            "tag_handling": "xml",
+            "ignore_tags": ["keep"],
+            "splitting_tags": ["p", "br"],
+            "outline_detection": false,
+        })
+    );
+
+    for tag in ["", "<p>", "a>b"] {
+        let tags = XmlTagHandling {
+            non_splitting: vec![tag.to_string()],
+            ..Default::default()
+        };
+        let err = api
+            .translate_xml("Hello", Lang::DE, tags)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(_)), "{tag}");
+    }
+    server.assert_request_count(1);
+}
+
+#[tokio::test]
+async fn test_detect_language() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    let fixtures = [
+        ("Hello World", Lang::EN),
+        ("こんにちは世界", Lang::JA),
+        ("你好，世界", Lang::ZH),
+        ("Привет, мир", Lang::RU),
+        ("Γειά σου Κόσμε", Lang::EL),
+    ];
+    for (text, lang) in fixtures {
+        assert_eq!(api.detect_language(text).await.unwrap(), lang, "{text}");
+    }
+
+    // 300 three-byte characters, cut after the 200th
+    let text = "語".repeat(300);
+    api.detect_language(&text).await.unwrap();
+    let body = server.requests().last().unwrap().json().unwrap();
+    assert_eq!(body["text"], json!(["語".repeat(DETECT_LANGUAGE_PREFIX)]));
+
+    api.detect_language("日本語のテキスト")
+        .prefix(3)
+        .await
+        .unwrap();
+    let body = server.requests().last().unwrap().json().unwrap();
+    assert_eq!(body["text"], json!(["日本語"]));
+
+    for text in ["", " \n\t"] {
+        let err = api.detect_language(text).await.unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(_)), "{err:?}");
+    }
+    let err = api.detect_language("Hello").prefix(0).await.unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(_)), "{err:?}");
+    server.assert_request_count(7);
+}
+
+#[test]
+fn test_response_serde() {
+    let raw = json!({
+        "translations": [
+            { "detected_source_language": "EN", "text": "Hallo Welt" },
+            {
+                "detected_source_language": "ZH",
+                "text": "Welt",
+                "billed_characters": 2,
+                "model_type_used": "quality_optimized",
+            },
+        ]
+    });
+    let resp: TranslateTextResp = serde_json::from_value(raw.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&resp).unwrap(), raw);
+    let again: TranslateTextResp =
+        serde_json::from_value(serde_json::to_value(&resp).unwrap()).unwrap();
+    assert_eq!(again, resp);
+
+    // DeepL sends upper case codes, lower case is accepted and normalized
+    let lower: Sentence =
+        serde_json::from_value(json!({ "detected_source_language": "en", "text": "Hallo Welt" }))
+            .unwrap();
+    assert_eq!(lower, resp.translations[0]);
+
+    let cache: std::collections::HashSet<_> = resp.translations.iter().cloned().collect();
+    assert!(cache.contains(&lower));
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn test_response_accessors() {
+    let sentence = |text: &str| Sentence {
+        detected_source_language: Lang::EN,
+        text: text.to_string(),
+        billed_characters: None,
+        model_type_used: None,
+    };
+    let resp = TranslateTextResp {
+        translations: vec![sentence("Hallo"), sentence("Welt")],
+    };
+
+    assert_eq!(resp.len(), 2);
+    assert!(!resp.is_empty());
+    assert_eq!(resp.first(), Some(&sentence("Hallo")));
+    assert_eq!(resp.texts().collect::<Vec<_>>(), ["Hallo", "Welt"]);
+    let borrowed: Vec<&Sentence> = (&resp).into_iter().collect();
+    assert_eq!(borrowed, [&sentence("Hallo"), &sentence("Welt")]);
+    assert_eq!(resp.clone().into_iter().count(), 2);
+    assert_eq!(resp.into_texts().join("\n"), "Hallo\nWelt");
+
+    let empty = TranslateTextResp {
+        translations: Vec::new(),
+    };
+    assert!(empty.is_empty());
+    assert_eq!(empty.first(), None);
+}
+
+#[tokio::test]
+async fn test_response_iteration() {
+    let (api, _server) = crate::testing::mock_api().await;
+    let resp = api
+        .translate_text(["Hello", "World"], Lang::DE)
+        .await
+        .unwrap();
+
+    let mut lines = Vec::new();
+    for sentence in &resp {
+        lines.push(format!(
+            "{}: {}",
+            sentence.detected_source_language, sentence.text
+        ));
+    }
+    assert_eq!(lines, ["EN: [DE] Hello", "EN: [DE] World"]);
+
+    let joined = resp.into_texts().join("\n");
+    assert_eq!(joined, "[DE] Hello\n[DE] World");
+}
+
+#[tokio::test]
+async fn test_translate_many() {
+    use crate::testing::{Fault, Route};
+    use std::time::Duration;
+
+    let (api, server) = crate::testing::mock_api().await;
+    let texts: Vec<String> = (0..230).map(|i| format!("text {i}")).collect();
+
+    // later batches answer first
+    let delays = [300, 10, 200, 50, 0].map(Duration::from_millis);
+    server.delay_each(Route::Translate, delays);
+    let sentences = api
+        .translate_many(texts.clone(), Lang::DE)
+        .concurrency(3)
+        .await
+        .unwrap();
+    let expected: Vec<String> = texts.iter().map(|text| format!("[DE] {text}")).collect();
+    assert_eq!(
+        sentences.into_iter().map(|s| s.text).collect::<Vec<_>>(),
+        expected
+    );
+    server.assert_request_count(5);
+    assert!(server.max_concurrent_requests() <= 3);
+
+    let sentences = api
+        .translate_many(texts.clone(), Lang::DE)
+        .batch_size(100)
+        .await
+        .unwrap();
+    assert_eq!(sentences.len(), 230);
+    server.assert_request_count(10);
+
+    // the second batch fails
+    server.inject(Route::Translate, Fault::status(400).after(1).times(1));
+    let err = api
+        .translate_many(texts.clone(), Lang::DE)
+        .concurrency(1)
+        .await
+        .unwrap_err();
+    let Error::BatchFailed { range, .. } = &err else {
+        panic!("unexpected error: {err:?}");
+    };
+    assert_eq!(*range, 50..100);
+    assert_eq!(err.status(), Some(reqwest::StatusCode::BAD_REQUEST));
+    server.assert_request_count(12);
+
+    server.inject(Route::Translate, Fault::status(400).after(1).times(1));
+    let results = api
+        .translate_many(texts, Lang::DE)
+        .concurrency(1)
+        .continue_on_error()
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 230);
+    for (i, result) in results.iter().enumerate() {
+        match result {
+            Ok(sentence) => assert_eq!(sentence.text, expected[i]),
+            Err(Error::BatchFailed { range, .. }) => {
+                assert!(range.contains(&i) && *range == (50..100))
+            }
+            Err(err) => panic!("unexpected error: {err:?}"),
+        }
+    }
+    assert_eq!(results.iter().filter(|r| r.is_err()).count(), 50);
+    server.assert_request_count(17);
+}
+
+#[tokio::test]
+async fn test_translate_stream() {
+    use crate::testing::Route;
+    use tokio_stream::StreamExt;
+
+    let (api, server) = crate::testing::mock_api().await;
+    let texts: Vec<String> = (0..100).map(|i| format!("text {i}")).collect();
+    let expected: Vec<String> = texts.iter().map(|text| format!("[DE] {text}")).collect();
+
+    // later batches answer first
+    let delays = [200, 10, 100, 0].map(Duration::from_millis);
+    server.delay_each(Route::Translate, delays);
+    let sentences: Vec<_> = api
+        .translate_stream(
+            tokio_stream::iter(texts.clone()),
+            Lang::DE,
+            TranslateOptions::default(),
+        )
+        .batch_size(30)
+        .concurrency(2)
+        .collect()
+        .await;
+    let sentences: Vec<String> = sentences.into_iter().map(|s| s.unwrap().text).collect();
+    assert_eq!(sentences, expected);
+    let batches: Vec<usize> = server
+        .requests()
+        .iter()
+        .map(|req| req.json().unwrap()["text"].as_array().unwrap().len())
+        .collect();
+    assert_eq!(batches, [30, 30, 30, 10]);
+    assert!(server.max_concurrent_requests() <= 2);
+
+    // a partial batch is sent after lingering, while the input stays open
+    let (tx, rx) = tokio::sync::mpsc::channel(10);
+    let input = tokio_stream::wrappers::ReceiverStream::new(rx);
+    let mut stream = api
+        .translate_stream(input, Lang::DE, TranslateOptions::default())
+        .linger(Duration::from_millis(50));
+    for text in &texts[..3] {
+        tx.send(text.clone()).await.unwrap();
+    }
+    for text in &expected[..3] {
+        assert_eq!(&stream.next().await.unwrap().unwrap().text, text);
+    }
+    server.assert_request_count(5);
+    drop(tx);
+    assert!(stream.next().await.is_none());
 }
 
 #[tokio::test]
-async fn test_formality() {
-    let api = DeepLApi::with(&std::env::var("DEEPL_API_KEY").unwrap()).new();
+async fn test_translate_stream_errors() {
+    use crate::testing::{Fault, Route};
+    use tokio_stream::StreamExt;
 
-    // can specify a formality
-    let text = "How are you?";
-    let src = Lang::EN;
-    let trg = Lang::ES;
-    let more = Formality::More;
+    let (api, server) = crate::testing::mock_api().await;
+    let texts: Vec<String> = (0..100).map(|i| format!("text {i}")).collect();
+    let translate = |policy| {
+        api.translate_stream(
+            tokio_stream::iter(texts.clone()),
+            Lang::DE,
+            TranslateOptions::default(),
+        )
+        .batch_size(25)
+        .concurrency(1)
+        .on_error(policy)
+        .collect::<Vec<_>>()
+    };
+
+    // the second batch fails
+    server.inject(Route::Translate, Fault::status(400).after(1).times(1));
+    let results = translate(StreamErrorPolicy::Terminate).await;
+    assert_eq!(results.len(), 26);
+    assert!(results[..25].iter().all(Result::is_ok));
+    assert!(matches!(&results[25], Err(Error::BatchFailed { range, .. }) if *range == (25..50)));
+    server.assert_request_count(2);
+
+    server.inject(Route::Translate, Fault::status(400).after(1).times(1));
+    let results = translate(StreamErrorPolicy::Skip).await;
+    assert_eq!(results.len(), 100);
+    for (i, result) in results.iter().enumerate() {
+        assert_eq!(result.is_err(), (25..50).contains(&i), "{i}");
+    }
+    assert_eq!(results[99].as_ref().unwrap().text, "[DE] text 99");
+    server.assert_request_count(6);
+}
+
+#[tokio::test]
+async fn test_validate_formality() {
+    let (api, server) = crate::testing::mock_api().await;
+    let translate = |lang, formality| {
+        api.translate_text("Hello", lang)
+            .formality(formality)
+            .validate_formality()
+            .send()
+    };
+    let paths = || -> Vec<String> {
+        server
+            .requests()
+            .iter()
+            .map(|req| req.path().to_string())
+            .collect()
+    };
+
+    translate(Lang::DE, Formality::More).await.unwrap();
+    let err = translate(Lang::ZH, Formality::More).await.unwrap_err();
+    assert!(
+        matches!(err, Error::InvalidInput(ref msg) if msg.contains("ZH")),
+        "{err:?}"
+    );
+    translate(Lang::ZH, Formality::PreferMore).await.unwrap();
+    translate(Lang::EN_GB, Formality::PreferLess).await.unwrap();
+    assert_eq!(
+        paths(),
+        [
+            "/v2/languages",
+            "/v2/translate",
+            "/v2/translate",
+            "/v2/translate"
+        ]
+    );
+
+    let err = api
+        .upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::EN_GB)
+        .formality(Formality::Less)
+        .validate_formality()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(_)), "{err:?}");
+    server.assert_request_count(4);
+}
+
+#[tokio::test]
+async fn test_models() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    let response = api.translate_text("Hello", Lang::DE).await.unwrap();
+    assert_eq!(response.translations[0].model_type_used, None);
 
     let response = api
-        .translate_text(text, trg)
-        .source_lang(src)
-        .formality(more)
+        .translate_text("Hello", Lang::DE)
+        .model_type(ModelType::PreferQualityOptimized)
         .await
         .unwrap();
-    assert!(!response.translations.is_empty());
+    assert_eq!(
+        response.translations[0].model_type_used.as_deref(),
+        Some("quality_optimized")
+    );
+    let body = server.requests().last().unwrap().json().unwrap();
+    assert_eq!(body["model_type"], "prefer_quality_optimized");
 
-    // response ok, despite target lang not supporting formality
-    let text = "¿Cómo estás?";
-    let src = Lang::ES;
-    let trg = Lang::EN_US;
-    let less = Formality::PreferLess;
+    let response = api
+        .translate_text("Hello", Lang::DE)
+        .model_type(ModelType::LatencyOptimized)
+        .await
+        .unwrap();
+    assert_eq!(
+        response.translations[0].model_type_used.as_deref(),
+        Some("latency_optimized")
+    );
+}
+
+#[tokio::test]
+async fn test_empty_input() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    let mut req = TranslateRequester::new(&api, Vec::new(), Lang::DE);
+    assert!(matches!((&mut req).await, Err(Error::EmptyInput)));
+    assert!(matches!(req.send_raw().await, Err(Error::EmptyInput)));
+    assert!(matches!(req.await, Err(Error::EmptyInput)));
+    server.assert_request_count(0);
+
+    let resp = TranslateRequester::new(&api, vec!["Hello".into(), String::new()], Lang::DE)
+        .await
+        .unwrap();
+    assert_eq!(resp.translations.len(), 2);
+    server.assert_request_count(1);
+}
+
+#[tokio::test]
+async fn test_request_too_large() {
+    use crate::testing::{Fault, Route};
+
+    let (api, server) = crate::testing::mock_api().await;
+
+    let batch = vec!["a".repeat(3 * 1024); 50];
+    let err = TranslateRequester::new(&api, batch, Lang::DE)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        Error::RequestTooLarge { size: Some(size), limit: Some(limit), .. }
+            if size > limit && limit == 128 * 1024
+    ));
+
+    // quotes are escaped in JSON, doubling the size of the text
+    let quotes = "\"".repeat(70 * 1024);
+    let result = api.translate_text(&quotes, Lang::DE).send_raw().await;
+    assert!(matches!(result, Err(Error::RequestTooLarge { .. })));
+    server.assert_request_count(0);
+
+    server.inject(Route::Translate, Fault::status(413).body(""));
+    let err = api.translate_text("Hello", Lang::DE).await.unwrap_err();
+    assert!(matches!(
+        err,
+        Error::RequestTooLarge {
+            size: None,
+            limit: None,
+            ..
+        }
+    ));
+}
+
+#[tokio::test]
+async fn test_request_size_multibyte() {
+    let (api, server) = crate::testing::mock_api().await;
+    let size = |text: &str| {
+        let requester = api.translate_text(text, Lang::DE);
+        match requester.body() {
+            Ok(body) => body.len(),
+            Err(Error::RequestTooLarge {
+                size: Some(size), ..
+            }) => size,
+            Err(err) => panic!("{err:?}"),
+        }
+    };
+    let overhead = size("");
+
+    // CJK characters take 3 bytes and emoji 4 bytes in UTF-8, and are not escaped
+    assert_eq!(size("你好世界") - overhead, 12);
+    assert_eq!(size("👋🌍") - overhead, 8);
+    assert_eq!(size("\"\\\n") - overhead, 6);
+
+    // far less characters than the limit, but more bytes
+    let cjk = "語".repeat(44 * 1024);
+    let err = api.translate_text(&cjk, Lang::DE).await.unwrap_err();
+    assert!(matches!(
+        err,
+        Error::RequestTooLarge { size: Some(size), .. } if size > 132 * 1024
+    ));
+    let emoji = "🦀".repeat(33 * 1024);
+    let err = api.translate_text(&emoji, Lang::DE).await.unwrap_err();
+    assert!(matches!(err, Error::RequestTooLarge { .. }));
+    server.assert_request_count(0);
 
     let response = api
-        .translate_text(text, trg)
-        .source_lang(src)
-        .formality(less)
+        .translate_text(&emoji, Lang::DE)
+        .skip_size_check()
         .await
         .unwrap();
-    assert!(!response.translations.is_empty());
+    assert_eq!(response.translations[0].text, format!("[DE] {emoji}"));
+    server.assert_request_count(1);
+}
+
+#[tokio::test]
+async fn test_server_error() {
+    use crate::testing::{Fault, Route};
+
+    let (api, server) = crate::testing::mock_api().await;
+
+    let html = "<html><body><h1>500 Internal Server Error</h1></body></html>";
+    server.inject(Route::Translate, Fault::status(500).body(html));
+    let err = api.translate_text("Hello", Lang::DE).await.unwrap_err();
+    assert!(matches!(err, Error::ServerError { status, .. } if status.as_u16() == 500));
+    assert_eq!(
+        err.to_string(),
+        "server error: 500 Internal Server Error (in translate_text)"
+    );
+    assert!(err.is_retryable());
+
+    server.inject(Route::Translate, Fault::status(503).body(""));
+    let err = api.translate_text("Hello", Lang::DE).await.unwrap_err();
+    assert!(matches!(err, Error::TooManyRequests { status, .. } if status.as_u16() == 503));
+    assert!(err.is_retryable());
+}
+
+#[tokio::test]
+async fn test_shared_requester() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    let mut template = api.translate_text("Hello", Lang::DE);
+    template.source_lang(Lang::EN).formality(Formality::Less);
+    let copy = template.clone();
+
+    let first = (&template).await.unwrap();
+    let second = (&template).await.unwrap();
+    assert_eq!(first, second);
+    assert_eq!(copy.await.unwrap(), first);
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[0].body(), requests[1].body());
+    assert_eq!(requests[0].body(), requests[2].body());
+}
+
+#[test]
+fn test_estimate_characters() {
+    assert_eq!(estimate_characters::<&str>(&[]), 0);
+    assert_eq!(estimate_characters(&["Hello World"]), 11);
+    // CJK characters and punctuation count one each
+    assert_eq!(estimate_characters(&["你好，世界"]), 5);
+    assert_eq!(estimate_characters(&["こんにちは", "안녕"]), 7);
+    // emoji count every code point, like the skin tone and the joiners
+    assert_eq!(estimate_characters(&["🦀"]), 1);
+    assert_eq!(estimate_characters(&["👍🏽"]), 2);
+    assert_eq!(estimate_characters(&["👨‍👩‍👧"]), 5);
+    // combining characters count on their own, the precomposed form counts once
+    assert_eq!(estimate_characters(&["e\u{301}"]), 2);
+    assert_eq!(estimate_characters(&["\u{e9}"]), 1);
+    assert_eq!(
+        estimate_characters(&[String::from("Café"), String::new()]),
+        4
+    );
+}
+
+#[test]
+fn test_estimated_characters() {
+    let api = DeepLApi::with("key").new();
+
+    let mut requester = api.translate_text(["Hello <b>World</b>", "👍🏽"], Lang::DE);
+    assert_eq!(requester.estimated_characters(), 20);
+    requester.context("not billed");
+    assert_eq!(requester.estimated_characters(), 20);
+
+    requester.tag_handling(TagHandling::Html);
+    assert_eq!(requester.estimated_characters(), 13);
+}
+
+#[tokio::test]
+async fn test_translate_options() {
+    let options: TranslateOptions = toml::from_str(
+        r#"
+        source_lang = "EN"
+        formality = "prefer_less"
+        preserve_formatting = true
+        split_sentences = "nonewlines"
+        tag_handling = "html"
+        ignore_tags = ["code"]
+        "#,
+    )
+    .unwrap();
+    assert_eq!(options.formality, Some(Formality::PreferLess));
+    assert_eq!(options.glossary_id, None);
+    let config = toml::to_string(&options).unwrap();
+    assert_eq!(
+        toml::from_str::<TranslateOptions>(&config).unwrap(),
+        options
+    );
+    let json = serde_json::to_string(&options).unwrap();
+    assert_eq!(
+        serde_json::from_str::<TranslateOptions>(&json).unwrap(),
+        options
+    );
+    assert_eq!(
+        serde_json::to_string(&TranslateOptions::default()).unwrap(),
+        "{}"
+    );
+
+    let (api, server) = crate::testing::mock_api().await;
+    api.translate_with("<p>Hello</p>", Lang::DE, &options)
+        .await
+        .unwrap();
+    api.translate_text("<p>Hello</p>", Lang::DE)
+        .source_lang(Lang::EN)
+        .formality(Formality::PreferLess)
+        .preserve_formatting(PreserveFormatting::Preserve)
+        .split_sentences(SplitSentences::PunctuationOnly)
+        .tag_handling(TagHandling::Html)
+        .ignore_tags(vec!["code".to_string()])
+        .await
+        .unwrap();
+    let requests = server.requests();
+    assert_eq!(requests[0].json(), requests[1].json());
+
+    // setters called afterwards win, options left unset keep the value of the requester
+    api.translate_text("Hello", Lang::DE)
+        .glossary_id("glossary")
+        .with_options(&options)
+        .formality(Formality::More)
+        .await
+        .unwrap();
+    let body = server.requests()[2].json().unwrap();
+    assert_eq!(body["formality"], "more");
+    assert_eq!(body["glossary_id"], "glossary");
+    assert_eq!(body["tag_handling"], "html");
+}
+
+#[tokio::test]
+async fn test_form_encoded() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    let mut requester = api.translate_text(["Hello <x>World</x>", "a&b=c"], Lang::DE);
+    requester
+        .source_lang(Lang::EN)
+        .formality(Formality::PreferLess)
+        .tag_handling(TagHandling::Xml)
+        .ignore_tags(vec!["x".to_string(), "y".to_string()])
+        .preserve_formatting(PreserveFormatting::Preserve)
+        .split_sentences(SplitSentences::None)
+        .form_encoded(true);
+    let resp = (&requester).await.unwrap();
+    assert_eq!(resp.to_string(), "[DE] Hello <x>World</x>\n[DE] a&b=c");
+
+    let request = &server.requests()[0];
+    assert_eq!(
+        request.header("content-type"),
+        Some("application/x-www-form-urlencoded")
+    );
+    assert_eq!(
+        String::from_utf8_lossy(request.body()),
+        "formality=prefer_less&ignore_tags=x%2Cy&preserve_formatting=1&source_lang=EN\
+         &split_sentences=0&tag_handling=xml&target_lang=DE\
+         &text=Hello+%3Cx%3EWorld%3C%2Fx%3E&text=a%26b%3Dc"
+    );
+
+    // the same request as JSON gets the same response
+    requester.form_encoded(false);
+    assert_eq!((&requester).await.unwrap(), resp);
+    assert!(server.requests()[1].json().is_some());
+}
+
+#[tokio::test]
+#[ignore = "needs DEEPL_API_KEY"]
+async fn test_form_encoded_live() {
+    let key = std::env::var("DEEPL_API_KEY").unwrap();
+    let api = DeepLApi::with(&key).new();
+
+    let resp = api
+        .translate_text(["Hello World", "<p>Good morning</p>"], Lang::DE)
+        .source_lang(Lang::EN)
+        .tag_handling(TagHandling::Html)
+        .ignore_tags(vec!["code".to_string(), "pre".to_string()])
+        .form_encoded(true)
+        .await
+        .unwrap();
+    assert_eq!(resp.translations.len(), 2);
+    assert_eq!(resp.translations[0].detected_source_language, Lang::EN);
+}
+
+#[tokio::test]
+async fn test_body_snapshot() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    api.translate_text(["Hello \"World\"", "你好 🦀"], Lang::DE)
+        .source_lang(Lang::EN)
+        .formality(Formality::PreferLess)
+        .tag_handling(TagHandling::Xml)
+        .ignore_tags(vec!["x".to_string()])
+        .preserve_formatting(PreserveFormatting::Preserve)
+        .split_sentences(SplitSentences::None)
+        .show_billed_characters(true)
+        .await
+        .unwrap();
+    let expected = r#"{"context":null,"formality":"prefer_less","glossary_id":null,"ignore_tags":["x"],"model_type":null,"non_splitting_tags":null,"outline_detection":null,"preserve_formatting":true,"show_billed_characters":true,"source_lang":"EN","split_sentences":"0","splitting_tags":null,"tag_handling":"xml","target_lang":"DE","text":["Hello \"World\"","你好 🦀"]}"#;
+    assert_eq!(
+        String::from_utf8_lossy(server.requests()[0].body()),
+        expected
+    );
+}
+
+/// Compare the body built from a `serde_json::Value` with the one serialized at once, run with
+/// `cargo test --release bench_body -- --ignored --nocapture`
+#[test]
+#[ignore]
+fn bench_body() {
+    use std::time::Instant;
+
+    let api = DeepLApi::with("key").new();
+    let texts: Vec<String> = (0..50).map(|i| format!("{i:04}").repeat(512)).collect();
+    let requester = api.translate_text(texts, Lang::DE);
+    let rounds = 200;
+
+    let start = Instant::now();
+    let mut value_bytes = Vec::new();
+    for _ in 0..rounds {
+        // the previous way: a copy of every text in the value, a string to check the size, and
+        // the serialization by reqwest
+        let value = json!(requester);
+        std::hint::black_box(value.to_string().len());
+        value_bytes = serde_json::to_vec(&value).unwrap();
+    }
+    let value_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut body = None;
+    for _ in 0..rounds {
+        body = Some(requester.body().unwrap());
+    }
+    let body_time = start.elapsed();
+
+    let Some(Body::Json(body)) = body else {
+        panic!("not a JSON body");
+    };
+    assert_eq!(body, value_bytes);
+    println!("50 texts of 2 KiB: value {value_time:?}, direct {body_time:?} for {rounds} bodies");
+}
+
+#[tokio::test]
+async fn test_empty_text_policy() {
+    let (api, server) = crate::testing::mock_api().await;
+    let texts = ["", "Hello", " \n", "World", "\t"];
+
+    // sent as is by default
+    let resp = api.translate_text(texts, Lang::DE).await.unwrap();
+    assert_eq!(resp.len(), 5);
+    assert_eq!(
+        server.requests()[0].json().unwrap()["text"]
+            .as_array()
+            .unwrap()
+            .len(),
+        5
+    );
+
+    let err = api
+        .translate_text(texts, Lang::DE)
+        .empty_text_policy(EmptyTextPolicy::Error)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(msg) if msg == "text 0 is empty"));
+    server.assert_request_count(1);
+
+    let resp = api
+        .translate_text(texts, Lang::DE)
+        .show_billed_characters(true)
+        .empty_text_policy(EmptyTextPolicy::SkipAndPreserveIndices)
+        .await
+        .unwrap();
+    assert_eq!(
+        resp.texts().collect::<Vec<_>>(),
+        ["", "[DE] Hello", "", "[DE] World", ""]
+    );
+    assert!(resp
+        .translations
+        .iter()
+        .all(|s| s.detected_source_language == Lang::EN));
+    assert_eq!(resp.total_billed_characters(), Some(10));
+    let body = server.requests()[1].json().unwrap();
+    assert_eq!(body["text"], json!(["Hello", "World"]));
+
+    // nothing to send
+    let resp = api
+        .translate_text(["", " "], Lang::DE)
+        .source_lang(Lang::FR)
+        .empty_text_policy(EmptyTextPolicy::SkipAndPreserveIndices)
+        .await
+        .unwrap();
+    assert_eq!(resp.texts().collect::<Vec<_>>(), ["", ""]);
+    assert_eq!(resp.translations[0].detected_source_language, Lang::FR);
+    server.assert_request_count(2);
+}
+
+#[test]
+fn test_iter_with() {
+    let sentence = |text: &str| Sentence {
+        detected_source_language: Lang::EN,
+        text: text.to_string(),
+        billed_characters: None,
+        model_type_used: None,
+    };
+    let resp = TranslateTextResp {
+        translations: vec![sentence("Hallo"), sentence("Welt"), sentence("Hallo")],
+    };
+
+    let inputs = ["hello", "world", "HELLO"];
+    let pairs: Vec<_> = resp
+        .iter_with(inputs)
+        .map(|(input, s)| (input, s.text.as_str()))
+        .collect();
+    assert_eq!(
+        pairs,
+        [("hello", "Hallo"), ("world", "Welt"), ("HELLO", "Hallo")]
+    );
+    let keys: Vec<_> = resp
+        .try_iter_with(&inputs)
+        .unwrap()
+        .map(|(k, _)| *k)
+        .collect();
+    assert_eq!(keys, inputs);
+
+    // stop at the shorter side, unless checked
+    assert_eq!(resp.iter_with(["hello"]).count(), 1);
+    assert_eq!(resp.iter_with(["a", "b", "c", "d"]).count(), 3);
+    let err = resp.try_iter_with(["hello"]).err().unwrap();
+    assert_eq!(
+        err.to_string(),
+        "invalid response: 3 translations for 1 inputs"
+    );
+    assert!(resp.try_iter_with(["a", "b", "c", "d"]).is_err());
+
+    let map = resp.clone().into_map(inputs).unwrap();
+    assert_eq!(map.len(), 3);
+    assert_eq!(map["world"], "Welt");
+    assert!(resp.into_map(vec![String::from("hello")]).is_err());
+}
+
+#[tokio::test]
+async fn test_formality_fallback() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    // Chinese doesn't support formality
+    let resp = api
+        .translate_text("Hello", Lang::ZH)
+        .formality_with_fallback(Formality::More)
+        .await
+        .unwrap();
+    assert_eq!(resp.to_string(), "[ZH] Hello");
+    let requests = server.requests();
+    assert_eq!(requests[0].path(), "/v2/languages");
+    assert_eq!(requests[1].json().unwrap()["formality"], "prefer_more");
+
+    api.translate_text("Hello", Lang::DE)
+        .formality_with_fallback(Formality::Less)
+        .await
+        .unwrap();
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[2].json().unwrap()["formality"], "less");
+
+    // soft formality is sent as is, without fetching the languages
+    api.translate_text("Hello", Lang::ZH)
+        .formality_with_fallback(Formality::PreferLess)
+        .await
+        .unwrap();
+    let requests = server.requests();
+    assert_eq!(requests.len(), 4);
+    assert_eq!(requests[3].json().unwrap()["formality"], "prefer_less");
+}
+
+#[tokio::test]
+async fn test_with_texts() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    let mut template = api.translate_text(Vec::<String>::new(), Lang::DE);
+    template
+        .source_lang(Lang::EN)
+        .formality(Formality::PreferLess)
+        .tag_handling(TagHandling::Html)
+        .glossary_id("glossary");
+    let batches = [vec!["Hello"], vec!["World", "!"], vec!["<p>Bye</p>"]];
+    for texts in &batches {
+        template.with_texts(texts).await.unwrap();
+    }
+    assert!(matches!(template.await, Err(Error::EmptyInput)));
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    let mut options = None;
+    for (request, texts) in requests.iter().zip(&batches) {
+        let mut body = request.json().unwrap();
+        let text = body.as_object_mut().unwrap().remove("text").unwrap();
+        assert_eq!(text, json!(texts));
+        assert_eq!(body["formality"], "prefer_less");
+        assert_eq!(options.get_or_insert_with(|| body.clone()), &body);
+    }
+}
+
+#[tokio::test]
+async fn test_glossary_setter() {
+    let (api, server) = crate::testing::mock_api().await;
+    let glossary = api
+        .create_glossary("greetings")
+        .source_lang(Lang::EN)
+        .target_lang(Lang::DE)
+        .entries([("Hello", "Hallo")])
+        .send()
+        .await
+        .unwrap();
+
+    api.translate_text("Hello", Lang::DE)
+        .glossary(&glossary)
+        .await
+        .unwrap();
+    let body = server.requests().last().unwrap().json().unwrap();
+    assert_eq!(body["glossary_id"], glossary.glossary_id.as_str());
+    assert_eq!(body["source_lang"], "EN");
+
+    let err = api
+        .translate_text("Hello", Lang::FR)
+        .glossary(&glossary)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "invalid input: glossary {} translates to DE, not FR",
+            glossary.glossary_id
+        )
+    );
+    server.assert_request_count(2);
+}
+
+#[tokio::test]
+async fn test_translate_map() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    let mut map: HashMap<String, String> = (0..120)
+        .map(|i| (format!("message.{i}"), format!("Text {i:03}")))
+        .collect();
+    map.insert("empty".to_string(), String::new());
+    map.insert("again".to_string(), "Text 007".to_string());
+
+    let options = TranslateOptions {
+        source_lang: Some(Lang::EN),
+        ..Default::default()
+    };
+    let translated = api.translate_map(&map, Lang::DE, &options).await.unwrap();
+    assert_eq!(translated.len(), map.len());
+    for (key, text) in &map {
+        match text.is_empty() {
+            true => assert_eq!(translated[key], ""),
+            false => assert_eq!(translated[key], format!("[DE] {text}")),
+        }
+    }
+    assert_eq!(translated["again"], "[DE] Text 007");
+
+    // the distinct values in order, 50 per request, sent a few at once
+    let mut sent: Vec<Vec<serde_json::Value>> = server
+        .requests()
+        .iter()
+        .map(|r| r.json().unwrap()["text"].as_array().unwrap().clone())
+        .collect();
+    sent.sort_by_key(|texts| texts[0].as_str().unwrap().to_string());
+    assert_eq!(sent.iter().map(Vec::len).collect::<Vec<_>>(), [50, 50, 20]);
+    assert_eq!(sent[0][0], "Text 000");
+    assert_eq!(sent[2][19], "Text 119");
+
+    let empty: HashMap<u32, String> = HashMap::from([(1, String::new())]);
+    let translated = api.translate_map(&empty, Lang::DE, &options).await.unwrap();
+    assert_eq!(translated, empty);
+    server.assert_request_count(3);
+}
+
+#[cfg(feature = "tokio-fs")]
+#[tokio::test]
+async fn test_translate_html_file() {
+    let (api, server) = crate::testing::mock_api().await;
+    let dir = std::env::temp_dir().join(format!("deepl-html-file-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (input, output) = (dir.join("index.html"), dir.join("index.de.html"));
+    let html = r#"<html><body><p>Hello</p><pre translate="no">cargo build</pre></body></html>"#;
+    std::fs::write(&input, html).unwrap();
+
+    api.translate_html_file(&input, &output, Lang::DE, &TranslateOptions::default())
+        .await
+        .unwrap();
+    let translated = std::fs::read_to_string(&output).unwrap();
+    assert_eq!(translated, format!("[DE] {html}"));
+    assert!(translated.contains(r#"<pre translate="no">cargo build</pre>"#));
+    assert!(!dir.join("index.de.html.part").exists());
+    let body = server.requests()[0].json().unwrap();
+    assert_eq!(body["tag_handling"], "html");
+
+    std::fs::write(&input, [0xff, 0xfe]).unwrap();
+    let err = api
+        .translate_html_file(&input, &output, Lang::DE, &TranslateOptions::default())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(msg) if msg.contains("index.html")));
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), translated);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let parts = split_html("<p>Hello</p><p>World</p>", 16);
+    assert_eq!(parts, ["<p>Hello</p>", "<p>World</p>"]);
+    assert_eq!(split_html("Hello World", 8), ["Hello", " World"]);
+}
+
+#[cfg(feature = "tokio-fs")]
+#[test]
+fn test_split_html() {
+    // the limit falls inside the element kept as is, which moves to the next part whole
+    let kept = r#"<div translate="no"><b>keep</b> a > b</div>"#;
+    let html = format!("<p>one</p>{kept}<p>two</p>");
+    let limit = "<p>one</p>".len() + kept.len() - 4;
+    assert_eq!(split_html(&html, limit), ["<p>one</p>", kept, "<p>two</p>"]);
+
+    // in a whole document, the parts end between the children of body
+    let code = "<code>let x = 1;</code>";
+    let html = format!(
+        "<!DOCTYPE html><html><body><p>Hello <br> World</p>{code}<!-- x --><p>Bye</p></body></html>"
+    );
+    let parts = split_html(&html, 50);
+    assert_eq!(parts.concat(), html);
+    assert!(parts[0].ends_with("World</p>"), "{parts:?}");
+    assert!(parts.iter().any(|part| part.starts_with(code)), "{parts:?}");
+    assert!(parts.iter().all(|part| part.len() <= 50));
+
+    // parts are sized by their escaped length, a quote takes two bytes and a control character six
+    let quoted = "<p>\"\"\"\"</p>";
+    let html = quoted.repeat(2);
+    assert_eq!(split_html(&html, 16), [quoted, quoted]);
+    let control = "\u{1}".repeat(10);
+    let parts = split_html(&control, 12);
+    assert_eq!(parts, vec!["\u{1}\u{1}"; 5]);
+    for part in split_html(&format!("{html}{control}\\ \\ \\"), 13) {
+        assert!(
+            serde_json::to_string(part).unwrap().len() - 2 <= 13,
+            "{part:?}"
+        );
+    }
+    assert_eq!(split_html("\u{1}\u{1}", 3), ["\u{1}", "\u{1}"]);
+    assert_eq!(split_html("a語b", 3), ["a", "語", "b"]);
+
+    // script content isn't parsed as tags
+    let boundaries = tag_boundaries("<script>if (a <b) {}</script><p>x</p>");
+    let found: Vec<_> = boundaries.iter().map(|b| (b.at, b.depth)).collect();
+    assert_eq!(found, [(0, 0), (20, 1), (29, 0), (33, 1)]);
+    assert_eq!(boundaries[3].text, Some(33));
+}
+
+#[tokio::test]
+async fn test_tag_lists() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    api.translate_text("Hello <x>World</x>", Lang::DE)
+        .tag_handling(TagHandling::Xml)
+        .ignore_tags([" x ", "code", "x", "code"])
+        .splitting_tags(["p", "p"])
+        .non_splitting_tags(Vec::<&str>::new())
+        .await
+        .unwrap();
+    let body = server.requests()[0].json().unwrap();
+    assert_eq!(body["ignore_tags"], json!(["x", "code"]));
+    assert_eq!(body["splitting_tags"], json!(["p"]));
+    assert_eq!(body["non_splitting_tags"], json!([]));
+
+    for tag in ["", "  ", "<keep>", "a>b", "my tag"] {
+        let err = api
+            .translate_text("Hello", Lang::DE)
+            .tag_handling(TagHandling::Xml)
+            .splitting_tags([tag])
+            .await
+            .unwrap_err();
+        let expected = format!("invalid tag name {tag:?} in splitting_tags");
+        assert!(
+            matches!(&err, Error::InvalidInput(msg) if *msg == expected),
+            "{err}"
+        );
+    }
+
+    let err = api
+        .translate_text("Hello", Lang::DE)
+        .non_splitting_tags(["p"])
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(msg) if msg.contains("without tag_handling")));
+    server.assert_request_count(1);
+}
+
+#[tokio::test]
+async fn test_extra_param() {
+    let (api, server) = crate::testing::mock_api().await;
+
+    let mut requester = api.translate_text("Hello", Lang::DE);
+    requester
+        .source_lang(Lang::EN)
+        .extra_param("custom_instructions", json!(["Be brief"]))
+        .extra_param("enable_beta", json!(true))
+        .extra_param("target_lang", json!("FR"))
+        .extra_param("formality", json!("more"));
+    let resp = (&requester).await.unwrap();
+    assert_eq!(resp.translations[0].text, "[DE] Hello");
+
+    let body = server.requests()[0].json().unwrap();
+    assert_eq!(body["source_lang"], "EN");
+    assert_eq!(body["target_lang"], "DE");
+    assert_eq!(body["formality"], json!(null));
+    assert_eq!(body["custom_instructions"], json!(["Be brief"]));
+    assert_eq!(body["enable_beta"], json!(true));
+
+    requester.form_encoded(true);
+    (&requester).await.unwrap();
+    let form = String::from_utf8_lossy(server.requests()[1].body()).into_owned();
+    assert!(
+        form.contains("custom_instructions=Be+brief&enable_beta=1"),
+        "{form}"
+    );
+    assert!(
+        form.contains("target_lang=DE") && !form.contains("FR"),
+        "{form}"
+    );
+
+    let err = api
+        .translate_text("Hello", Lang::DE)
+        .extra_param("", json!(1))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidInput(_)));
+    server.assert_request_count(2);
+}
+
+#[test]
+fn test_params_fields() {
+    let api = DeepLApi::with("key").new();
+    let requester = api.translate_text("Hello", Lang::DE);
+    let params = json!(requester.params(&requester.text));
+    let mut keys: Vec<_> = params.as_object().unwrap().keys().collect();
+    let mut fields = TranslateRequester::FIELDS.to_vec();
+    keys.sort();
+    fields.sort();
+    assert_eq!(keys, fields);
 }
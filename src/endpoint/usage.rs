@@ -1,15 +1,76 @@
 use super::Result;
 use crate::{DeepLApi, Error};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 /// Response from the usage API
-#[derive(Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UsageResponse {
     pub character_count: u64,
     pub character_limit: u64,
 }
 
+/// Plan of a DeepL API key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Plan {
+    /// DeepL API Free, the key ends with `:fx`
+    Free,
+    /// DeepL API Pro
+    Pro,
+}
+
+/// Outcome of [`DeepLApi::verify_key`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyStatus {
+    /// The key is accepted by the endpoint
+    Valid { plan: Plan },
+    /// The key is wrong, or used against the wrong endpoint (free key on pro endpoint or
+    /// the reverse)
+    Invalid,
+    /// The key is valid but its character quota is exceeded
+    QuotaExceeded,
+}
+
 impl DeepLApi {
+    /// Check the configured key with a cheap `usage` request, to fail fast at startup.
+    ///
+    /// # Error
+    ///
+    /// Return [`Error::Connectivity`] if the endpoint can't be reached, and the DeepL error
+    /// for unexpected status codes. A rejected key is not an error but [`KeyStatus::Invalid`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// match deepl.verify_key().await? {
+    ///     KeyStatus::Valid { plan } => println!("using DeepL {plan:?}"),
+    ///     KeyStatus::Invalid => panic!("wrong DeepL key or endpoint"),
+    ///     KeyStatus::QuotaExceeded => eprintln!("DeepL quota exceeded"),
+    /// }
+    /// ```
+    pub async fn verify_key(&self) -> Result<KeyStatus> {
+        self.call("verify_key", None, async {
+            let response = self
+                .execute_retry(self.post(self.get_endpoint("usage")?))
+                .await?;
+
+            match response.status().as_u16() {
+                200..=299 => {}
+                401 | 403 => return Ok(KeyStatus::Invalid),
+                456 => return Ok(KeyStatus::QuotaExceeded),
+                _ => return super::extract_deepl_error(response).await,
+            }
+
+            let key = match &self.auth_override {
+                Some(key) => key,
+                None => &self.inner.keys.keys[self.active_key_index()],
+            };
+            let plan = if key.is_free() { Plan::Free } else { Plan::Pro };
+
+            Ok(KeyStatus::Valid { plan })
+        })
+        .await
+    }
+
     /// Get the current DeepL API usage
     ///
     /// # Example
@@ -24,29 +85,76 @@ impl DeepLApi {
     /// assert_ne!(response.character_count, 0);
     /// ```
     pub async fn get_usage(&self) -> Result<UsageResponse> {
-        let response = self
-            .post(self.get_endpoint("usage"))
-            .send()
-            .await
-            .map_err(|err| Error::RequestFail(err.to_string()))?;
+        self.call("get_usage", None, async {
+            let response = self
+                .execute_retry(self.post(self.get_endpoint("usage")?))
+                .await?;
 
-        if !response.status().is_success() {
-            return super::extract_deepl_error(response).await;
-        }
+            if !response.status().is_success() {
+                return super::extract_deepl_error(response).await;
+            }
 
-        let response: UsageResponse = response.json().await.map_err(|err| {
-            Error::InvalidResponse(format!("convert json bytes to Rust type: {err}"))
-        })?;
+            let response: UsageResponse = response.json().await.map_err(|err| {
+                Error::InvalidResponse(format!("convert json bytes to Rust type: {err}"))
+            })?;
 
-        Ok(response)
+            Ok(response)
+        })
+        .await
     }
 }
 
 #[tokio::test]
 async fn test_usage() {
-    let key = std::env::var("DEEPL_API_KEY").unwrap();
-    let api = DeepLApi::with(&key).new();
+    let (api, server) = crate::testing::mock_api().await;
     let response = api.get_usage().await.unwrap();
 
     assert_ne!(response.character_limit, 0);
+    server.assert_request_count(1);
+}
+
+#[tokio::test]
+async fn test_verify_key() {
+    use crate::testing::{FakeServer, Fault, Route};
+
+    let server = FakeServer::start().await;
+    let api = DeepLApi::with("key:fx").endpoint(server.endpoint()).new();
+    let status = api.verify_key().await.unwrap();
+    assert_eq!(status, KeyStatus::Valid { plan: Plan::Free });
+
+    let pro = api.with_key("key").unwrap();
+    let status = pro.verify_key().await.unwrap();
+    assert_eq!(status, KeyStatus::Valid { plan: Plan::Pro });
+
+    server.inject(Route::Usage, Fault::status(403).times(1));
+    assert_eq!(api.verify_key().await.unwrap(), KeyStatus::Invalid);
+
+    server.inject(Route::Usage, Fault::status(456).times(1));
+    assert_eq!(api.verify_key().await.unwrap(), KeyStatus::QuotaExceeded);
+
+    server.inject(Route::Usage, Fault::status(500).times(1));
+    assert!(api.verify_key().await.is_err());
+}
+
+#[tokio::test]
+async fn test_verify_key_unreachable() {
+    let api = DeepLApi::with("key")
+        .endpoint("http://127.0.0.1:1/v2/")
+        .new();
+    assert!(matches!(
+        api.verify_key().await,
+        Err(Error::Connectivity(_))
+    ));
+}
+
+#[tokio::test]
+#[ignore = "needs DEEPL_API_KEY"]
+async fn test_verify_key_live() {
+    let key = std::env::var("DEEPL_API_KEY").unwrap();
+    let api = DeepLApi::with(&key).new();
+
+    assert!(matches!(
+        api.verify_key().await.unwrap(),
+        KeyStatus::Valid { .. }
+    ));
 }
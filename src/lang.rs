@@ -4,7 +4,7 @@ use paste::paste;
 use serde::{Deserialize, Deserializer, Serialize};
 use thiserror::Error;
 
-#[derive(Debug, Error)]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
 pub enum LangConvertError {
     #[error("invalid language code {0}")]
     InvalidLang(String),
@@ -11,8 +11,8 @@
 //! ```rust
 //! use deepl::DeepLApi;
 //!
-//! let key = std::env::var("DEEPL_API_KEY").unwrap();
-//! let api = DeepLApi::with(&key).new();
+//! // read the key from `DEEPL_API_KEY`
+//! let api = DeepLApi::from_env().unwrap();
 //! let response = api.translate_text("Hello World", Lang::ZH).await.unwrap();
 //!
 //! assert!(!response.translations.is_empty());
@@ -25,22 +25,60 @@
 //! This project is licensed under MIT license.
 //!
 
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+pub mod blocking;
 mod endpoint;
 mod lang;
+mod limit;
+mod metrics;
+#[cfg(not(target_arch = "wasm32"))]
+mod provider;
+mod raw;
+mod retry;
+#[cfg(all(any(test, feature = "test-util"), not(target_arch = "wasm32")))]
+pub mod testing;
+/// Former name of [`testing`]
+#[cfg(all(any(test, feature = "test-util"), not(target_arch = "wasm32")))]
+#[doc(hidden)]
+pub use testing as test_util;
+#[cfg(not(target_arch = "wasm32"))]
+mod tls;
 
-use std::sync::Arc;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, USER_AGENT};
+#[cfg(feature = "gzip")]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
 //- Type Re-exporting
 pub use endpoint::{
-    document::{DocumentStatusResp, DocumentTranslateStatus, UploadDocumentResp},
+    document::{
+        DocumentOutputFormat, DocumentStatusResp, DocumentTranslateStatus, UploadDocumentResp,
+    },
     glossary,
     languages::{LangInfo, LangType},
-    translate::{TagHandling, TranslateTextResp},
-    usage::UsageResponse,
-    Error, Formality,
+    translate::{
+        estimate_characters, EmptyTextPolicy, ModelType, StreamErrorPolicy, TagHandling,
+        ToTranslatable, TranslateHtmlRequester, TranslateOptions, TranslateStream,
+        TranslateTextResp,
+    },
+    usage::{KeyStatus, Plan, UsageResponse},
+    DeepLApiError, Error, ErrorContext, ErrorKind, Formality, IntoParam, ResponseFuture,
 };
 pub use lang::{Lang, LangConvertError};
+pub use metrics::{RequestMetrics, ResponseMeta, SessionStats};
+#[cfg(not(target_arch = "wasm32"))]
+pub use provider::{ProviderFuture, TranslationProvider};
+pub use raw::RawResponse;
 pub use reqwest;
+pub use retry::{Attempt, AttemptOutcome, Attempts, RetryConfig};
+#[cfg(feature = "secrecy")]
+pub use secrecy;
+#[cfg(not(target_arch = "wasm32"))]
+pub use tls::{Certificate, TlsVersion};
+pub use tokio_util::sync::CancellationToken;
 //-
 
 /// A struct that contains necessary data for runtime. Data is stored in
@@ -69,93 +107,3367 @@ pub use reqwest;
 #[derive(Debug, Clone)]
 pub struct DeepLApi {
     inner: Arc<DeepLApiInner>,
+    /// Key used instead of the client wide ones, see [`DeepLApi::with_key`]
+    auth_override: Option<ApiKey>,
+    /// Headers added to every request of this clone, see [`DeepLApi::with_header`]
+    headers: HeaderMap,
+    /// Limit of the duration of every call of this clone, see [`DeepLApi::with_timeout`]
+    timeout: Option<Duration>,
+    /// See [`DeepLApi::with_deadline`]
+    deadline: Option<Instant>,
+    /// See [`DeepLApi::with_cancellation`]
+    cancel: Option<tokio_util::sync::CancellationToken>,
+    /// Replaces the retry policy of the client, `Some(None)` disables retries, see
+    /// [`DeepLApi::with_retry`]
+    retry: Option<Option<RetryConfig>>,
 }
 
 /// The inner instance which actually holds data
 #[derive(Debug)]
 struct DeepLApiInner {
     client: reqwest::Client,
-    key: String,
+    /// Client sending the requests built with `client`, see
+    /// [`DeepLApiBuilder::client_with_middleware`]
+    #[cfg(feature = "reqwest-middleware")]
+    middleware: Option<reqwest_middleware::ClientWithMiddleware>,
+    keys: KeyPool,
+    user_agent: HeaderValue,
+    request_hook: Option<RequestHook>,
+    on_response: Option<metrics::ResponseHook>,
+    stats: metrics::SessionCounters,
+    retry: Option<RetryConfig>,
+    rate_limiter: Option<limit::RateLimiter>,
+    concurrency: Option<tokio::sync::Semaphore>,
+    /// Target languages fetched for the first formality check, see
+    /// [`TranslateRequester::validate_formality`](endpoint::translate::TranslateRequester::validate_formality)
+    target_languages: tokio::sync::OnceCell<Vec<LangInfo>>,
     endpoint: reqwest::Url,
+    /// Send gzip compressed JSON bodies, disabled once the server rejects them
+    #[cfg(feature = "gzip")]
+    compress_requests: AtomicBool,
 }
 
 impl DeepLApi {
     /// Create a new api instance with auth key.
     pub fn with(key: &str) -> DeepLApiBuilder {
-        DeepLApiBuilder::init(key.to_string())
+        DeepLApiBuilder::init(key)
+    }
+
+    /// Create a new api instance with an auth key which is zeroized on drop.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use deepl::secrecy::SecretString;
+    ///
+    /// let key = SecretString::from(std::env::var("DEEPL_API_KEY")?);
+    /// let deepl = DeepLApi::with_secret(key).build()?;
+    /// ```
+    #[cfg(feature = "secrecy")]
+    pub fn with_secret(key: secrecy::SecretString) -> DeepLApiBuilder {
+        DeepLApiBuilder::init(key)
+    }
+
+    /// Create a new api instance from the environment variables `DEEPL_API_KEY` and, if set,
+    /// `DEEPL_SERVER_URL` as custom endpoint.
+    ///
+    /// # Error
+    ///
+    /// Return [`Error::InvalidApiKey`] if `DEEPL_API_KEY` is missing or empty, or any error
+    /// of [`DeepLApiBuilder::build`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let deepl = DeepLApi::from_env()?;
+    /// ```
+    pub fn from_env() -> Result<DeepLApi, Error> {
+        Self::with_env()?.build()
+    }
+
+    /// Like [`from_env`](Self::from_env), but return the builder for further customization.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let deepl = DeepLApi::with_env()?.client(my_client).build()?;
+    /// ```
+    pub fn with_env() -> Result<DeepLApiBuilder, Error> {
+        DeepLApiBuilder::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Return a cheap clone of this client which authenticates with another key, e.g. the key
+    /// of a customer in a multi-tenant service. The clone shares the connection pool and all
+    /// other settings with this client, and applies to every endpoint.
+    ///
+    /// The key is cleaned up and validated like in [`DeepLApiBuilder::build`]. Note that the
+    /// endpoint (free or pro) is not changed by the new key.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let deepl = DeepLApi::with(&shared_key).new();
+    /// let resp = deepl
+    ///     .with_key(&customer_key)?
+    ///     .translate_text("Hello World", Lang::DE)
+    ///     .await?;
+    /// ```
+    pub fn with_key(&self, key: &str) -> Result<DeepLApi, Error> {
+        let mut api = self.clone();
+        api.auth_override = Some(ApiKey::new(key)?);
+        Ok(api)
+    }
+
+    /// Return a cheap clone of this client which sends an extra header with every request,
+    /// replacing a header of the same name set by the client. Use the `header` setter of the
+    /// requesters to set a header on a single request.
+    ///
+    /// # Error
+    ///
+    /// Return [`Error::InvalidConfig`] if the name or value is invalid, or the name is
+    /// `Authorization`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let usage = deepl
+    ///     .with_header("X-Request-Id", &request_id)?
+    ///     .get_usage()
+    ///     .await?;
+    /// ```
+    pub fn with_header(&self, name: &str, value: &str) -> Result<DeepLApi, Error> {
+        let (name, value) = endpoint::parse_header(name, value)?;
+        let mut api = self.clone();
+        api.headers.insert(name, value);
+        Ok(api)
+    }
+
+    /// Return a cheap clone of this client whose calls fail with [`Error::DeadlineExceeded`] if
+    /// they take longer than `timeout`, including retries and waiting for the limits of the
+    /// client. Unlike [`DeepLApiBuilder::timeout`], which limits each HTTP request, this
+    /// limits the whole call. It also replaces the timeout of the builder for the HTTP requests
+    /// of these calls, so it may be longer. Use the `timeout` setter of the requesters for a
+    /// single request.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let usage = deepl
+    ///     .with_timeout(Duration::from_secs(2))
+    ///     .get_usage()
+    ///     .await?;
+    /// ```
+    pub fn with_timeout(&self, timeout: Duration) -> DeepLApi {
+        let mut api = self.clone();
+        api.timeout = Some(api.timeout.map_or(timeout, |t| t.min(timeout)));
+        api
+    }
+
+    /// Return a cheap clone of this client whose calls fail with [`Error::DeadlineExceeded`] if
+    /// they aren't done at `deadline`, e.g. the deadline of the request handler calling DeepL.
+    pub fn with_deadline(&self, deadline: Instant) -> DeepLApi {
+        let mut api = self.clone();
+        api.deadline = Some(api.deadline.map_or(deadline, |d| d.min(deadline)));
+        api
+    }
+
+    /// Return a cheap clone of this client retrying with `config` instead of the policy of
+    /// [`DeepLApiBuilder::retry`], e.g. more attempts for a batch job. Glossary creation and
+    /// document upload are still only retried when enabled on the requester.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let glossaries = deepl
+    ///     .with_retry(RetryConfig {
+    ///         max_attempts: 10,
+    ///         ..Default::default()
+    ///     })
+    ///     .list_all_glossaries()
+    ///     .await?;
+    /// ```
+    pub fn with_retry(&self, config: RetryConfig) -> DeepLApi {
+        let mut api = self.clone();
+        api.retry = Some(Some(config));
+        api
+    }
+
+    /// Return a cheap clone of this client that never retries, e.g. for latency sensitive
+    /// calls, see [`with_retry`](Self::with_retry)
+    pub fn without_retry(&self) -> DeepLApi {
+        let mut api = self.clone();
+        api.retry = Some(None);
+        api
+    }
+
+    /// Return a cheap clone of this client whose calls are aborted with [`Error::Cancelled`]
+    /// once `token` is cancelled. A partially downloaded document is removed then.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let token = CancellationToken::new();
+    /// let api = deepl.with_cancellation(token.clone());
+    /// tokio::spawn(async move {
+    ///     shutdown_signal().await;
+    ///     token.cancel();
+    /// });
+    /// api.download_document(&doc, "translated.docx").await?;
+    /// ```
+    pub fn with_cancellation(&self, token: tokio_util::sync::CancellationToken) -> DeepLApi {
+        let mut api = self.clone();
+        api.cancel = Some(token);
+        api
+    }
+
+    /// Index of the key currently used, in the order given to [`DeepLApiBuilder::keys`]
+    pub fn active_key_index(&self) -> usize {
+        self.inner.keys.state.lock().unwrap().active
     }
 
     fn del(&self, url: reqwest::Url) -> reqwest::RequestBuilder {
-        self.inner
-            .client
-            .delete(url)
-            .header("Authorization", &self.inner.key)
+        self.hook(self.inner.client.delete(url))
     }
 
     fn post(&self, url: reqwest::Url) -> reqwest::RequestBuilder {
+        self.hook(self.inner.client.post(url))
+    }
+
+    fn get(&self, url: reqwest::Url) -> reqwest::RequestBuilder {
+        self.hook(self.inner.client.get(url))
+    }
+
+    /// Wait for a free slot if the number of concurrent requests is limited. The permit is
+    /// held until the endpoint call returns, including the download of the response body.
+    async fn concurrency_permit(&self) -> Option<tokio::sync::SemaphorePermit<'_>> {
+        match &self.inner.concurrency {
+            Some(semaphore) => Some(
+                semaphore
+                    .acquire()
+                    .await
+                    .expect("the semaphore is never closed"),
+            ),
+            None => None,
+        }
+    }
+
+    fn hook(&self, req: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.inner.request_hook {
+            Some(hook) => (hook.0)(req),
+            None => req,
+        }
+    }
+
+    /// Send a request which must not be repeated, see [`execute_with`](Self::execute_with)
+    async fn execute(&self, req: reqwest::RequestBuilder) -> Result<reqwest::Response, Error> {
+        self.execute_with(req, false).await
+    }
+
+    /// Send an idempotent request, retried by the retry policy of the client
+    async fn execute_retry(
+        &self,
+        req: reqwest::RequestBuilder,
+    ) -> Result<reqwest::Response, Error> {
+        self.execute_with(req, true).await
+    }
+
+    /// Build and send the request, retrying transient failures if `retry` is set and the
+    /// client has a retry policy. A response is returned as is unless all attempts failed.
+    async fn execute_with(
+        &self,
+        req: reqwest::RequestBuilder,
+        retry: bool,
+    ) -> Result<reqwest::Response, Error> {
+        let req = self.prepare(req)?;
+        self.send_with_retry(req, retry, reqwest::Request::try_clone)
+            .await
+    }
+
+    /// Like [`execute_with`](Self::execute_with), for requests whose body can't be cloned, like
+    /// multipart forms. The request is built again for each attempt.
+    async fn execute_rebuilt(
+        &self,
+        make: impl Fn() -> reqwest::RequestBuilder,
+        retry: bool,
+    ) -> Result<reqwest::Response, Error> {
+        let req = self.prepare(make())?;
+        self.send_with_retry(req, retry, |_| self.prepare(make()).ok())
+            .await
+    }
+
+    fn prepare(&self, req: reqwest::RequestBuilder) -> Result<reqwest::Request, Error> {
+        let mut req = req.build()?;
+        req.headers_mut()
+            .insert(USER_AGENT, self.inner.user_agent.clone());
+        for (name, value) in &self.headers {
+            req.headers_mut().insert(name, value.clone());
+        }
+
+        Ok(req)
+    }
+
+    async fn send_with_retry(
+        &self,
+        mut req: reqwest::Request,
+        retry: bool,
+        next: impl Fn(&reqwest::Request) -> Option<reqwest::Request>,
+    ) -> Result<reqwest::Response, Error> {
+        let config = match &self.retry {
+            Some(config) => config.as_ref(),
+            None => self.inner.retry.as_ref(),
+        };
+        let Some(config) = config.filter(|_| retry) else {
+            return self.send_authorized(req).await;
+        };
+
+        let start = Instant::now();
+        let mut attempts = Attempts::default();
+        for attempt in 1.. {
+            let next = next(&req);
+            let result = self.send_authorized(req).await;
+            let (outcome, delay) = match &result {
+                Ok(resp) if retry::is_retryable_status(resp.status()) => (
+                    AttemptOutcome::Status(resp.status().as_u16()),
+                    // a distant Retry-After would stall the request, wait at most max_backoff
+                    retry::retry_after(resp)
+                        .map(|delay| delay.min(config.max_backoff))
+                        .unwrap_or_else(|| config.backoff(attempt)),
+                ),
+                // only transport failures, a request rejected before sending would fail again
+                Err(Error::Connectivity(_) | Error::Timeout { .. }) => {
+                    (AttemptOutcome::RequestFail, config.backoff(attempt))
+                }
+                Err(Error::Http(err)) if err.is_connect() || err.is_request() => {
+                    (AttemptOutcome::RequestFail, config.backoff(attempt))
+                }
+                _ => {
+                    if let (Ok(resp), true) = (&result, attempt > 1) {
+                        attempts.push(AttemptOutcome::Status(resp.status().as_u16()), None);
+                        attempts.set_elapsed(start.elapsed());
+                        metrics::record_attempts(attempts);
+                    }
+                    return result;
+                }
+            };
+
+            let Some(next) = next.filter(|_| attempt < config.max_attempts) else {
+                attempts.push(outcome, None);
+                attempts.set_elapsed(start.elapsed());
+                if attempts.len() == 1 {
+                    return result;
+                }
+                let source = match result {
+                    Ok(resp) => endpoint::extract_deepl_error::<()>(resp).await.unwrap_err(),
+                    Err(err) => err,
+                };
+                return Err(Error::RetriesExhausted {
+                    source: Box::new(source),
+                    attempts: Box::new(attempts),
+                });
+            };
+            attempts.push(outcome, Some(delay));
+            tokio::time::sleep(delay).await;
+            req = next;
+        }
+
+        unreachable!("attempts are unbounded")
+    }
+
+    /// Authenticate and send the request. With multiple keys, the request is sent again with
+    /// the next key when the current one runs out of quota.
+    async fn send_authorized(&self, mut req: reqwest::Request) -> Result<reqwest::Response, Error> {
+        if let Some(key) = &self.auth_override {
+            req.headers_mut().insert(AUTHORIZATION, key.header()?);
+            return self.send_request(req).await;
+        }
+
+        loop {
+            let (index, auth) = self.inner.keys.current()?;
+            req.headers_mut().insert(AUTHORIZATION, auth);
+            let retry = req.try_clone();
+            let resp = self.send_request(req).await?;
+
+            if resp.status().as_u16() != 456 || self.inner.keys.keys.len() == 1 {
+                return Ok(resp);
+            }
+            self.inner.keys.exhaust(index);
+            match retry {
+                Some(retry) => req = retry,
+                // the body can't be sent twice, later requests use the next key
+                None => return Ok(resp),
+            }
+        }
+    }
+
+    async fn send_request(&self, req: reqwest::Request) -> Result<reqwest::Response, Error> {
+        if let Some(limiter) = &self.inner.rate_limiter {
+            limiter.acquire().await;
+        }
+
+        self.inner.stats.add_request();
+        let resp = self.execute_http(req).await?;
+        metrics::record_response(&resp);
+
+        Ok(resp)
+    }
+
+    async fn execute_http(&self, mut req: reqwest::Request) -> Result<reqwest::Response, Error> {
+        // `Instant::now` panics on wasm32, where timeouts can't be configured anyway
+        #[cfg(not(target_arch = "wasm32"))]
+        let start = Instant::now();
+        // the timeout of the call replaces the one of the client, longer or shorter
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(timeout) = self.timeout {
+            *req.timeout_mut() = Some(timeout);
+        }
+        let timeout = |err: Error| match err {
+            #[cfg(not(target_arch = "wasm32"))]
+            Error::Timeout { source, .. } => Error::Timeout {
+                elapsed: Some(start.elapsed()),
+                source,
+            },
+            err => err,
+        };
+
+        #[cfg(feature = "reqwest-middleware")]
+        if let Some(client) = &self.inner.middleware {
+            let resp = client.execute(req).await;
+            return resp.map_err(|err| match err {
+                reqwest_middleware::Error::Reqwest(err) => timeout(err.into()),
+                err => Error::Middleware(err),
+            });
+        }
+
         self.inner
             .client
+            .execute(req)
+            .await
+            .map_err(|err| timeout(err.into()))
+    }
+
+    /// POST a JSON body, gzip compressed when enabled. If the server rejects the compressed body
+    /// with 415 Unsupported Media Type, it is sent again uncompressed, and so are all later ones.
+    async fn post_json<T: serde::Serialize + ?Sized>(
+        &self,
+        url: reqwest::Url,
+        body: &T,
+        retry: bool,
+    ) -> Result<reqwest::Response, Error> {
+        let json = serde_json::to_vec(body)
+            .map_err(|err| Error::InvalidInput(format!("fail to serialize body: {err}")))?;
+        self.post_json_bytes(url, json, retry).await
+    }
+
+    /// POST a body already serialized to JSON
+    async fn post_json_bytes(
+        &self,
+        url: reqwest::Url,
+        json: Vec<u8>,
+        retry: bool,
+    ) -> Result<reqwest::Response, Error> {
+        use reqwest::header::CONTENT_TYPE;
+
+        #[cfg(feature = "gzip")]
+        if self.inner.compress_requests.load(Ordering::Relaxed) {
+            use reqwest::header::CONTENT_ENCODING;
+
+            let req = self
+                .post(url.clone())
+                .header(CONTENT_TYPE, "application/json")
+                .header(CONTENT_ENCODING, "gzip")
+                .body(gzip_encode(&json));
+            let resp = self.execute_with(req, retry).await?;
+
+            if resp.status() != reqwest::StatusCode::UNSUPPORTED_MEDIA_TYPE {
+                return Ok(resp);
+            }
+            self.inner.compress_requests.store(false, Ordering::Relaxed);
+        }
+
+        let req = self
             .post(url)
-            .header("Authorization", &self.inner.key)
+            .header(CONTENT_TYPE, "application/json")
+            .body(json);
+        self.execute_with(req, retry).await
     }
 
-    fn get(&self, url: reqwest::Url) -> reqwest::RequestBuilder {
+    async fn post_form(
+        &self,
+        url: reqwest::Url,
+        body: String,
+        retry: bool,
+    ) -> Result<reqwest::Response, Error> {
+        let req = self
+            .post(url)
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/x-www-form-urlencoded",
+            )
+            .body(body);
+        self.execute_with(req, retry).await
+    }
+
+    fn get_endpoint(&self, route: &str) -> Result<reqwest::Url, Error> {
         self.inner
-            .client
-            .get(url)
-            .header("Authorization", &self.inner.key)
+            .endpoint
+            .join(route)
+            .map_err(|err| Error::InvalidEndpoint(format!("{route}: {err}")))
     }
 
-    fn get_endpoint(&self, route: &str) -> reqwest::Url {
-        self.inner.endpoint.join(route).unwrap()
+    /// Build the URL `route/{id}/{sub...}`, percent-encoding the identifier so it stays a single
+    /// path segment.
+    fn get_resource_endpoint(
+        &self,
+        route: &str,
+        id: &str,
+        sub: &[&str],
+    ) -> Result<reqwest::Url, Error> {
+        if matches!(id, "" | "." | "..") {
+            return Err(Error::InvalidEndpoint(format!(
+                "{route}: invalid identifier {id:?}"
+            )));
+        }
+
+        let mut url = self.get_endpoint(route)?;
+        url.path_segments_mut()
+            .map_err(|_| Error::InvalidEndpoint(format!("{route}: not a base URL")))?
+            .push(id)
+            .extend(sub);
+
+        Ok(url)
     }
 }
 
-/// The builder struct. **DO NOT USE IT IN YOUR APPS**
+const API_KEY_ENV: &str = "DEEPL_API_KEY";
+const SERVER_URL_ENV: &str = "DEEPL_SERVER_URL";
+
+/// The builder of [`DeepLApi`], created by [`DeepLApi::with`]. Setters take and return the
+/// builder by value, so it can be stored and passed around before it is finished by
+/// [`new`](Self::new) or [`build`](Self::build).
+///
+/// # Example
+///
+/// ```rust
+/// fn make_builder(key: &str) -> DeepLApiBuilder {
+///     DeepLApi::with(key).is_pro(true).timeout(Duration::from_secs(30))
+/// }
+///
+/// let mut builder = make_builder(&key);
+/// if let Ok(url) = std::env::var("MY_DEEPL_PROXY") {
+///     builder = builder.endpoint(url);
+/// }
+/// let deepl = builder.build()?;
+/// ```
 pub struct DeepLApiBuilder {
-    is_pro: bool,
+    is_pro: Option<bool>,
     client: Option<reqwest::Client>,
-    key: String,
+    configure_client: Option<Box<ConfigureClientFn>>,
+    #[cfg(feature = "reqwest-middleware")]
+    middleware: Option<reqwest_middleware::ClientWithMiddleware>,
+    key: KeyString,
+    keys: Vec<KeyString>,
+    key_cooldown: Duration,
+    endpoint: Option<String>,
+    allow_insecure_http: bool,
+    proxy: Option<String>,
+    proxy_auth: Option<(String, String)>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    #[cfg(not(target_arch = "wasm32"))]
+    min_tls_version: Option<TlsVersion>,
+    #[cfg(not(target_arch = "wasm32"))]
+    root_certificates: Vec<Certificate>,
+    #[cfg(not(target_arch = "wasm32"))]
+    accept_invalid_certs: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    pool_max_idle_per_host: Option<usize>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pool_idle_timeout: Option<Duration>,
+    app_info: Option<(String, String)>,
+    send_platform_info: bool,
+    request_hook: Option<RequestHook>,
+    on_response: Option<metrics::ResponseHook>,
+    retry: Option<RetryConfig>,
+    max_requests_per_second: Option<f64>,
+    rate_limit_burst: u32,
+    max_concurrent_requests: Option<usize>,
+    #[cfg(feature = "gzip")]
+    gzip: bool,
+    #[cfg(feature = "brotli")]
+    brotli: bool,
+    #[cfg(feature = "gzip")]
+    compress_requests: bool,
 }
 
 impl DeepLApiBuilder {
-    fn init(key: String) -> Self {
+    fn init(key: impl Into<KeyString>) -> Self {
         Self {
-            key,
-            is_pro: false,
+            key: key.into(),
+            keys: Vec::new(),
+            key_cooldown: Duration::from_secs(60 * 60),
+            proxy: None,
+            proxy_auth: None,
+            timeout: None,
+            connect_timeout: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            min_tls_version: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            root_certificates: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            accept_invalid_certs: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            pool_max_idle_per_host: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            pool_idle_timeout: None,
+            app_info: None,
+            send_platform_info: true,
+            request_hook: None,
+            on_response: None,
+            retry: None,
+            max_requests_per_second: None,
+            rate_limit_burst: 1,
+            max_concurrent_requests: None,
+            is_pro: None,
             client: None,
+            configure_client: None,
+            #[cfg(feature = "reqwest-middleware")]
+            middleware: None,
+            endpoint: None,
+            allow_insecure_http: false,
+            #[cfg(feature = "gzip")]
+            gzip: false,
+            #[cfg(feature = "brotli")]
+            brotli: false,
+            #[cfg(feature = "gzip")]
+            compress_requests: false,
+        }
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Result<Self, Error> {
+        let key = lookup(API_KEY_ENV).ok_or_else(|| {
+            Error::InvalidApiKey(format!("environment variable {API_KEY_ENV} is not set"))
+        })?;
+        if key.trim().is_empty() {
+            return Err(Error::InvalidApiKey(format!(
+                "environment variable {API_KEY_ENV} is empty"
+            )));
+        }
+
+        let mut builder = Self::init(key);
+        if let Some(url) = lookup(SERVER_URL_ENV).filter(|url| !url.is_empty()) {
+            builder = builder.endpoint(url);
         }
+
+        Ok(builder)
+    }
+
+    /// Additional keys to rotate through. When a request fails with 456 Quota Exceeded, the
+    /// key is marked as exhausted and the request is sent again with the next key. Once every
+    /// key is exhausted, requests fail with [`Error::KeysExhausted`]. With a single key, they
+    /// fail with [`Error::QuotaExceeded`] instead.
+    ///
+    /// The key given to [`DeepLApi::with`] is always the first one, and decides whether the
+    /// free or pro API is used.
+    ///
+    /// Key rotation needs [`std::time::Instant`], so it's not supported on wasm32.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let deepl = DeepLApi::with("first-key:fx")
+    ///     .keys(["second-key:fx", "third-key:fx"])
+    ///     .new();
+    /// ```
+    pub fn keys<I, S>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.keys = keys.into_iter().map(|k| k.as_ref().into()).collect();
+        self
+    }
+
+    /// How long an exhausted key is skipped before it is tried again, one hour by default
+    pub fn key_cooldown(mut self, cooldown: Duration) -> Self {
+        self.key_cooldown = cooldown;
+        self
+    }
+
+    /// Send all requests through an HTTP(S) or SOCKS proxy, e.g. `http://proxy.internal:8080`.
+    ///
+    /// Like all client settings of the builder, this only applies to the client created by the
+    /// builder. A user defined [`reqwest::Client`] set by [`client`](Self::client) takes
+    /// precedence and is used as is.
+    ///
+    /// An invalid proxy URL is reported by [`build`](Self::build).
+    pub fn proxy(mut self, url: &str) -> Self {
+        self.proxy = Some(url.to_string());
+        self
+    }
+
+    /// Authenticate to the [`proxy`](Self::proxy) with HTTP basic auth
+    pub fn proxy_auth(mut self, username: &str, password: &str) -> Self {
+        self.proxy_auth = Some((username.to_string(), password.to_string()));
+        self
+    }
+
+    /// Identify your application to DeepL. The name and version are appended to the
+    /// `User-Agent` header of every request, e.g. `deepl-rs/0.6.5 (linux; x86_64) rust/1.83.0
+    /// my-app/1.2.3`.
+    pub fn app_info(mut self, name: &str, version: &str) -> Self {
+        self.app_info = Some((name.to_string(), version.to_string()));
+        self
+    }
+
+    /// Whether to send the operating system, architecture and Rust version in the
+    /// `User-Agent` header, enabled by default.
+    pub fn send_platform_info(mut self, enable: bool) -> Self {
+        self.send_platform_info = enable;
+        self
+    }
+
+    /// Install a function applied to every request before it is sent, on every endpoint. It
+    /// can be used to add dynamic headers, e.g. a correlation ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let deepl = DeepLApi::with("key")
+    ///     .with_request_hook(|req| req.header("x-correlation-id", new_correlation_id()))
+    ///     .new();
+    /// ```
+    pub fn with_request_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(reqwest::RequestBuilder) -> reqwest::RequestBuilder + Send + Sync + 'static,
+    {
+        self.request_hook = Some(RequestHook(Arc::new(hook)));
+        self
+    }
+
+    /// Install a function receiving the [`RequestMetrics`] of every endpoint call, e.g. to
+    /// export latency and error counts. It's called once per call, after retries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let deepl = DeepLApi::with("key")
+    ///     .on_response(|metrics| {
+    ///         histogram!("deepl_latency", "endpoint" => metrics.endpoint).record(metrics.elapsed)
+    ///     })
+    ///     .new();
+    /// ```
+    pub fn on_response<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(RequestMetrics) + Send + Sync + 'static,
+    {
+        self.on_response = Some(metrics::ResponseHook(Arc::new(hook)));
+        self
+    }
+
+    /// Retry idempotent requests (translation, usage, languages, glossary reads and document
+    /// status) on connection errors, 429 Too Many Requests and 5xx server errors. The
+    /// `Retry-After` header is honored, in seconds or as an HTTP date, up to `max_backoff`.
+    /// Glossary creation and document upload are only retried when enabled on the requester.
+    /// Requests are not retried by default.
+    ///
+    /// Timeouts are retried too, with caution: DeepL may have processed and billed the timed
+    /// out request, so a translation may be billed twice. Set
+    /// [`no_retry`](endpoint::translate::TranslateRequester::no_retry) on the requests that must
+    /// not, or give them a longer [`timeout`](Self::timeout).
+    ///
+    /// Once all attempts failed, [`Error::RetriesExhausted`] is returned with the history of the
+    /// attempts. Retry needs a timer, so it's not supported on wasm32.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let deepl = DeepLApi::with("key")
+    ///     .retry(RetryConfig {
+    ///         max_attempts: 5,
+    ///         ..Default::default()
+    ///     })
+    ///     .new();
+    /// ```
+    pub fn retry(mut self, config: RetryConfig) -> Self {
+        self.retry = Some(config);
+        self
     }
 
-    /// Set the a user defined [`reqwest::Client`]
-    pub fn client(&mut self, c: reqwest::Client) -> &mut Self {
+    /// Limit the rate of requests sent by this client and all its clones, e.g. to stay below
+    /// the rate limit of DeepL in batch jobs. Every request waits for its turn before it is
+    /// sent, including retries. There is no limit by default.
+    ///
+    /// A rate which is not a positive number is reported by [`build`](Self::build).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let deepl = DeepLApi::with("key")
+    ///     .max_requests_per_second(5.0)
+    ///     .rate_limit_burst(10)
+    ///     .new();
+    /// ```
+    pub fn max_requests_per_second(mut self, rate: f64) -> Self {
+        self.max_requests_per_second = Some(rate);
+        self
+    }
+
+    /// Number of requests which may be sent at once after the client was idle, 1 by default.
+    /// Only applies with [`max_requests_per_second`](Self::max_requests_per_second).
+    pub fn rate_limit_burst(mut self, burst: u32) -> Self {
+        self.rate_limit_burst = burst;
+        self
+    }
+
+    /// Limit the number of endpoint calls in flight at once, shared by this client and all its
+    /// clones. Further calls wait for a free slot. A document download holds its slot until
+    /// the document is written. There is no limit by default.
+    ///
+    /// A limit of 0 is reported by [`build`](Self::build).
+    pub fn max_concurrent_requests(mut self, max: usize) -> Self {
+        self.max_concurrent_requests = Some(max);
+        self
+    }
+
+    /// Total timeout of a request, from connecting until the response body is read. There is
+    /// no timeout by default. Calls with a timeout of their own, see [`DeepLApi::with_timeout`],
+    /// use that one instead.
+    ///
+    /// Ignored if a user defined [`reqwest::Client`] is set by [`client`](Self::client),
+    /// configure the timeout on that client instead.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Timeout of the connect phase of a request. There is no timeout by default.
+    ///
+    /// Ignored if a user defined [`reqwest::Client`] is set by [`client`](Self::client),
+    /// configure the timeout on that client instead.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// The lowest TLS version accepted when connecting to DeepL. Building fails if the TLS
+    /// backend doesn't support the version.
+    ///
+    /// Ignored if a user defined [`reqwest::Client`] is set by [`client`](Self::client).
+    /// Not available on wasm32, where the browser owns the connection.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn min_tls_version(mut self, version: TlsVersion) -> Self {
+        self.min_tls_version = Some(version);
+        self
+    }
+
+    /// Trust an additional root certificate, e.g. of a TLS intercepting corporate proxy. The
+    /// system root certificates stay trusted.
+    ///
+    /// Ignored if a user defined [`reqwest::Client`] is set by [`client`](Self::client).
+    /// Not available on wasm32.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let cert = Certificate::from_pem(&std::fs::read("proxy-ca.pem")?)?;
+    /// let deepl = DeepLApi::with("Your DeepL Key")
+    ///     .add_root_certificate(cert)
+    ///     .build()?;
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn add_root_certificate(mut self, cert: Certificate) -> Self {
+        self.root_certificates.push(cert);
+        self
+    }
+
+    /// Accept any server certificate, even expired ones or ones for another host.
+    ///
+    /// **Dangerous**: anyone on the network path can then read the auth key and the texts.
+    /// Only meant for local testing, prefer [`add_root_certificate`](Self::add_root_certificate).
+    /// Not available on wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn danger_accept_invalid_certs(mut self, accept: bool) -> Self {
+        self.accept_invalid_certs = accept;
+        self
+    }
+
+    /// Maximum number of idle connections kept open to DeepL, unlimited by default. Set to 0
+    /// to open a new connection for every request.
+    ///
+    /// Ignored if a user defined [`reqwest::Client`] is set by [`client`](Self::client).
+    /// Not available on wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// How long an idle connection is kept open, 90 seconds by default.
+    ///
+    /// Ignored if a user defined [`reqwest::Client`] is set by [`client`](Self::client).
+    /// Not available on wasm32.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the a user defined [`reqwest::Client`]. Client settings of the builder, like
+    /// [`proxy`](Self::proxy), are ignored then.
+    pub fn client(mut self, c: reqwest::Client) -> Self {
         self.client = Some(c);
         self
     }
 
-    /// Set if you want to use the pro version DeepL Api
-    pub fn is_pro(&mut self, is_pro: bool) -> &mut Self {
-        self.is_pro = is_pro;
+    /// Customize the [`reqwest::ClientBuilder`] of the client created by [`build`](Self::build)
+    /// beyond the settings of this builder, without losing them.
+    ///
+    /// The settings of this builder, like [`timeout`](Self::timeout) and
+    /// [`proxy`](Self::proxy), are applied first, then the closure runs and may override them.
+    /// The User-Agent and the auth key are set on every request afterwards, so they can't be
+    /// lost. Ignored if a client is set by [`client`](Self::client).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let deepl = DeepLApi::with("Your DeepL Key")
+    ///     .timeout(Duration::from_secs(30))
+    ///     .configure_client(|builder| builder.tcp_nodelay(false))
+    ///     .build()?;
+    /// ```
+    pub fn configure_client(
+        mut self,
+        f: impl FnOnce(reqwest::ClientBuilder) -> reqwest::ClientBuilder + Send + Sync + 'static,
+    ) -> Self {
+        self.configure_client = Some(Box::new(f));
+        self
+    }
+
+    /// Send every request through a [`reqwest_middleware::ClientWithMiddleware`], e.g. for
+    /// tracing or caching shared with other HTTP clients. Like with [`client`](Self::client),
+    /// client settings of the builder are ignored, except for a client set by
+    /// [`client`](Self::client) which is then only used to build the requests.
+    ///
+    /// Errors of the middleware are returned as [`Error::Middleware`], errors of the inner
+    /// client as [`Error::Http`], [`Error::Connectivity`] or [`Error::Timeout`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+    ///     .with(TracingMiddleware::default())
+    ///     .build();
+    /// let deepl = DeepLApi::with("Your DeepL Key")
+    ///     .client_with_middleware(client)
+    ///     .new();
+    /// ```
+    #[cfg(feature = "reqwest-middleware")]
+    pub fn client_with_middleware(
+        mut self,
+        client: reqwest_middleware::ClientWithMiddleware,
+    ) -> Self {
+        self.middleware = Some(client);
+        self
+    }
+
+    /// Set if you want to use the pro version DeepL Api. When not set, the free API is used for
+    /// keys ending with `:fx`, and the pro API for all others.
+    pub fn is_pro(mut self, is_pro: bool) -> Self {
+        self.is_pro = Some(is_pro);
+        self
+    }
+
+    /// Send requests to a custom API base URL instead of the official DeepL endpoints, e.g. a
+    /// proxy like `https://my-proxy.internal/deepl/v2/`. Take precedence over
+    /// [`is_pro`](Self::is_pro). A missing trailing slash is added automatically.
+    ///
+    /// The URL is validated when the client is built. Plain HTTP is only accepted for loopback
+    /// hosts like `localhost`, unless [`allow_insecure_http`](Self::allow_insecure_http) is set.
+    pub fn endpoint(mut self, url: impl AsRef<str>) -> Self {
+        self.endpoint = Some(url.as_ref().to_string());
+        self
+    }
+
+    /// Accept a custom [`endpoint`](Self::endpoint) with plain HTTP on any host, e.g. a proxy in
+    /// an air-gapped network. The auth key is then sent in cleartext.
+    pub fn allow_insecure_http(mut self, allow: bool) -> Self {
+        self.allow_insecure_http = allow;
+        self
+    }
+
+    /// Accept gzip compressed responses. Only applies to the client created by the builder,
+    /// a user defined [`reqwest::Client`] keeps its own setting.
+    #[cfg(feature = "gzip")]
+    pub fn gzip(mut self, enable: bool) -> Self {
+        self.gzip = enable;
+        self
+    }
+
+    /// Accept brotli compressed responses. Only applies to the client created by the builder,
+    /// a user defined [`reqwest::Client`] keeps its own setting.
+    #[cfg(feature = "brotli")]
+    pub fn brotli(mut self, enable: bool) -> Self {
+        self.brotli = enable;
         self
     }
 
+    /// Send JSON request bodies (translate, glossary creation) gzip compressed. If the server
+    /// rejects compressed bodies, the request is sent again uncompressed and compression stays
+    /// disabled for this client.
+    #[cfg(feature = "gzip")]
+    pub fn compress_requests(mut self, enable: bool) -> Self {
+        self.compress_requests = enable;
+        self
+    }
+
+    /// Create a new instance of the DeepLApi
+    ///
+    /// # Panics
+    ///
+    /// Panic if the configuration is invalid, see [`build`](Self::build) for the cases. Use
+    /// [`build`](Self::build) to handle them as an [`Error`].
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(self) -> DeepLApi {
+        self.build().unwrap()
+    }
+
     /// Create a new instance of the DeepLApi
-    pub fn new(&self) -> DeepLApi {
-        let client = self.client.clone().unwrap_or_else(reqwest::Client::new);
-        let endpoint = if self.is_pro || !self.key.ends_with(":fx") {
-            "https://api.deepl.com/v2/"
-        } else {
-            "https://api-free.deepl.com/v2/"
+    ///
+    /// The auth key is cleaned up before use: surrounding whitespace (e.g. the trailing newline
+    /// of a key read from a file) and a duplicated `DeepL-Auth-Key ` prefix are removed.
+    ///
+    /// # Error
+    ///
+    /// Return [`Error::InvalidApiKey`] if the auth key is empty or can't be sent as an HTTP
+    /// header value, e.g. it contains control characters, [`Error::InvalidEndpoint`] if the custom endpoint
+    /// is not a valid HTTP(S) URL, [`Error::InsecureEndpoint`] if it uses plain HTTP on a
+    /// non-loopback host, or [`Error::InvalidConfig`] if the HTTP client can't be created.
+    pub fn build(mut self) -> Result<DeepLApi, Error> {
+        let client = match &self.client {
+            Some(client) => client.clone(),
+            None => self.build_client()?,
         };
+        let key = ApiKey::new(expose(&self.key))?;
+        let endpoint = match &self.endpoint {
+            Some(endpoint) => Self::parse_endpoint(endpoint, self.allow_insecure_http)?,
+            None if self.is_pro.unwrap_or(!key.is_free()) => {
+                reqwest::Url::parse("https://api.deepl.com/v2/").unwrap()
+            }
+            None => reqwest::Url::parse("https://api-free.deepl.com/v2/").unwrap(),
+        };
+
+        let keys = std::iter::once(Ok(key))
+            .chain(self.keys.iter().map(|key| ApiKey::new(expose(key))))
+            .collect::<Result<Vec<_>, _>>()?;
 
         let inner = DeepLApiInner {
-            key: format!("DeepL-Auth-Key {}", self.key),
+            keys: KeyPool::new(keys, self.key_cooldown),
+            user_agent: self.user_agent()?,
+            request_hook: self.request_hook.clone(),
+            on_response: self.on_response.clone(),
+            stats: Default::default(),
+            retry: self.retry.clone(),
+            rate_limiter: self.rate_limiter()?,
+            concurrency: match self.max_concurrent_requests {
+                Some(0) => {
+                    return Err(Error::InvalidConfig(
+                        "max concurrent requests must be at least 1".to_string(),
+                    ))
+                }
+                Some(max) => Some(tokio::sync::Semaphore::new(max)),
+                None => None,
+            },
+            target_languages: tokio::sync::OnceCell::new(),
             client,
-            endpoint: reqwest::Url::parse(endpoint).unwrap(),
+            #[cfg(feature = "reqwest-middleware")]
+            middleware: self.middleware,
+            endpoint,
+            #[cfg(feature = "gzip")]
+            compress_requests: AtomicBool::new(self.compress_requests),
         };
 
-        DeepLApi {
+        Ok(DeepLApi {
             inner: Arc::new(inner),
+            auth_override: None,
+            headers: HeaderMap::new(),
+            timeout: None,
+            deadline: None,
+            cancel: None,
+            retry: None,
+        })
+    }
+
+    fn rate_limiter(&self) -> Result<Option<limit::RateLimiter>, Error> {
+        match self.max_requests_per_second {
+            Some(rate) if !(rate.is_finite() && rate > 0.0) => Err(Error::InvalidConfig(format!(
+                "max requests per second must be a positive number, got {rate}"
+            ))),
+            Some(rate) => Ok(Some(limit::RateLimiter::new(rate, self.rate_limit_burst))),
+            None => Ok(None),
+        }
+    }
+
+    fn user_agent(&self) -> Result<HeaderValue, Error> {
+        let mut user_agent = format!("deepl-rs/{}", env!("CARGO_PKG_VERSION"));
+        if self.send_platform_info {
+            user_agent.push_str(&format!(
+                " ({}; {}) rust/{}",
+                std::env::consts::OS,
+                std::env::consts::ARCH,
+                option_env!("DEEPL_RUSTC_VERSION").unwrap_or("unknown"),
+            ));
+        }
+        if let Some((name, version)) = &self.app_info {
+            user_agent.push_str(&format!(" {name}/{version}"));
+        }
+
+        HeaderValue::try_from(user_agent)
+            .map_err(|_| Error::InvalidConfig("app info is not a valid header value".to_string()))
+    }
+
+    fn parse_endpoint(endpoint: &str, allow_insecure_http: bool) -> Result<reqwest::Url, Error> {
+        let mut url = reqwest::Url::parse(endpoint)
+            .map_err(|err| Error::InvalidEndpoint(format!("{endpoint}: {err}")))?;
+        if !matches!(url.scheme(), "http" | "https") || url.cannot_be_a_base() {
+            return Err(Error::InvalidEndpoint(format!(
+                "{endpoint}: not an HTTP(S) base URL"
+            )));
+        }
+        if url.scheme() == "http" && !allow_insecure_http && !is_loopback(&url) {
+            return Err(Error::InsecureEndpoint(endpoint.to_string()));
+        }
+
+        // without the trailing slash, `join` would replace the last path segment
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+
+        Ok(url)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn build_client(&mut self) -> Result<reqwest::Client, Error> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(version) = self.min_tls_version {
+            builder = builder.min_tls_version(version.into());
+        }
+        for cert in self.root_certificates.drain(..) {
+            builder = builder.add_root_certificate(cert.into_inner());
+        }
+        builder = builder.danger_accept_invalid_certs(self.accept_invalid_certs);
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        #[cfg(feature = "gzip")]
+        let builder = builder.gzip(self.gzip);
+        #[cfg(feature = "brotli")]
+        let builder = builder.brotli(self.brotli);
+
+        let builder = match (&self.proxy, &self.proxy_auth) {
+            (Some(url), auth) => {
+                let mut proxy = reqwest::Proxy::all(url)
+                    .map_err(|err| Error::InvalidConfig(format!("invalid proxy {url}: {err}")))?;
+                if let Some((username, password)) = auth {
+                    proxy = proxy.basic_auth(username, password);
+                }
+                builder.proxy(proxy)
+            }
+            (None, Some(_)) => {
+                return Err(Error::InvalidConfig(
+                    "proxy auth is set without a proxy".to_string(),
+                ))
+            }
+            (None, None) => builder,
+        };
+        let builder = match self.configure_client.take() {
+            Some(configure) => configure(builder),
+            None => builder,
+        };
+
+        builder
+            .build()
+            .map_err(|err| Error::InvalidConfig(format!("fail to create HTTP client: {err}")))
+    }
+
+    /// The browser owns the connection, so proxy and timeouts can't be configured
+    #[cfg(target_arch = "wasm32")]
+    fn build_client(&mut self) -> Result<reqwest::Client, Error> {
+        if self.proxy.is_some()
+            || self.proxy_auth.is_some()
+            || self.timeout.is_some()
+            || self.connect_timeout.is_some()
+        {
+            return Err(Error::InvalidConfig(
+                "proxy and timeouts are not supported on wasm32".to_string(),
+            ));
+        }
+
+        let builder = reqwest::Client::builder();
+        let builder = match self.configure_client.take() {
+            Some(configure) => configure(builder),
+            None => builder,
+        };
+
+        builder
+            .build()
+            .map_err(|err| Error::InvalidConfig(format!("fail to create HTTP client: {err}")))
+    }
+}
+
+fn is_loopback(url: &reqwest::Url) -> bool {
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    // IPv6 hosts are enclosed in brackets
+    let ip = host.trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost")
+        || ip
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// User defined changes of the HTTP client, see [`DeepLApiBuilder::configure_client`]
+type ConfigureClientFn = dyn FnOnce(reqwest::ClientBuilder) -> reqwest::ClientBuilder + Send + Sync;
+
+type RequestHookFn = dyn Fn(reqwest::RequestBuilder) -> reqwest::RequestBuilder + Send + Sync;
+
+/// User defined function applied to every request, see [`DeepLApiBuilder::with_request_hook`]
+#[derive(Clone)]
+struct RequestHook(Arc<RequestHookFn>);
+
+impl std::fmt::Debug for RequestHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RequestHook")
+    }
+}
+
+/// The auth keys of a client and which of them ran out of quota
+#[derive(Debug)]
+struct KeyPool {
+    keys: Vec<ApiKey>,
+    cooldown: Duration,
+    state: Mutex<KeyPoolState>,
+}
+
+#[derive(Debug)]
+struct KeyPoolState {
+    active: usize,
+    exhausted_until: Vec<Option<Instant>>,
+}
+
+impl KeyPool {
+    fn new(keys: Vec<ApiKey>, cooldown: Duration) -> Self {
+        let state = KeyPoolState {
+            active: 0,
+            exhausted_until: vec![None; keys.len()],
+        };
+
+        Self {
+            keys,
+            cooldown,
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Return the active key, skipping keys in their cooldown
+    fn current(&self) -> Result<(usize, HeaderValue), Error> {
+        let mut state = self.state.lock().unwrap();
+        let len = self.keys.len();
+
+        // the clock is only read once a key is exhausted, it's not available on wasm32
+        let index = (0..len)
+            .map(|offset| (state.active + offset) % len)
+            .find(|&i| state.exhausted_until[i].is_none_or(|until| until <= Instant::now()))
+            .ok_or(Error::KeysExhausted)?;
+        state.active = index;
+        state.exhausted_until[index] = None;
+
+        Ok((index, self.keys[index].header()?))
+    }
+
+    fn exhaust(&self, index: usize) {
+        let mut state = self.state.lock().unwrap();
+        state.exhausted_until[index] = Some(Instant::now() + self.cooldown);
+        if state.active == index {
+            state.active = (index + 1) % self.keys.len();
         }
     }
 }
+
+/// Trim the key and strip a duplicated `DeepL-Auth-Key ` prefix
+fn normalize_key(key: &str) -> Result<&str, Error> {
+    let key = key.trim();
+    let key = key
+        .strip_prefix("DeepL-Auth-Key")
+        .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+        .unwrap_or(key)
+        .trim();
+    if key.is_empty() {
+        return Err(Error::InvalidApiKey("key is empty".to_string()));
+    }
+
+    Ok(key)
+}
+
+#[cfg(feature = "secrecy")]
+type KeyString = secrecy::SecretString;
+#[cfg(not(feature = "secrecy"))]
+type KeyString = String;
+
+#[cfg(feature = "secrecy")]
+fn expose(key: &KeyString) -> &str {
+    use secrecy::ExposeSecret;
+    key.expose_secret()
+}
+
+#[cfg(not(feature = "secrecy"))]
+fn expose(key: &KeyString) -> &str {
+    key
+}
+
+/// A normalized auth key, zeroized on drop with the `secrecy` feature. Without it, the
+/// `Authorization` header is built once and reused by every request, with it the header is only
+/// built when a request is sent, so the key isn't kept in a second copy.
+#[derive(Clone)]
+struct ApiKey {
+    key: KeyString,
+    #[cfg(not(feature = "secrecy"))]
+    header: HeaderValue,
+}
+
+impl ApiKey {
+    fn new(key: &str) -> Result<Self, Error> {
+        let key = normalize_key(key)?;
+        // checked once, so sending with the key can't fail
+        #[cfg(not(feature = "secrecy"))]
+        let header = auth_header(key)?;
+        #[cfg(feature = "secrecy")]
+        auth_header(key)?;
+
+        Ok(Self {
+            key: key.into(),
+            #[cfg(not(feature = "secrecy"))]
+            header,
+        })
+    }
+
+    fn is_free(&self) -> bool {
+        expose(&self.key).ends_with(":fx")
+    }
+
+    /// The sensitive `Authorization` header value
+    #[cfg(not(feature = "secrecy"))]
+    fn header(&self) -> Result<HeaderValue, Error> {
+        Ok(self.header.clone())
+    }
+
+    /// The sensitive `Authorization` header value
+    #[cfg(feature = "secrecy")]
+    fn header(&self) -> Result<HeaderValue, Error> {
+        auth_header(expose(&self.key))
+    }
+}
+
+fn auth_header(key: &str) -> Result<HeaderValue, Error> {
+    let mut auth = HeaderValue::try_from(format!("DeepL-Auth-Key {key}")).map_err(|_| {
+        Error::InvalidApiKey("key contains characters not allowed in a header".to_string())
+    })?;
+    auth.set_sensitive(true);
+
+    Ok(auth)
+}
+
+impl std::fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ApiKey(..)")
+    }
+}
+
+/// Compress the given bytes with gzip
+#[cfg(feature = "gzip")]
+pub(crate) fn gzip_encode(bytes: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(bytes).unwrap();
+    encoder.finish().unwrap()
+}
+
+#[test]
+fn test_auth_header_is_sensitive() {
+    let api = DeepLApi::with("some-key:fx").new();
+
+    let auth = &api.inner.keys.keys[0].header().unwrap();
+    assert!(auth.is_sensitive());
+    assert_eq!(auth, "DeepL-Auth-Key some-key:fx");
+    assert!(!format!("{api:?}").contains("some-key"));
+}
+
+#[test]
+fn test_invalid_auth_key() {
+    let result = DeepLApi::with("some\nkey").build();
+    assert!(matches!(result, Err(Error::InvalidApiKey(_))));
+
+    let result = DeepLApi::with("some\x7fkey").build();
+    assert!(matches!(result, Err(Error::InvalidApiKey(_))));
+}
+
+#[tokio::test]
+async fn test_custom_endpoint() {
+    let server = testing::FakeServer::start().await;
+    let url = server.endpoint();
+    let proxy = format!(
+        "http://{}:{}/deepl/v2",
+        url.host_str().unwrap(),
+        url.port().unwrap()
+    );
+
+    let api = DeepLApi::with(testing::FAKE_AUTH_KEY)
+        .is_pro(true)
+        .endpoint(&proxy)
+        .new();
+    assert_eq!(api.inner.endpoint.as_str(), format!("{proxy}/"));
+
+    api.translate_text("Hello", Lang::DE).await.unwrap();
+    api.get_usage().await.unwrap();
+
+    let paths: Vec<_> = server
+        .requests()
+        .iter()
+        .map(|req| req.path().to_string())
+        .collect();
+    assert_eq!(paths, ["/deepl/v2/translate", "/deepl/v2/usage"]);
+}
+
+#[test]
+fn test_auth_key_normalization() {
+    let auth = |key: &str| {
+        DeepLApi::with(key)
+            .build()
+            .map(|api| api.inner.keys.keys[0].header().unwrap())
+    };
+
+    // trailing newline of a key read from a file
+    assert_eq!(auth("key:fx\n").unwrap(), "DeepL-Auth-Key key:fx");
+    assert_eq!(auth("  key:fx\r\n").unwrap(), "DeepL-Auth-Key key:fx");
+    // prefix included in the key
+    assert_eq!(
+        auth("DeepL-Auth-Key key:fx").unwrap(),
+        "DeepL-Auth-Key key:fx"
+    );
+
+    assert!(matches!(auth(""), Err(Error::InvalidApiKey(_))));
+    assert!(matches!(auth(" \n"), Err(Error::InvalidApiKey(_))));
+    assert!(matches!(
+        auth("DeepL-Auth-Key "),
+        Err(Error::InvalidApiKey(_))
+    ));
+
+    // the normalized key decides the endpoint
+    let api = DeepLApi::with("key:fx\n").new();
+    assert_eq!(
+        api.inner.endpoint.as_str(),
+        "https://api-free.deepl.com/v2/"
+    );
+}
+
+#[cfg(feature = "secrecy")]
+#[tokio::test]
+async fn test_secret_key() {
+    let server = testing::FakeServer::start().await;
+    let plain = DeepLApi::with("secret-key:fx")
+        .endpoint(server.endpoint())
+        .new();
+    let secret = DeepLApi::with_secret("secret-key:fx".into())
+        .endpoint(server.endpoint())
+        .new();
+    assert!(!format!("{secret:?}").contains("secret-key"));
+
+    plain.get_usage().await.unwrap();
+    secret.get_usage().await.unwrap();
+
+    let auth: Vec<_> = server
+        .requests()
+        .iter()
+        .map(|req| req.header("authorization").unwrap().to_string())
+        .collect();
+    assert_eq!(auth[0], "DeepL-Auth-Key secret-key:fx");
+    assert_eq!(auth[0], auth[1]);
+}
+
+#[tokio::test]
+async fn test_per_request_key() {
+    let server = testing::FakeServer::start().await;
+    let api = server.api();
+
+    api.translate_text("Hello", Lang::DE).await.unwrap();
+    api.with_key("tenant-key\n")
+        .unwrap()
+        .translate_text("Hello", Lang::DE)
+        .await
+        .unwrap();
+    api.with_key("tenant-key")
+        .unwrap()
+        .get_usage()
+        .await
+        .unwrap();
+
+    let keys: Vec<_> = server
+        .requests()
+        .iter()
+        .map(|req| req.header("authorization").unwrap().to_string())
+        .collect();
+    assert_eq!(
+        keys,
+        [
+            format!("DeepL-Auth-Key {}", testing::FAKE_AUTH_KEY),
+            "DeepL-Auth-Key tenant-key".to_string(),
+            "DeepL-Auth-Key tenant-key".to_string(),
+        ]
+    );
+    assert!(api.with_key("").is_err());
+}
+
+#[tokio::test]
+async fn test_key_rotation() {
+    use testing::{FakeServer, Fault, Route};
+
+    let server = FakeServer::start().await;
+    let api = DeepLApi::with("first-key")
+        .keys(["second-key", "third-key"])
+        .endpoint(server.endpoint())
+        .new();
+
+    server.inject(Route::Translate, Fault::status(456).times(1));
+    api.translate_text("Hello", Lang::DE).await.unwrap();
+    assert_eq!(api.active_key_index(), 1);
+    // clones share the rotation state
+    api.clone().get_usage().await.unwrap();
+
+    let keys: Vec<_> = server
+        .requests()
+        .iter()
+        .map(|req| req.header("authorization").unwrap().to_string())
+        .collect();
+    assert_eq!(
+        keys,
+        [
+            "DeepL-Auth-Key first-key",
+            "DeepL-Auth-Key second-key",
+            "DeepL-Auth-Key second-key"
+        ]
+    );
+
+    server.inject(Route::Usage, Fault::status(456));
+    assert!(matches!(api.get_usage().await, Err(Error::KeysExhausted)));
+    assert!(matches!(api.get_usage().await, Err(Error::KeysExhausted)));
+    assert_eq!(server.requests().len(), 5);
+}
+
+#[tokio::test]
+async fn test_quota_exceeded() {
+    use testing::{Fault, Route};
+
+    let (api, server) = testing::mock_api().await;
+    for fault in [
+        Fault::status(456),
+        Fault::status(456).body(""),
+        Fault::status(456).body("Quota Exceeded"),
+    ] {
+        server.inject(Route::Translate, fault.clone());
+        server.inject(Route::DocumentUpload, fault.clone());
+        server.inject(Route::Glossaries, fault);
+
+        let translated = api.translate_text("Hello", Lang::DE).await;
+        assert!(matches!(translated, Err(Error::QuotaExceeded(_))));
+        let uploaded = api
+            .upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+            .await;
+        assert!(matches!(uploaded, Err(Error::QuotaExceeded(_))));
+        let created = api
+            .create_glossary("quota")
+            .source_lang(Lang::EN)
+            .target_lang(Lang::DE)
+            .entries([("Hello", "Hallo")])
+            .send()
+            .await;
+        assert!(matches!(created, Err(Error::QuotaExceeded(_))));
+    }
+
+    server.inject(Route::Translate, Fault::status(456));
+    let Err(Error::QuotaExceeded(message)) = api.translate_text("Hello", Lang::DE).await else {
+        panic!("expected a quota error");
+    };
+    assert_eq!(
+        message,
+        "Quota exceeded. The character limit has been reached."
+    );
+}
+
+#[tokio::test]
+async fn test_authorization_failed() {
+    use testing::{Fault, Route};
+
+    let (api, server) = testing::mock_api().await;
+    server.inject(Route::Translate, Fault::status(403));
+    server.inject(Route::Usage, Fault::status(403).body(""));
+    server.inject(Route::Glossaries, Fault::status(403));
+
+    let Err(Error::AuthorizationFailed(message)) = api.translate_text("Hello", Lang::DE).await
+    else {
+        panic!("expected an authorization error");
+    };
+    assert_eq!(
+        message,
+        "Authorization failed. Please supply a valid auth_key parameter."
+    );
+    assert!(matches!(
+        api.get_usage().await,
+        Err(Error::AuthorizationFailed(_))
+    ));
+    assert!(matches!(
+        api.list_all_glossaries().await,
+        Err(Error::AuthorizationFailed(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_api_error() {
+    use testing::{Fault, Route};
+
+    let (api, server) = testing::mock_api().await;
+    server.inject(Route::Translate, Fault::status(400).times(1));
+    let err = api.translate_text("Hello", Lang::DE).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "request fail: 400 Bad Request: Bad request (in translate_text)"
+    );
+    assert!(matches!(
+        err,
+        Error::Api { status, error: Some(ref error), body: None, .. }
+            if status.as_u16() == 400 && error.message == "Bad request"
+    ));
+
+    server.inject(
+        Route::Languages,
+        Fault::status(400).body("Bad Request\n").times(1),
+    );
+    let err = api.languages(LangType::Source).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "request fail: 400 Bad Request: Bad Request (in languages)"
+    );
+    assert!(matches!(
+        err,
+        Error::Api { status, error: None, body: Some(ref body), .. }
+            if status.as_u16() == 400 && body == "Bad Request"
+    ));
+
+    server.inject(Route::Usage, Fault::status(404).body("").times(1));
+    let err = api.get_usage().await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "request fail: 404 Not Found (in get_usage)"
+    );
+    assert!(matches!(
+        err,
+        Error::Api { status, error: None, body: None, .. } if status.as_u16() == 404
+    ));
+
+    server.inject(
+        Route::Usage,
+        Fault::status(400).body("é".repeat(1000)).times(1),
+    );
+    let Err(Error::Api {
+        body: Some(body), ..
+    }) = api.get_usage().await
+    else {
+        panic!("expected an API error");
+    };
+    assert_eq!(body, "é".repeat(512));
+}
+
+#[tokio::test]
+async fn test_error_format() {
+    use testing::{Fault, Route};
+
+    let (api, server) = testing::mock_api().await;
+    let routes = [
+        Route::Translate,
+        Route::DocumentUpload,
+        Route::Glossaries,
+        Route::Languages,
+        Route::Usage,
+    ];
+    let cases = [
+        (
+            Fault::status(400).body(r#"{"message":"Bad request"}"#),
+            "request fail: 400 Bad Request: Bad request (in {endpoint})",
+        ),
+        (
+            Fault::status(502).body("<html><body>Bad Gateway</body></html>"),
+            "server error: 502 Bad Gateway (in {endpoint})",
+        ),
+    ];
+    for (fault, expected) in cases {
+        for route in routes {
+            server.inject(route, fault.clone());
+        }
+        let errors = [
+            api.translate_text("Hello", Lang::DE).await.unwrap_err(),
+            api.upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+                .await
+                .unwrap_err(),
+            api.list_all_glossaries().await.unwrap_err(),
+            api.languages(LangType::Target).await.unwrap_err(),
+            api.get_usage().await.unwrap_err(),
+        ];
+        for err in errors {
+            let endpoint = err.endpoint().unwrap();
+            assert_eq!(err.to_string(), expected.replace("{endpoint}", endpoint));
+            assert!(err.status().is_some());
+        }
+        server.clear_faults();
+    }
+}
+
+#[tokio::test]
+async fn test_error_classification() {
+    use testing::{Fault, Route};
+
+    fn classify(err: &Error) -> [bool; 5] {
+        [
+            err.is_retryable(),
+            err.is_rate_limited(),
+            err.is_quota_exceeded(),
+            err.is_auth_error(),
+            err.is_connectivity(),
+        ]
+    }
+
+    let (api, server) = testing::mock_api().await;
+    let cases = [
+        (429, [true, true, false, false, false]),
+        (503, [true, false, false, false, false]),
+        (400, [false, false, false, false, false]),
+        (403, [false, false, false, true, false]),
+        (456, [false, false, true, false, false]),
+    ];
+    for (status, expected) in cases {
+        server.inject(Route::Translate, Fault::status(status));
+        let err = api.translate_text("Hello", Lang::DE).await.unwrap_err();
+        assert_eq!(classify(&err), expected, "{status}");
+    }
+
+    let unreachable = DeepLApi::with("key")
+        .endpoint("http://127.0.0.1:1/v2/")
+        .new();
+    let err = unreachable.get_usage().await.unwrap_err();
+    assert_eq!(classify(&err), [true, false, false, false, true]);
+
+    let err = Error::ReadFileError {
+        path: "missing.txt".into(),
+        source: std::io::ErrorKind::NotFound.into(),
+    };
+    assert_eq!(classify(&err), [false; 5]);
+    assert_eq!(
+        classify(&Error::KeysExhausted),
+        [false, false, true, false, false]
+    );
+
+    // the classification of the last attempt
+    let api = retry_api(&server);
+    server.inject(Route::Usage, Fault::status(429));
+    let err = api.get_usage().await.unwrap_err();
+    assert!(matches!(err, Error::RetriesExhausted { .. }));
+    assert_eq!(classify(&err), [true, true, false, false, false]);
+}
+
+#[tokio::test]
+async fn test_http_error_source() {
+    use std::error::Error as _;
+
+    let api = DeepLApi::with("key")
+        .endpoint("http://127.0.0.1:1/v2/")
+        .new();
+    let err = api.translate_text("Hello", Lang::DE).await.unwrap_err();
+    assert!(err.to_string().starts_with("connection failed: "));
+    let source = err
+        .source()
+        .and_then(|source| source.downcast_ref::<reqwest::Error>())
+        .expect("the reqwest error is the source");
+    assert!(source.is_connect());
+    let Error::Connectivity(inner) = &err else {
+        panic!("expected a connectivity error");
+    };
+    assert!(inner.is_connect());
+}
+
+#[tokio::test]
+async fn test_connectivity() {
+    // nothing listens on a port bound and released right away
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let api = DeepLApi::with("key")
+        .endpoint(format!("http://127.0.0.1:{port}/v2/"))
+        .new();
+
+    let usage = api.get_usage().await.unwrap_err();
+    let upload = api
+        .upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+        .await
+        .unwrap_err();
+    for err in [usage, upload] {
+        assert!(matches!(err, Error::Connectivity(_)), "{err:?}");
+        assert!(err.is_retryable());
+        assert!(err.to_string().starts_with("connection failed: "));
+    }
+}
+
+#[tokio::test]
+async fn test_too_many_requests() {
+    use testing::{Fault, Route};
+
+    let (api, server) = testing::mock_api().await;
+    server.inject(
+        Route::Translate,
+        Fault::status(429).header("Retry-After", "3"),
+    );
+    let err = api.translate_text("Hello", Lang::DE).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "request throttled: 429 Too Many Requests, retry after 3s (in translate_text)"
+    );
+    assert!(matches!(
+        err,
+        Error::TooManyRequests { retry_after: Some(delay), .. } if delay == Duration::from_secs(3)
+    ));
+
+    // a date in the past means retry now
+    server.inject(
+        Route::Usage,
+        Fault::status(429).header("Retry-After", "Sun, 06 Nov 1994 08:49:37 GMT"),
+    );
+    assert!(matches!(
+        api.get_usage().await,
+        Err(Error::TooManyRequests {
+            retry_after: Some(Duration::ZERO),
+            ..
+        })
+    ));
+
+    server.inject(
+        Route::Usage,
+        Fault::status(429).header("Retry-After", "Fri, 01 Jan 2100 00:00:00 GMT"),
+    );
+    let Err(Error::TooManyRequests {
+        retry_after: Some(delay),
+        ..
+    }) = api.get_usage().await
+    else {
+        panic!("expected a throttled request");
+    };
+    assert!(delay > Duration::from_secs(365 * 24 * 3600));
+
+    server.inject(Route::Languages, Fault::status(429));
+    assert!(matches!(
+        api.languages(LangType::Source).await,
+        Err(Error::TooManyRequests {
+            retry_after: None,
+            ..
+        })
+    ));
+
+    server.inject(Route::Glossaries, Fault::status(503));
+    let err = api.list_all_glossaries().await.unwrap_err();
+    assert!(matches!(
+        err,
+        Error::TooManyRequests { status, retry_after: None, .. } if status.as_u16() == 503
+    ));
+    assert!(err.is_retryable() && !err.is_rate_limited());
+}
+
+#[tokio::test]
+async fn test_deepl_api_error() {
+    use testing::{Fault, Route};
+
+    let error: DeepLApiError = serde_json::from_str(
+        r#"{"message":"Unsupported glossary source and target language pair"}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        error,
+        DeepLApiError {
+            message: "Unsupported glossary source and target language pair".to_string(),
+            detail: None,
+        }
+    );
+
+    let body = r#"{"message":"Bad request","detail":"Value for 'target_lang' not supported."}"#;
+    let (api, server) = testing::mock_api().await;
+    server.inject(Route::Translate, Fault::status(400).body(body));
+    let err = api.translate_text("Hello", Lang::DE).await.unwrap_err();
+    let error = err.api_error().unwrap();
+    assert_eq!(error.message, "Bad request");
+    assert_eq!(
+        error.detail.as_deref(),
+        Some("Value for 'target_lang' not supported.")
+    );
+    assert_eq!(
+        err.to_string(),
+        "request fail: 400 Bad Request: Bad request: Value for 'target_lang' not supported. (in translate_text)"
+    );
+
+    server.inject(
+        Route::Translate,
+        Fault::status(400).body("<html>Bad</html>"),
+    );
+    let err = api.translate_text("Hello", Lang::DE).await.unwrap_err();
+    assert!(err.api_error().is_none());
+    assert!(matches!(err, Error::Api { body: Some(ref body), .. } if body == "<html>Bad</html>"));
+}
+
+#[tokio::test]
+async fn test_error_kind() {
+    use testing::{Fault, Route};
+
+    let (api, server) = testing::mock_api().await;
+    let cases = [
+        (400, ErrorKind::Api),
+        (403, ErrorKind::Auth),
+        (429, ErrorKind::RateLimited),
+        (456, ErrorKind::Quota),
+        (500, ErrorKind::Api),
+        (503, ErrorKind::RateLimited),
+    ];
+    for (status, kind) in cases {
+        server.inject(Route::Usage, Fault::status(status));
+        let err = api.get_usage().await.unwrap_err();
+        assert_eq!(err.kind(), kind, "{status}");
+    }
+
+    server.inject(Route::Usage, Fault::status(200).body("not json"));
+    assert_eq!(api.get_usage().await.unwrap_err().kind(), ErrorKind::Decode);
+    server.clear_faults();
+    let err = api.delete_glossary("missing").await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Api);
+    let err = api
+        .translate_text("Hello", Lang::DE)
+        .header("Authorization", "other")
+        .await
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Config);
+    let token = CancellationToken::new();
+    token.cancel();
+    let err = api.with_cancellation(token).get_usage().await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Cancelled);
+
+    let unreachable = DeepLApi::with("key")
+        .endpoint("http://127.0.0.1:1/v2/")
+        .new();
+    let err = unreachable.get_usage().await.unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Connectivity);
+    assert_eq!(err.kind().as_str(), "connectivity");
+
+    #[cfg(feature = "tokio-fs")]
+    {
+        let err = api
+            .upload_document("missing.txt", Lang::DE)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Io);
+    }
+
+    let api = retry_api(&server);
+    server.inject(Route::Usage, Fault::status(429));
+    let err = api.get_usage().await.unwrap_err();
+    assert!(matches!(err, Error::RetriesExhausted { .. }));
+    assert_eq!(err.kind(), ErrorKind::RateLimited);
+}
+
+#[tokio::test]
+async fn test_error_serialize() {
+    use serde_json::json;
+    use testing::{Fault, Route};
+
+    let (api, server) = testing::mock_api().await;
+    server.inject(
+        Route::Translate,
+        Fault::status(429).header("Retry-After", "3"),
+    );
+    let err = api.translate_text("Hello", Lang::DE).await.unwrap_err();
+    assert_eq!(
+        serde_json::to_value(&err).unwrap(),
+        json!({
+            "kind": "rate_limited",
+            "status": 429,
+            "message": "request throttled: 429 Too Many Requests, retry after 3s (in translate_text)",
+            "retryable": true,
+            "retry_after_secs": 3,
+            "endpoint": "translate_text",
+            "attempts": 1,
+        })
+    );
+
+    server.inject(
+        Route::Glossaries,
+        Fault::status(400).body(r#"{"message":"Bad request"}"#),
+    );
+    let err = api.delete_glossary("abc").await.unwrap_err();
+    assert_eq!(
+        serde_json::to_value(&err).unwrap(),
+        json!({
+            "kind": "api",
+            "status": 400,
+            "message": "request fail: 400 Bad Request: Bad request (in delete_glossary of abc)",
+            "retryable": false,
+            "endpoint": "delete_glossary",
+            "id": "abc",
+            "attempts": 1,
+        })
+    );
+
+    server.inject(Route::Usage, Fault::status(456));
+    let err = api.get_usage().await.unwrap_err();
+    let value = serde_json::to_value(&err).unwrap();
+    assert_eq!(value["kind"], "quota");
+    assert_eq!(value["status"], 456);
+    assert!(!value.to_string().contains(testing::FAKE_AUTH_KEY));
+
+    let err = Error::WriteFileError {
+        path: "out/hello.txt".into(),
+        source: std::io::ErrorKind::PermissionDenied.into(),
+    };
+    assert_eq!(
+        serde_json::to_value(&err).unwrap(),
+        json!({
+            "kind": "io",
+            "message": "fail to write file out/hello.txt: permission denied",
+            "retryable": false,
+            "path": "out/hello.txt",
+        })
+    );
+
+    let api = retry_api(&server);
+    server.inject(Route::Usage, Fault::status(500));
+    let err = api.get_usage().await.unwrap_err();
+    let value = serde_json::to_value(&err).unwrap();
+    assert_eq!(value["kind"], "api");
+    assert_eq!(value["status"], 500);
+    assert_eq!(value["attempts"], err.attempts().unwrap().len());
+}
+
+#[tokio::test]
+async fn test_util_alias() {
+    let server = test_util::FakeServer::start().await;
+    server.api().get_usage().await.unwrap();
+    server.assert_request_count(1);
+}
+
+#[tokio::test]
+async fn test_proxy() {
+    let server = testing::FakeServer::start().await;
+    let proxy = server.endpoint();
+    let api = DeepLApi::with("key")
+        .endpoint("http://deepl.invalid/v2/")
+        .allow_insecure_http(true)
+        .proxy(&format!(
+            "http://{}:{}",
+            proxy.host_str().unwrap(),
+            proxy.port().unwrap()
+        ))
+        .proxy_auth("user", "pass")
+        .new();
+
+    api.get_usage().await.unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests[0].path(), "/v2/usage");
+    assert_eq!(requests[0].header("host"), Some("deepl.invalid"));
+    assert_eq!(
+        requests[0].header("proxy-authorization"),
+        Some("Basic dXNlcjpwYXNz")
+    );
+}
+
+#[tokio::test]
+async fn test_user_agent() {
+    let server = testing::FakeServer::start().await;
+    let version = env!("CARGO_PKG_VERSION");
+
+    server.api().get_usage().await.unwrap();
+    DeepLApi::with("key")
+        .endpoint(server.endpoint())
+        .app_info("my-app", "1.2.3")
+        .new()
+        .translate_text("Hello", Lang::DE)
+        .await
+        .unwrap();
+    DeepLApi::with("key")
+        .endpoint(server.endpoint())
+        .app_info("my-app", "1.2.3")
+        .send_platform_info(false)
+        .new()
+        .get_usage()
+        .await
+        .unwrap();
+
+    let agents: Vec<_> = server
+        .requests()
+        .iter()
+        .map(|req| req.header("user-agent").unwrap().to_string())
+        .collect();
+    let platform = format!("({}; {})", std::env::consts::OS, std::env::consts::ARCH);
+    assert!(agents[0].starts_with(&format!("deepl-rs/{version} {platform} rust/")));
+    assert!(!agents[0].contains("my-app"));
+    assert!(agents[1].starts_with(&format!("deepl-rs/{version} {platform} rust/")));
+    assert!(agents[1].ends_with(" my-app/1.2.3"));
+    assert_eq!(agents[2], format!("deepl-rs/{version} my-app/1.2.3"));
+
+    let result = DeepLApi::with("key").app_info("my\napp", "1").build();
+    assert!(matches!(result, Err(Error::InvalidConfig(_))));
+}
+
+#[tokio::test]
+async fn test_request_hook() {
+    let server = testing::FakeServer::start().await;
+    let api = DeepLApi::with("key")
+        .endpoint(server.endpoint())
+        .with_request_hook(|req| req.header("x-test", "1"))
+        .new();
+
+    api.translate_text("Hello", Lang::DE).await.unwrap();
+    api.get_usage().await.unwrap();
+
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests.iter().all(|req| req.header("x-test") == Some("1")));
+}
+
+#[tokio::test]
+async fn test_stored_builder() {
+    fn make_builder(key: &str) -> DeepLApiBuilder {
+        DeepLApi::with(key)
+            .is_pro(true)
+            .timeout(Duration::from_secs(30))
+            .app_info("my-app", "1.2.3")
+    }
+
+    let server = testing::FakeServer::start().await;
+    let mut builder = make_builder(testing::FAKE_AUTH_KEY);
+    // the endpoint set later wins over the one of `is_pro`
+    builder = builder.endpoint(server.endpoint());
+    let api = builder.build().unwrap();
+
+    assert_eq!(api.inner.endpoint, server.endpoint());
+    assert!(api
+        .inner
+        .user_agent
+        .to_str()
+        .unwrap()
+        .ends_with("my-app/1.2.3"));
+    let resp = api.translate_text("Hello", Lang::DE).await.unwrap();
+    assert_eq!(resp.to_string(), "[DE] Hello");
+}
+
+#[cfg(test)]
+fn retry_api(server: &testing::FakeServer) -> DeepLApi {
+    DeepLApi::with(testing::FAKE_AUTH_KEY)
+        .endpoint(server.endpoint())
+        .retry(RetryConfig {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(50),
+            jitter: false,
+        })
+        .new()
+}
+
+#[tokio::test]
+async fn test_retry() {
+    use testing::{Fault, Route};
+
+    let server = testing::FakeServer::start().await;
+    let api = retry_api(&server);
+
+    server.inject(Route::Translate, Fault::status(503).times(2));
+    api.translate_text("Hello", Lang::DE).await.unwrap();
+    assert_eq!(server.requests().len(), 3);
+
+    // Retry-After takes precedence over the backoff
+    server.inject(Route::Usage, Fault::status(429).header("retry-after", "0"));
+    let err = api.get_usage().await.err().unwrap();
+    let attempts = err.attempts().unwrap();
+    let history: Vec<_> = attempts.iter().copied().collect();
+    let retried = Attempt {
+        outcome: AttemptOutcome::Status(429),
+        backoff: Some(Duration::ZERO),
+    };
+    let last = Attempt {
+        backoff: None,
+        ..retried
+    };
+    assert_eq!(history, [retried, retried, last]);
+    assert!(matches!(
+        err,
+        Error::RetriesExhausted { ref source, .. }
+            if matches!(**source, Error::TooManyRequests { status, .. } if status.as_u16() == 429)
+    ));
+
+    // client errors are not retried
+    server.clear_faults();
+    server.inject(Route::Languages, Fault::status(400).times(1));
+    let before = server.requests().len();
+    assert!(api.languages(LangType::Target).await.is_err());
+    assert_eq!(server.requests().len(), before + 1);
+}
+
+#[tokio::test]
+async fn test_retry_after_capped() {
+    use testing::{Fault, Route};
+
+    let server = testing::FakeServer::start().await;
+    let api = retry_api(&server);
+
+    for retry_after in ["86400", "Fri, 31 Dec 9999 23:59:59 GMT"] {
+        server.inject(
+            Route::Translate,
+            Fault::status(429)
+                .header("retry-after", retry_after)
+                .times(1),
+        );
+        let (_, meta) = tokio::time::timeout(
+            Duration::from_secs(5),
+            api.translate_text("Hello", Lang::DE).send_with_meta(),
+        )
+        .await
+        .expect("Retry-After is capped by max_backoff")
+        .unwrap();
+        let first = meta.attempts.iter().next().unwrap();
+        assert_eq!(
+            first.backoff,
+            Some(Duration::from_millis(50)),
+            "{retry_after}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_retry_meta() {
+    use testing::{Fault, Route};
+
+    let server = testing::FakeServer::start().await;
+    let api = retry_api(&server);
+
+    server.inject(Route::Translate, Fault::status(503).times(2));
+    let (_, meta) = api
+        .translate_text("Hello", Lang::DE)
+        .send_with_meta()
+        .await
+        .unwrap();
+    let history: Vec<_> = meta.attempts.iter().copied().collect();
+    let failed = |backoff| Attempt {
+        outcome: AttemptOutcome::Status(503),
+        backoff: Some(Duration::from_millis(backoff)),
+    };
+    let success = Attempt {
+        outcome: AttemptOutcome::Status(200),
+        backoff: None,
+    };
+    assert_eq!(history, [failed(10), failed(20), success]);
+    assert!(meta.attempts.elapsed() >= Duration::from_millis(30));
+    assert_eq!(meta.status, reqwest::StatusCode::OK);
+
+    // a request that succeeds at once has a single attempt
+    let (_, meta) = api
+        .translate_text("Hello", Lang::DE)
+        .send_with_meta()
+        .await
+        .unwrap();
+    assert_eq!(meta.attempts.iter().copied().collect::<Vec<_>>(), [success]);
+}
+
+#[tokio::test]
+async fn test_retry_override() {
+    use testing::{Fault, Route};
+
+    let server = testing::FakeServer::start().await;
+    let api = retry_api(&server);
+
+    // the client would retry
+    server.inject(Route::Translate, Fault::status(503).times(1));
+    let err = api
+        .translate_text("Hello", Lang::DE)
+        .no_retry()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::TooManyRequests { .. }), "{err:?}");
+    let attempts = err.attempts().unwrap();
+    let single = Attempt {
+        outcome: AttemptOutcome::Status(503),
+        backoff: None,
+    };
+    assert_eq!(attempts.iter().copied().collect::<Vec<_>>(), [single]);
+    server.assert_request_count(1);
+
+    server.inject(Route::Translate, Fault::status(503).times(4));
+    let err = api
+        .translate_text("Hello", Lang::DE)
+        .retry_policy(RetryConfig {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(1),
+            jitter: false,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.attempts().unwrap().len(), 4);
+    server.assert_request_count(5);
+
+    server.inject(Route::DocumentUpload, Fault::status(503).times(1));
+    let result = api
+        .upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+        .retry(true)
+        .no_retry()
+        .await;
+    assert!(result.is_err());
+    server.assert_request_count(6);
+
+    server.inject(Route::Glossaries, Fault::status(503).times(1));
+    let result = api
+        .create_glossary("retry")
+        .source_lang(Lang::EN)
+        .target_lang(Lang::DE)
+        .entries([("Hello", "Hallo")])
+        .retry(true)
+        .no_retry()
+        .send()
+        .await;
+    assert!(result.is_err());
+    server.assert_request_count(7);
+
+    server.inject(Route::Languages, Fault::status(503).times(1));
+    assert!(api
+        .without_retry()
+        .languages(LangType::Target)
+        .await
+        .is_err());
+    server.assert_request_count(8);
+
+    // a client without retry policy
+    let api = DeepLApi::with(testing::FAKE_AUTH_KEY)
+        .endpoint(server.endpoint())
+        .new();
+    server.inject(Route::Glossaries, Fault::status(503).times(2));
+    let glossaries = api
+        .with_retry(RetryConfig {
+            initial_backoff: Duration::from_millis(1),
+            jitter: false,
+            ..Default::default()
+        })
+        .list_all_glossaries()
+        .await;
+    assert!(glossaries.is_ok());
+    server.assert_request_count(11);
+}
+
+#[tokio::test]
+async fn test_retry_connection_error() {
+    let api = DeepLApi::with("key")
+        .endpoint("http://127.0.0.1:1/v2/")
+        .retry(RetryConfig {
+            max_attempts: 2,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(10),
+            jitter: false,
+        })
+        .new();
+
+    let err = api.get_usage().await.err().unwrap();
+    let attempts = err.attempts().unwrap();
+    assert_eq!(attempts.len(), 2);
+    assert!(attempts
+        .iter()
+        .all(|attempt| attempt.outcome == AttemptOutcome::RequestFail));
+    assert!(attempts.elapsed() >= Duration::from_millis(10));
+}
+
+#[tokio::test]
+async fn test_retry_opt_in() {
+    use testing::{Fault, Route};
+
+    let server = testing::FakeServer::start().await;
+    let api = retry_api(&server);
+    let create = |retry| {
+        api.create_glossary("retry")
+            .source_lang(Lang::EN)
+            .target_lang(Lang::DE)
+            .entries([("Hello", "Hallo")])
+            .retry(retry)
+            .send()
+    };
+
+    server.inject(Route::Glossaries, Fault::status(503).times(1));
+    assert!(create(false).await.is_err());
+    assert_eq!(server.requests().len(), 1);
+
+    server.inject(Route::Glossaries, Fault::status(503).times(1));
+    create(true).await.unwrap();
+    assert_eq!(server.requests().len(), 3);
+
+    server.inject(Route::DocumentUpload, Fault::status(503).times(1));
+    api.upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+        .retry(true)
+        .await
+        .unwrap();
+    assert_eq!(server.requests().len(), 5);
+}
+
+#[tokio::test]
+async fn test_rate_limit() {
+    let server = testing::FakeServer::start().await;
+    let api = DeepLApi::with(testing::FAKE_AUTH_KEY)
+        .endpoint(server.endpoint())
+        .max_requests_per_second(5.0)
+        .new();
+
+    let start = Instant::now();
+    let calls = (0..20).map(|_| {
+        let api = api.clone();
+        tokio::spawn(async move { api.translate_text("Hello", Lang::DE).await.map(|_| ()) })
+    });
+    for call in calls.collect::<Vec<_>>() {
+        call.await.unwrap().unwrap();
+    }
+
+    assert!(start.elapsed() >= Duration::from_secs(3));
+    assert_eq!(server.requests().len(), 20);
+    assert!(DeepLApi::with("key")
+        .max_requests_per_second(0.0)
+        .build()
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_max_concurrent_requests() {
+    let server = testing::FakeServer::start().await;
+    server.delay(testing::Route::Translate, Duration::from_millis(100));
+    let api = DeepLApi::with(testing::FAKE_AUTH_KEY)
+        .endpoint(server.endpoint())
+        .max_concurrent_requests(2)
+        .new();
+
+    let calls = (0..6).map(|_| {
+        let api = api.clone();
+        tokio::spawn(async move { api.translate_text("Hello", Lang::DE).await.map(|_| ()) })
+    });
+    for call in calls.collect::<Vec<_>>() {
+        call.await.unwrap().unwrap();
+    }
+
+    assert_eq!(server.requests().len(), 6);
+    assert_eq!(server.max_concurrent_requests(), 2);
+    assert!(DeepLApi::with("key")
+        .max_concurrent_requests(0)
+        .build()
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_on_response() {
+    use testing::{Fault, Route};
+
+    let server = testing::FakeServer::start().await;
+    let collected = Arc::new(Mutex::new(Vec::new()));
+    let sink = collected.clone();
+    let api = DeepLApi::with(testing::FAKE_AUTH_KEY)
+        .endpoint(server.endpoint())
+        .on_response(move |metrics| sink.lock().unwrap().push(metrics))
+        .new();
+
+    api.translate_text("Hello", Lang::DE).await.unwrap();
+    server.inject(Route::Languages, Fault::status(400));
+    assert!(api.languages(LangType::Target).await.is_err());
+    let doc = api
+        .upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+        .await
+        .unwrap();
+    api.download_document_bytes(&doc).await.unwrap();
+
+    let metrics = collected.lock().unwrap();
+    let endpoints: Vec<_> = metrics.iter().map(|m| m.endpoint).collect();
+    assert_eq!(
+        endpoints,
+        [
+            "translate_text",
+            "languages",
+            "upload_document",
+            "download_document_bytes"
+        ]
+    );
+
+    assert_eq!(metrics[0].status, Some(200));
+    assert_eq!(metrics[0].characters, Some(5));
+    assert!(metrics[0].error.is_none());
+    assert!(metrics[0].elapsed > Duration::ZERO);
+
+    assert_eq!(metrics[1].status, Some(400));
+    assert!(metrics[1].error.is_some());
+
+    assert_eq!(metrics[3].bytes, Some("[DE] Hello".len() as u64));
+}
+
+#[tokio::test]
+async fn test_timeout() {
+    use testing::{FakeServer, Route};
+
+    let server = FakeServer::start().await;
+    server.delay(Route::Usage, Duration::from_secs(5));
+    let api = DeepLApi::with("key")
+        .endpoint(server.endpoint())
+        .timeout(Duration::from_millis(100))
+        .connect_timeout(Duration::from_secs(1))
+        .new();
+
+    let start = Instant::now();
+    let err = api.get_usage().await.unwrap_err();
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert!(
+        matches!(err, Error::Timeout { elapsed: Some(elapsed), .. } if elapsed >= Duration::from_millis(100))
+    );
+    let source = std::error::Error::source(&err)
+        .and_then(|source| source.downcast_ref::<reqwest::Error>())
+        .expect("the reqwest error is the source");
+    assert!(source.is_timeout());
+    assert!(err.is_timeout());
+    assert!(err.is_retryable());
+    assert_eq!(err.kind(), ErrorKind::Connectivity);
+
+    let doc = api
+        .upload_document_from_bytes(b"Hello World".to_vec(), "hello.txt", Lang::DE)
+        .await
+        .unwrap();
+    server.stall_body(Route::DocumentResult, Duration::from_secs(5));
+    let err = api.download_document_bytes(&doc).await.unwrap_err();
+    assert!(err.is_timeout(), "{err:?}");
+}
+
+#[test]
+fn test_invalid_proxy() {
+    let result = DeepLApi::with("key").proxy("not a proxy").build();
+    assert!(matches!(result, Err(Error::InvalidConfig(_))));
+
+    let result = DeepLApi::with("key").proxy_auth("user", "pass").build();
+    assert!(matches!(result, Err(Error::InvalidConfig(_))));
+}
+
+#[test]
+fn test_endpoint_from_key_suffix() {
+    const FREE: &str = "https://api-free.deepl.com/v2/";
+    const PRO: &str = "https://api.deepl.com/v2/";
+
+    let endpoint = |builder: DeepLApiBuilder| builder.new().inner.endpoint.to_string();
+    assert_eq!(endpoint(DeepLApi::with("key:fx")), FREE);
+    assert_eq!(endpoint(DeepLApi::with("key")), PRO);
+    assert_eq!(endpoint(DeepLApi::with("key:fx").is_pro(true)), PRO);
+    assert_eq!(endpoint(DeepLApi::with("key").is_pro(false)), FREE);
+}
+
+#[test]
+fn test_builder_from_env() {
+    let env = |vars: &'static [(&'static str, &'static str)]| {
+        move |name: &str| {
+            vars.iter()
+                .find(|(k, _)| *k == name)
+                .map(|(_, v)| v.to_string())
+        }
+    };
+
+    let api = DeepLApiBuilder::from_lookup(env(&[("DEEPL_API_KEY", "key:fx")]))
+        .unwrap()
+        .new();
+    assert_eq!(
+        api.inner.endpoint.as_str(),
+        "https://api-free.deepl.com/v2/"
+    );
+
+    let api = DeepLApiBuilder::from_lookup(env(&[
+        ("DEEPL_API_KEY", "key"),
+        ("DEEPL_SERVER_URL", "http://localhost:3000/v2"),
+    ]))
+    .unwrap()
+    .new();
+    assert_eq!(api.inner.endpoint.as_str(), "http://localhost:3000/v2/");
+
+    let result = DeepLApiBuilder::from_lookup(env(&[]));
+    assert!(matches!(result, Err(Error::InvalidApiKey(msg)) if msg.contains("not set")));
+
+    let result = DeepLApiBuilder::from_lookup(env(&[("DEEPL_API_KEY", " ")]));
+    assert!(matches!(result, Err(Error::InvalidApiKey(msg)) if msg.contains("empty")));
+}
+
+#[tokio::test]
+async fn test_resource_endpoint_encoding() {
+    let server = testing::FakeServer::start().await;
+    let url = server.endpoint();
+    // without trailing slash
+    let api = DeepLApi::with("key")
+        .endpoint(format!(
+            "http://{}:{}/v2",
+            url.host_str().unwrap(),
+            url.port().unwrap()
+        ))
+        .new();
+
+    let url = api
+        .get_resource_endpoint("glossaries", "a/b?c", &["entries"])
+        .unwrap();
+    assert!(url.as_str().ends_with("/v2/glossaries/a%2Fb%3Fc/entries"));
+
+    assert!(api.retrieve_glossary_details("a/b").await.is_err());
+    assert_eq!(server.requests()[0].path(), "/v2/glossaries/a%2Fb");
+
+    for id in ["", ".", ".."] {
+        let result = api.delete_glossary(id).await;
+        assert!(matches!(result, Err(Error::InvalidEndpoint(_))), "{id}");
+    }
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[test]
+fn test_invalid_endpoint() {
+    for url in [
+        "not a url",
+        "ftp://example.com/v2/",
+        "mailto:someone@example.com",
+    ] {
+        let result = DeepLApi::with("key").endpoint(url).build();
+        assert!(matches!(result, Err(Error::InvalidEndpoint(_))), "{url}");
+    }
+}
+
+#[cfg(feature = "gzip")]
+#[tokio::test]
+async fn test_compressed_round_trip() {
+    let server = testing::FakeServer::start().await;
+    let plain = server.api();
+    let compressed = DeepLApi::with(testing::FAKE_AUTH_KEY)
+        .endpoint(server.endpoint())
+        .gzip(true)
+        .compress_requests(true)
+        .new();
+
+    let expect = plain.translate_text("Hello", Lang::DE).await.unwrap();
+    let get = compressed.translate_text("Hello", Lang::DE).await.unwrap();
+    assert_eq!(get.to_string(), expect.to_string());
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("content-encoding"), None);
+    assert_eq!(requests[1].header("content-encoding"), Some("gzip"));
+    assert!(requests[1]
+        .header("accept-encoding")
+        .unwrap()
+        .contains("gzip"));
+    assert_eq!(requests[0].body(), requests[1].body());
+}
+
+#[cfg(feature = "gzip")]
+#[tokio::test]
+async fn test_compressed_request_fallback() {
+    let server = testing::FakeServer::start().await;
+    server.reject_compressed_requests(true);
+    let api = DeepLApi::with(testing::FAKE_AUTH_KEY)
+        .endpoint(server.endpoint())
+        .compress_requests(true)
+        .new();
+
+    api.translate_text("Hello", Lang::DE).await.unwrap();
+    api.translate_text("Hello", Lang::DE).await.unwrap();
+
+    let encodings: Vec<_> = server
+        .requests()
+        .iter()
+        .map(|req| req.header("content-encoding").map(str::to_string))
+        .collect();
+    assert_eq!(encodings, [Some("gzip".to_string()), None, None]);
+}
+
+#[tokio::test]
+async fn test_request_headers() {
+    let server = testing::FakeServer::start().await;
+    let api = server.api().with_header("X-Tenant", "acme").unwrap();
+
+    api.translate_text("Hello", Lang::DE)
+        .header("X-Request-Id", "translate")
+        .header("User-Agent", "custom")
+        .await
+        .unwrap();
+    api.upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+        .header("X-Request-Id", "upload")
+        .await
+        .unwrap();
+    api.create_glossary("headers")
+        .source_lang(Lang::EN)
+        .target_lang(Lang::DE)
+        .entries([("Hello", "Hallo")])
+        .header("X-Request-Id", "glossary")
+        .send()
+        .await
+        .unwrap();
+    api.get_usage().await.unwrap();
+
+    let requests = server.requests();
+    let ids: Vec<_> = requests.iter().map(|r| r.header("x-request-id")).collect();
+    assert_eq!(
+        ids,
+        [Some("translate"), Some("upload"), Some("glossary"), None]
+    );
+    assert!(requests
+        .iter()
+        .all(|r| r.header("x-tenant") == Some("acme")));
+    assert_eq!(requests[0].header("user-agent"), Some("custom"));
+    assert!(requests[0].header("authorization").is_some());
+
+    assert!(matches!(
+        api.with_header("Authorization", "DeepL-Auth-Key other"),
+        Err(Error::InvalidConfig(_))
+    ));
+    assert!(matches!(
+        api.translate_text("Hello", Lang::DE)
+            .header("authorization", "DeepL-Auth-Key other")
+            .await,
+        Err(Error::InvalidConfig(_))
+    ));
+    assert!(matches!(
+        api.translate_text("Hello", Lang::DE)
+            .header("X-Bad", "line\nbreak")
+            .await,
+        Err(Error::InvalidConfig(_))
+    ));
+    assert_eq!(server.requests().len(), 4);
+}
+
+#[tokio::test]
+async fn test_raw_response() {
+    let server = testing::FakeServer::start().await;
+    let api = server.api();
+
+    let mut requester = api.translate_text("Hello", Lang::DE);
+    requester.source_lang(Lang::EN);
+    let typed = (&mut requester).await.unwrap();
+    let raw = requester.send_raw().await.unwrap();
+    assert_eq!(raw.status(), reqwest::StatusCode::OK);
+    let decoded: TranslateTextResp = raw.json().await.unwrap();
+    assert_eq!(decoded.translations[0].text, typed.translations[0].text);
+
+    let requests = server.requests();
+    assert_eq!(requests[0].body(), requests[1].body());
+
+    let glossary = api
+        .create_glossary("raw")
+        .source_lang(Lang::EN)
+        .target_lang(Lang::DE)
+        .entries([("Hello", "Hallo")])
+        .send()
+        .await
+        .unwrap();
+    let raw = api
+        .retrieve_glossary_details_raw(&glossary.glossary_id)
+        .await
+        .unwrap()
+        .json::<serde_json::Value>()
+        .await
+        .unwrap();
+    assert_eq!(raw["name"], "raw");
+
+    let doc = api
+        .upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+        .await
+        .unwrap();
+    let raw = api.check_document_status_raw(&doc).await.unwrap();
+    assert!(raw.text().await.unwrap().contains("status"));
+
+    // error responses are returned as is unless asked otherwise
+    let raw = api.retrieve_glossary_details_raw("missing").await.unwrap();
+    assert!(!raw.status().is_success());
+    assert!(raw.error_for_status().await.is_err());
+}
+
+#[test]
+fn test_response_traits() {
+    fn assert_traits<T>()
+    where
+        T: Clone + std::fmt::Debug + PartialEq + serde::Serialize + serde::de::DeserializeOwned,
+    {
+    }
+
+    assert_traits::<TranslateTextResp>();
+    assert_traits::<endpoint::translate::Sentence>();
+    assert_traits::<UploadDocumentResp>();
+    assert_traits::<DocumentStatusResp>();
+    assert_traits::<glossary::GlossaryResp>();
+    assert_traits::<glossary::GlossaryLanguagePair>();
+    assert_traits::<LangInfo>();
+    assert_traits::<UsageResponse>();
+
+    let resp: TranslateTextResp = serde_json::from_str(
+        r#"{"translations":[{"detected_source_language":"EN","text":"Hallo"}]}"#,
+    )
+    .unwrap();
+    let json = serde_json::to_string(&resp).unwrap();
+    assert_eq!(
+        serde_json::from_str::<TranslateTextResp>(&json).unwrap(),
+        resp
+    );
+}
+
+#[cfg(feature = "reqwest-middleware")]
+#[tokio::test]
+async fn test_client_with_middleware() {
+    use std::sync::atomic::AtomicUsize;
+
+    struct Counter(Arc<AtomicUsize>);
+
+    #[async_trait::async_trait]
+    impl reqwest_middleware::Middleware for Counter {
+        async fn handle(
+            &self,
+            req: reqwest::Request,
+            extensions: &mut http::Extensions,
+            next: reqwest_middleware::Next<'_>,
+        ) -> reqwest_middleware::Result<reqwest::Response> {
+            if self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 3 {
+                return Err(reqwest_middleware::Error::middleware(
+                    std::io::Error::other("blocked by middleware"),
+                ));
+            }
+            next.run(req, extensions).await
+        }
+    }
+
+    let server = testing::FakeServer::start().await;
+    let count = Arc::new(AtomicUsize::new(0));
+    let client = reqwest_middleware::ClientBuilder::new(reqwest::Client::new())
+        .with(Counter(count.clone()))
+        .build();
+    let api = DeepLApi::with(testing::FAKE_AUTH_KEY)
+        .endpoint(server.endpoint())
+        .client_with_middleware(client)
+        .retry(RetryConfig {
+            initial_backoff: Duration::from_millis(1),
+            ..Default::default()
+        })
+        .new();
+
+    api.translate_text("Hello", Lang::DE).await.unwrap();
+    api.get_usage().await.unwrap();
+    api.upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+        .await
+        .unwrap();
+    assert_eq!(count.load(std::sync::atomic::Ordering::SeqCst), 3);
+    server.assert_request_count(3);
+
+    let err = api.languages(LangType::Target).await.unwrap_err();
+    // a rejection of the middleware isn't retried, the next attempt would pass
+    assert!(matches!(err, Error::Middleware(_)), "{err:?}");
+    assert_eq!(count.load(std::sync::atomic::Ordering::SeqCst), 4);
+    assert!(err.to_string().contains("blocked by middleware"), "{err}");
+    assert_eq!(err.kind(), ErrorKind::Config);
+    assert!(!err.is_retryable());
+    server.assert_request_count(3);
+}
+
+#[tokio::test]
+async fn test_configure_client() {
+    use reqwest::header::HeaderMap;
+
+    let server = testing::FakeServer::start().await;
+    let api = DeepLApi::with(testing::FAKE_AUTH_KEY)
+        .endpoint(server.endpoint())
+        .timeout(Duration::from_secs(10))
+        .configure_client(|builder| {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-custom", HeaderValue::from_static("tweaked"));
+            headers.insert(USER_AGENT, HeaderValue::from_static("overridden"));
+            builder
+                .pool_idle_timeout(Duration::from_secs(5))
+                .default_headers(headers)
+        })
+        .build()
+        .unwrap();
+
+    api.get_usage().await.unwrap();
+    let request = &server.requests()[0];
+    assert_eq!(request.header("x-custom"), Some("tweaked"));
+    assert!(request
+        .header("user-agent")
+        .unwrap()
+        .starts_with("deepl-rs/"));
+
+    // the default native TLS backend doesn't support TLS 1.3
+    let result = DeepLApi::with(testing::FAKE_AUTH_KEY)
+        .configure_client(|builder| builder.min_tls_version(reqwest::tls::Version::TLS_1_3))
+        .build();
+    assert!(matches!(result, Err(Error::InvalidConfig(_))));
+}
+
+#[test]
+fn test_insecure_endpoint() {
+    for url in ["http://deepl.example.com/v2/", "http://10.0.0.1/v2/"] {
+        let result = DeepLApi::with("key").endpoint(url).build();
+        assert!(matches!(result, Err(Error::InsecureEndpoint(_))), "{url}");
+
+        let api = DeepLApi::with("key")
+            .endpoint(url)
+            .allow_insecure_http(true)
+            .build()
+            .unwrap();
+        assert_eq!(api.inner.endpoint.as_str(), url);
+    }
+
+    for url in [
+        "http://localhost:3000/v2/",
+        "http://127.0.0.1:3000/v2/",
+        "http://[::1]:3000/v2/",
+        "https://deepl.example.com/v2/",
+    ] {
+        let result = DeepLApi::with("key").endpoint(url).build();
+        assert!(result.is_ok(), "{url}");
+    }
+}
+
+#[tokio::test]
+async fn test_owned_requesters() {
+    use endpoint::{document::UploadDocumentRequester, translate::TranslateRequester};
+
+    fn translate(api: DeepLApi) -> TranslateRequester<'static> {
+        let mut req = api.translate_text("Hello", Lang::DE);
+        req.source_lang(Lang::EN);
+        req.into_owned()
+    }
+    fn upload(api: DeepLApi) -> UploadDocumentRequester<'static> {
+        api.upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+            .into_owned()
+    }
+    fn create(api: DeepLApi) -> glossary::CreateGlossary<'static> {
+        api.create_glossary("owned")
+            .source_lang(Lang::EN)
+            .target_lang(Lang::DE)
+            .entries([("Hello", "Hallo")])
+            .send()
+            .into_owned()
+    }
+
+    let (api, server) = testing::mock_api().await;
+    let req = translate(api.clone());
+    let translated = tokio::spawn(async move { req.await });
+    let req = upload(api.clone());
+    let uploaded = tokio::spawn(async move { req.await });
+    let req = create(api);
+    let created = tokio::spawn(async move { req.await });
+
+    let resp = translated.await.unwrap().unwrap();
+    assert_eq!(resp.translations[0].text, "[DE] Hello");
+    uploaded.await.unwrap().unwrap();
+    assert_eq!(created.await.unwrap().unwrap().name, "owned");
+    server.assert_request_count(3);
+}
+
+#[tokio::test]
+async fn test_response_meta() {
+    use testing::{Fault, Route};
+
+    let server = testing::FakeServer::start().await;
+    let api = server.api();
+    server.response_header("x-trace-id", "trace-1");
+
+    let (resp, meta) = api
+        .translate_text("Hello", Lang::DE)
+        .send_with_meta()
+        .await
+        .unwrap();
+    assert_eq!(resp.translations[0].text, "[DE] Hello");
+    assert_eq!(meta.status, reqwest::StatusCode::OK);
+    assert_eq!(meta.trace_id.as_deref(), Some("trace-1"));
+
+    let doc = api
+        .upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+        .await
+        .unwrap();
+    let (_, meta) = ResponseMeta::capture(api.check_document_status(&doc))
+        .await
+        .unwrap();
+    assert_eq!(meta.trace_id.as_deref(), Some("trace-1"));
+    let (glossaries, meta) = ResponseMeta::capture(api.list_all_glossaries())
+        .await
+        .unwrap();
+    assert!(glossaries.is_empty());
+    assert_eq!(meta.status, reqwest::StatusCode::OK);
+
+    let server = testing::FakeServer::start().await;
+    let api = server.api();
+    server.inject(
+        Route::Translate,
+        Fault::status(400).header("x-trace-id", "trace-2"),
+    );
+    let err = api
+        .translate_text("Hello", Lang::DE)
+        .send_with_meta()
+        .await
+        .unwrap_err();
+    assert_eq!(err.trace_id(), Some("trace-2"));
+    assert_eq!(err.endpoint(), Some("translate_text"));
+    assert_eq!(serde_json::to_value(&err).unwrap()["trace_id"], "trace-2");
+
+    // no trace header
+    server.clear_faults();
+    let (_, meta) = api
+        .translate_text("Hello", Lang::DE)
+        .send_with_meta()
+        .await
+        .unwrap();
+    assert_eq!(meta.trace_id, None);
+}
+
+#[tokio::test]
+async fn test_session_stats() {
+    let server = testing::FakeServer::start().await;
+    let api = server.api();
+    let clone = api.clone();
+
+    api.translate_text("Hello", Lang::DE).await.unwrap();
+    clone.translate_text("World!", Lang::FR).await.unwrap();
+    let doc = api
+        .upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+        .await
+        .unwrap();
+    api.check_document_status(&doc).await.unwrap();
+    api.check_document_status(&doc).await.unwrap();
+    api.download_document_bytes(&doc).await.unwrap();
+
+    let stats = clone.session_stats();
+    assert_eq!(
+        stats,
+        SessionStats {
+            requests: 6,
+            characters: 11,
+            bytes_uploaded: 5,
+            bytes_downloaded: "[DE] Hello".len() as u64,
+            billed_characters: 5,
+        }
+    );
+
+    assert_eq!(api.reset_session_stats(), stats);
+    assert_eq!(api.session_stats(), SessionStats::default());
+    api.check_document_status(&doc).await.unwrap();
+    assert_eq!(api.session_stats().billed_characters, 5);
+}
+
+#[tokio::test]
+async fn test_timeout_override() {
+    use testing::Route;
+
+    let server = testing::FakeServer::start().await;
+    server.delay(Route::Translate, Duration::from_millis(300));
+    server.delay(Route::Usage, Duration::from_millis(300));
+    server.delay(Route::Languages, Duration::from_millis(300));
+
+    // longer than the client default
+    let api = DeepLApi::with("key")
+        .endpoint(server.endpoint())
+        .timeout(Duration::from_millis(100))
+        .new();
+    let err = api.translate_text("Hello", Lang::DE).await.unwrap_err();
+    assert!(matches!(err, Error::Timeout { .. }), "{err:?}");
+    api.translate_text("Hello", Lang::DE)
+        .timeout(Duration::from_secs(2))
+        .await
+        .unwrap();
+    assert!(api.get_usage().await.unwrap_err().is_timeout());
+    let api = api.with_timeout(Duration::from_secs(2));
+    api.get_usage().await.unwrap();
+    api.languages(LangType::Target).await.unwrap();
+
+    // shorter than the client default
+    let api = DeepLApi::with("key")
+        .endpoint(server.endpoint())
+        .timeout(Duration::from_secs(10))
+        .new();
+    let err = api
+        .translate_text("Hello", Lang::DE)
+        .timeout(Duration::from_millis(100))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::DeadlineExceeded), "{err:?}");
+    let result = api
+        .with_timeout(Duration::from_millis(100))
+        .languages(LangType::Target)
+        .await;
+    assert!(matches!(result, Err(Error::DeadlineExceeded)));
+}
+
+#[tokio::test]
+async fn test_deadline_and_cancellation() {
+    use testing::Route;
+    use tokio_util::sync::CancellationToken;
+
+    let server = testing::FakeServer::start().await;
+    let api = server.api();
+    server.delay(Route::Translate, Duration::from_secs(10));
+
+    let start = Instant::now();
+    let result = api
+        .translate_text("Hello", Lang::DE)
+        .timeout(Duration::from_millis(100))
+        .await;
+    assert!(matches!(result, Err(Error::DeadlineExceeded)));
+    let result = api
+        .with_deadline(Instant::now() + Duration::from_millis(100))
+        .translate_text("Hello", Lang::DE)
+        .await;
+    assert!(matches!(result, Err(Error::DeadlineExceeded)));
+    let result = api
+        .with_timeout(Duration::from_millis(100))
+        .languages(LangType::Target)
+        .await;
+    assert!(result.is_ok());
+
+    let token = CancellationToken::new();
+    let canceller = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        canceller.cancel();
+    });
+    let result = api
+        .translate_text("Hello", Lang::DE)
+        .cancellation(token)
+        .await;
+    assert!(matches!(result, Err(Error::Cancelled)));
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[cfg(feature = "tokio-fs")]
+#[tokio::test]
+async fn test_cancel_download() {
+    use testing::Route;
+    use tokio_util::sync::CancellationToken;
+
+    let server = testing::FakeServer::start().await;
+    let api = server.api();
+    let doc = api
+        .upload_document_from_bytes(b"Hello World".to_vec(), "hello.txt", Lang::DE)
+        .await
+        .unwrap();
+    server.stall_body(Route::DocumentResult, Duration::from_secs(10));
+
+    let output = std::env::temp_dir().join(format!("deepl-cancel-{}.txt", std::process::id()));
+    let token = CancellationToken::new();
+    let download = api.with_cancellation(token.clone());
+    let task = tokio::spawn({
+        let output = output.clone();
+        async move { download.download_document(&doc, output).await }
+    });
+
+    // wait until the first half of the document is written
+    let start = Instant::now();
+    while !output.exists() && start.elapsed() < Duration::from_secs(5) {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(output.exists());
+    token.cancel();
+
+    let result = task.await.unwrap();
+    assert!(matches!(result, Err(Error::Cancelled)));
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert!(!output.exists());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_response_future() {
+    use endpoint::{document::UploadDocumentRequester, translate::TranslateRequester};
+    use std::future::IntoFuture;
+
+    fn assert_send<T: Send>() {}
+
+    assert_send::<ResponseFuture<'static, TranslateTextResp>>();
+    assert_send::<<TranslateRequester<'static> as IntoFuture>::IntoFuture>();
+    assert_send::<<&mut TranslateRequester<'static> as IntoFuture>::IntoFuture>();
+    assert_send::<<UploadDocumentRequester<'static> as IntoFuture>::IntoFuture>();
+    assert_send::<<&mut UploadDocumentRequester<'static> as IntoFuture>::IntoFuture>();
+    assert_send::<<glossary::CreateGlossary<'static> as IntoFuture>::IntoFuture>();
+
+    struct Pending<'a> {
+        translation: ResponseFuture<'a, TranslateTextResp>,
+        upload: ResponseFuture<'a, UploadDocumentResp>,
+        glossary: ResponseFuture<'a, glossary::GlossaryResp>,
+    }
+
+    let server = testing::FakeServer::start().await;
+    let api = server.api();
+    let pending = Pending {
+        translation: api.translate_text("Hello", Lang::DE).into_future(),
+        upload: api
+            .upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+            .into_future(),
+        glossary: api
+            .create_glossary("pending")
+            .source_lang(Lang::EN)
+            .target_lang(Lang::DE)
+            .entries([("Hello", "Hallo")])
+            .send()
+            .into_future(),
+    };
+    assert!(server.requests().is_empty());
+
+    assert_eq!(pending.translation.await.unwrap().to_string(), "[DE] Hello");
+    pending.upload.await.unwrap();
+    pending.glossary.await.unwrap();
+
+    // the futures of owned requesters can run on another thread of the runtime
+    let task = tokio::spawn(
+        api.translate_text("Hello", Lang::FR)
+            .into_owned()
+            .into_future(),
+    );
+    assert_eq!(task.await.unwrap().unwrap().to_string(), "[FR] Hello");
+}
+
+/// Answer every usage request on the connection, keeping it open between requests
+#[cfg(test)]
+async fn serve_keep_alive<S>(stream: S)
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let body = r#"{"character_count":0,"character_limit":500000}"#;
+    let mut stream = BufReader::new(stream);
+    loop {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match stream.read_line(&mut line).await {
+                Ok(0) | Err(_) => return,
+                Ok(_) if line == "\r\n" => break,
+                Ok(_) => {}
+            }
+        }
+        let resp = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        );
+        if stream.get_mut().write_all(resp.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_tls_options() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let cert = include_bytes!("../tests/fixtures/localhost.crt");
+    let identity = tokio_native_tls::native_tls::Identity::from_pkcs8(
+        cert,
+        include_bytes!("../tests/fixtures/localhost.key"),
+    )
+    .unwrap();
+    let acceptor = tokio_native_tls::TlsAcceptor::from(
+        tokio_native_tls::native_tls::TlsAcceptor::new(identity).unwrap(),
+    );
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                if let Ok(stream) = acceptor.accept(stream).await {
+                    serve_keep_alive(stream).await;
+                }
+            });
+        }
+    });
+    let endpoint = format!("https://localhost:{port}/v2/");
+
+    // the self-signed certificate isn't trusted by default
+    let api = DeepLApi::with("key").endpoint(&endpoint).build().unwrap();
+    assert!(matches!(api.get_usage().await, Err(Error::Connectivity(_))));
+
+    let api = DeepLApi::with("key")
+        .endpoint(&endpoint)
+        .add_root_certificate(Certificate::from_pem(cert).unwrap())
+        .build()
+        .unwrap();
+    assert_eq!(api.get_usage().await.unwrap().character_limit, 500000);
+
+    let api = DeepLApi::with("key")
+        .endpoint(&endpoint)
+        .danger_accept_invalid_certs(true)
+        .build()
+        .unwrap();
+    assert_eq!(api.get_usage().await.unwrap().character_limit, 500000);
+
+    assert!(matches!(
+        Certificate::from_pem(b"not a certificate"),
+        Err(Error::InvalidConfig(_))
+    ));
+
+    // the default native TLS backend doesn't support TLS 1.3
+    let result = DeepLApi::with("key")
+        .min_tls_version(TlsVersion::Tls1_3)
+        .build();
+    assert!(matches!(result, Err(Error::InvalidConfig(_))));
+    DeepLApi::with("key")
+        .min_tls_version(TlsVersion::Tls1_2)
+        .build()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_connection_pool() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = format!("http://{}/v2/", listener.local_addr().unwrap());
+    let connections = Arc::new(AtomicUsize::new(0));
+    let accepted = connections.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            accepted.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(serve_keep_alive(stream));
+        }
+    });
+
+    let new_connections = |builder: DeepLApiBuilder, pause: Duration| {
+        let connections = connections.clone();
+        let api = builder.endpoint(&endpoint).build().unwrap();
+        async move {
+            let before = connections.load(Ordering::SeqCst);
+            for _ in 0..3 {
+                api.get_usage().await.unwrap();
+                tokio::time::sleep(pause).await;
+            }
+            connections.load(Ordering::SeqCst) - before
+        }
+    };
+
+    let no_pause = Duration::ZERO;
+    assert_eq!(new_connections(DeepLApi::with("key"), no_pause).await, 1);
+    let builder = DeepLApi::with("key").pool_max_idle_per_host(0);
+    assert_eq!(new_connections(builder, no_pause).await, 3);
+    let builder = DeepLApi::with("key").pool_idle_timeout(Duration::from_millis(10));
+    assert_eq!(
+        new_connections(builder, Duration::from_millis(200)).await,
+        3
+    );
+}
@@ -0,0 +1,113 @@
+//! Client side limits shared by all clones of a [`DeepLApi`](crate::DeepLApi), and the
+//! deadline and cancellation of a call
+
+use crate::{endpoint::Result, DeepLApi, Error};
+use std::{
+    future::{poll_fn, Future},
+    pin::pin,
+    sync::Mutex,
+    task::Poll,
+    time::{Duration, Instant},
+};
+
+/// Token bucket limiting the rate of requests, see
+/// [`DeepLApiBuilder::max_requests_per_second`](crate::DeepLApiBuilder::max_requests_per_second)
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    /// Tokens added per second
+    rate: f64,
+    burst: f64,
+    state: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    /// Negative when requests are waiting for their token
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(rate: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        Self {
+            rate,
+            burst,
+            state: Mutex::new(Bucket {
+                tokens: burst,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    /// Take a token, waiting until it's available. Tokens are handed out in call order.
+    pub(crate) async fn acquire(&self) {
+        let wait = self.reserve();
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn reserve(&self) -> Duration {
+        let mut bucket = self.state.lock().unwrap();
+        let now = Instant::now();
+        let refill = now.duration_since(bucket.updated).as_secs_f64() * self.rate;
+        bucket.tokens = (bucket.tokens + refill).min(self.burst) - 1.0;
+        bucket.updated = now;
+
+        if bucket.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-bucket.tokens / self.rate)
+        }
+    }
+}
+
+impl DeepLApi {
+    /// Run a call until the deadline of this clone or its cancellation, see
+    /// [`DeepLApi::with_timeout`] and [`DeepLApi::with_cancellation`]
+    pub(crate) async fn bounded<T>(&self, fut: impl Future<Output = Result<T>>) -> Result<T> {
+        let deadline = match (self.deadline, self.timeout) {
+            (Some(deadline), Some(timeout)) => Some(deadline.min(Instant::now() + timeout)),
+            (deadline, timeout) => deadline.or_else(|| timeout.map(|t| Instant::now() + t)),
+        };
+        let fut = async {
+            match deadline {
+                Some(deadline) => match tokio::time::timeout_at(deadline.into(), fut).await {
+                    // the HTTP timeout of the call expired along with the deadline
+                    Ok(Err(err)) if err.is_timeout() && Instant::now() >= deadline => {
+                        Err(Error::DeadlineExceeded)
+                    }
+                    Ok(result) => result,
+                    Err(_) => Err(Error::DeadlineExceeded),
+                },
+                None => fut.await,
+            }
+        };
+
+        let Some(token) = &self.cancel else {
+            return fut.await;
+        };
+        let mut fut = pin!(fut);
+        let mut cancelled = pin!(token.cancelled());
+        poll_fn(|cx| {
+            if cancelled.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Err(Error::Cancelled));
+            }
+            fut.as_mut().poll(cx)
+        })
+        .await
+    }
+}
+
+#[test]
+fn test_rate_limiter_reserve() {
+    let limiter = RateLimiter::new(10.0, 2);
+    assert_eq!(limiter.reserve(), Duration::ZERO);
+    assert_eq!(limiter.reserve(), Duration::ZERO);
+
+    let wait = limiter.reserve();
+    assert!(wait > Duration::from_millis(90) && wait <= Duration::from_millis(100));
+    let wait = limiter.reserve();
+    assert!(wait > Duration::from_millis(190) && wait <= Duration::from_millis(200));
+}
@@ -0,0 +1,273 @@
+//! Per call metrics, see [`DeepLApiBuilder::on_response`](crate::DeepLApiBuilder::on_response),
+//! and totals of a client, see [`DeepLApi::session_stats`]
+
+use crate::{endpoint::Result, AttemptOutcome, Attempts, DeepLApi, Error};
+use std::{
+    collections::HashSet,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Metrics of a single call of an endpoint method, retries included
+#[derive(Debug, Clone)]
+pub struct RequestMetrics {
+    /// Name of the called method, e.g. `translate_text` or `download_document`
+    pub endpoint: &'static str,
+    /// HTTP status of the last response, `None` if no response was received
+    pub status: Option<u16>,
+    /// Time from the call until the result was ready, including time waiting for the limits
+    /// of the client
+    pub elapsed: Duration,
+    /// Number of characters sent for translation
+    pub characters: Option<usize>,
+    /// Number of bytes of a downloaded document
+    pub bytes: Option<u64>,
+    /// Number of characters billed for a translated document, reported by
+    /// [`check_document_status`](DeepLApi::check_document_status) once the translation is done
+    pub billed_characters: Option<u64>,
+    /// The error returned by the call, formatted
+    pub error: Option<String>,
+}
+
+type ResponseHookFn = dyn Fn(RequestMetrics) + Send + Sync;
+
+/// User defined function receiving the metrics of every call
+#[derive(Clone)]
+pub(crate) struct ResponseHook(pub(crate) Arc<ResponseHookFn>);
+
+impl std::fmt::Debug for ResponseHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ResponseHook")
+    }
+}
+
+/// Totals of a client and all its clones since it was built or the stats were reset, see
+/// [`DeepLApi::session_stats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionStats {
+    /// Number of HTTP requests sent, retries included
+    pub requests: u64,
+    /// Number of characters sent for translation
+    pub characters: u64,
+    /// Number of bytes of uploaded documents
+    pub bytes_uploaded: u64,
+    /// Number of bytes of downloaded documents
+    pub bytes_downloaded: u64,
+    /// Number of characters billed for translated documents, counted once per document
+    pub billed_characters: u64,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct SessionCounters {
+    requests: AtomicU64,
+    characters: AtomicU64,
+    bytes_uploaded: AtomicU64,
+    bytes_downloaded: AtomicU64,
+    billed_characters: AtomicU64,
+    /// Documents whose billed characters are counted already, as the status may be checked
+    /// again after the translation is done
+    billed_documents: Mutex<HashSet<String>>,
+}
+
+impl SessionCounters {
+    pub(crate) fn add_request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn add_uploaded(&self, len: u64) {
+        self.bytes_uploaded.fetch_add(len, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self, take: bool) -> SessionStats {
+        let read = |counter: &AtomicU64| match take {
+            true => counter.swap(0, Ordering::Relaxed),
+            false => counter.load(Ordering::Relaxed),
+        };
+
+        SessionStats {
+            requests: read(&self.requests),
+            characters: read(&self.characters),
+            bytes_uploaded: read(&self.bytes_uploaded),
+            bytes_downloaded: read(&self.bytes_downloaded),
+            billed_characters: read(&self.billed_characters),
+        }
+    }
+}
+
+/// Metadata of the response to a call, see [`ResponseMeta::capture`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseMeta {
+    /// HTTP status of the response
+    pub status: reqwest::StatusCode,
+    /// The `x-trace-id` header, to hand to DeepL support when reporting an issue
+    pub trace_id: Option<String>,
+    /// Time from the call until the result was ready
+    pub elapsed: Duration,
+    /// Attempts of the last request, more than one if it was retried
+    pub attempts: Attempts,
+}
+
+impl ResponseMeta {
+    /// Run a call and return its result along with the metadata of its last response, e.g. of
+    /// [`DeepLApi::check_document_status`] or [`DeepLApi::list_all_glossaries`]. An error
+    /// holds the trace ID of the error response in its [`context`](crate::Error::context).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// let (status, meta) = ResponseMeta::capture(deepl.check_document_status(&doc)).await?;
+    /// println!("{:?}: {:?}", status.status, meta.trace_id);
+    /// ```
+    pub async fn capture<T>(fut: impl Future<Output = Result<T>>) -> Result<(T, ResponseMeta)> {
+        let start = Instant::now();
+        let last = Arc::new(Mutex::new(LastResponse::default()));
+        let value = LAST_RESPONSE.scope(last.clone(), fut).await?;
+        let LastResponse { response, attempts } = std::mem::take(&mut *last.lock().unwrap());
+        let (status, trace_id) = response
+            .ok_or_else(|| Error::InvalidResponse("no response was received".to_string()))?;
+        Ok((
+            value,
+            ResponseMeta {
+                status,
+                trace_id,
+                elapsed: start.elapsed(),
+                attempts,
+            },
+        ))
+    }
+}
+
+#[derive(Default)]
+struct CallState {
+    status: Mutex<Option<u16>>,
+    trace_id: Mutex<Option<String>>,
+    bytes: Mutex<Option<u64>>,
+    billed_characters: Mutex<Option<u64>>,
+}
+
+/// The last response of a call and the attempts of its request
+#[derive(Default)]
+struct LastResponse {
+    response: Option<(reqwest::StatusCode, Option<String>)>,
+    attempts: Attempts,
+}
+
+tokio::task_local! {
+    static CALL: Arc<CallState>;
+    static LAST_RESPONSE: Arc<Mutex<LastResponse>>;
+}
+
+/// Record the status and trace ID of a response of the current call
+pub(crate) fn record_response(resp: &reqwest::Response) {
+    let status = resp.status();
+    let trace_id = resp
+        .headers()
+        .get("x-trace-id")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let _ = CALL.try_with(|call| {
+        *call.status.lock().unwrap() = Some(status.as_u16());
+        *call.trace_id.lock().unwrap() = trace_id.clone();
+    });
+    let _ = LAST_RESPONSE.try_with(|last| {
+        let mut attempts = Attempts::default();
+        attempts.push(AttemptOutcome::Status(status.as_u16()), None);
+        *last.lock().unwrap() = LastResponse {
+            response: Some((status, trace_id)),
+            attempts,
+        };
+    });
+}
+
+/// Record the attempts of a retried request that succeeded, after its last response
+pub(crate) fn record_attempts(attempts: Attempts) {
+    let _ = LAST_RESPONSE.try_with(|last| last.lock().unwrap().attempts = attempts);
+}
+
+impl DeepLApi {
+    /// Totals of this client and all its clones, cheap to call
+    pub fn session_stats(&self) -> SessionStats {
+        self.inner.stats.snapshot(false)
+    }
+
+    /// Reset the totals of [`session_stats`](Self::session_stats) to zero, returning the ones
+    /// before the reset
+    pub fn reset_session_stats(&self) -> SessionStats {
+        let stats = self.inner.stats.snapshot(true);
+        self.inner.stats.billed_documents.lock().unwrap().clear();
+        stats
+    }
+
+    /// Record downloaded bytes of the current call
+    pub(crate) fn record_bytes(&self, len: u64) {
+        self.inner
+            .stats
+            .bytes_downloaded
+            .fetch_add(len, Ordering::Relaxed);
+        let _ = CALL.try_with(|call| *call.bytes.lock().unwrap().get_or_insert(0) += len);
+    }
+
+    /// Record the characters billed for a document, once per document
+    pub(crate) fn record_billed(&self, document_id: &str, billed_characters: u64) {
+        let _ =
+            CALL.try_with(|call| *call.billed_characters.lock().unwrap() = Some(billed_characters));
+        let mut counted = self.inner.stats.billed_documents.lock().unwrap();
+        if counted.insert(document_id.to_string()) {
+            self.inner
+                .stats
+                .billed_characters
+                .fetch_add(billed_characters, Ordering::Relaxed);
+        }
+    }
+
+    /// Run an endpoint call within the limits of the client, and report its metrics
+    pub(crate) async fn call<T>(
+        &self,
+        endpoint: &'static str,
+        characters: Option<usize>,
+        fut: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        if let Some(characters) = characters {
+            self.inner
+                .stats
+                .characters
+                .fetch_add(characters as u64, Ordering::Relaxed);
+        }
+
+        let start = Instant::now();
+        let state = Arc::new(CallState::default());
+        let fut = async {
+            self.bounded(async {
+                let _permit = self.concurrency_permit().await;
+                fut.await
+            })
+            .await
+        };
+        let result = CALL.scope(state.clone(), fut).await.map_err(|err| {
+            let trace_id = state.trace_id.lock().unwrap().take();
+            err.with_endpoint(endpoint).with_trace_id(trace_id)
+        });
+        let Some(hook) = &self.inner.on_response else {
+            return result;
+        };
+
+        let status = *state.status.lock().unwrap();
+        let bytes = *state.bytes.lock().unwrap();
+        let billed_characters = *state.billed_characters.lock().unwrap();
+        (hook.0)(RequestMetrics {
+            endpoint,
+            status,
+            elapsed: start.elapsed(),
+            characters,
+            bytes,
+            billed_characters,
+            error: result.as_ref().err().map(ToString::to_string),
+        });
+
+        result
+    }
+}
@@ -0,0 +1,65 @@
+//! Abstraction over the client, see [`TranslationProvider`]
+
+use crate::{
+    DeepLApi, Error, Lang, LangInfo, LangType, TranslateOptions, TranslateTextResp, UsageResponse,
+};
+use std::{future::Future, future::IntoFuture, pin::Pin};
+
+/// Future returned by the methods of [`TranslationProvider`]
+pub type ProviderFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, Error>> + Send + 'a>>;
+
+/// The core operations of [`DeepLApi`] as an object safe trait, so application code can take an
+/// `Arc<dyn TranslationProvider>` and tests can replace the client with a fake like
+/// [`StaticProvider`](crate::testing::StaticProvider) of the `test-util` feature.
+///
+/// See `examples/provider.rs` for the complete pattern.
+///
+/// # Example
+///
+/// ```rust
+/// async fn greet(provider: &dyn TranslationProvider) -> Result<String, Error> {
+///     let resp = provider
+///         .translate(vec!["Hello".to_string()], Lang::DE, TranslateOptions::default())
+///         .await?;
+///     Ok(resp.to_string())
+/// }
+///
+/// greet(&DeepLApi::from_env()?).await?;
+/// ```
+pub trait TranslationProvider: Send + Sync {
+    /// Translate the texts, see [`DeepLApi::translate_text`]
+    fn translate(
+        &self,
+        texts: Vec<String>,
+        target_lang: Lang,
+        options: TranslateOptions,
+    ) -> ProviderFuture<'_, TranslateTextResp>;
+
+    /// Current usage of the account, see [`DeepLApi::get_usage`]
+    fn usage(&self) -> ProviderFuture<'_, UsageResponse>;
+
+    /// Supported languages, see [`DeepLApi::languages`]
+    fn languages(&self, lang_type: LangType) -> ProviderFuture<'_, Vec<LangInfo>>;
+}
+
+impl TranslationProvider for DeepLApi {
+    fn translate(
+        &self,
+        texts: Vec<String>,
+        target_lang: Lang,
+        options: TranslateOptions,
+    ) -> ProviderFuture<'_, TranslateTextResp> {
+        Box::pin(
+            self.translate_with(texts, target_lang, &options)
+                .into_future(),
+        )
+    }
+
+    fn usage(&self) -> ProviderFuture<'_, UsageResponse> {
+        Box::pin(self.get_usage())
+    }
+
+    fn languages(&self, lang_type: LangType) -> ProviderFuture<'_, Vec<LangInfo>> {
+        Box::pin(DeepLApi::languages(self, lang_type))
+    }
+}
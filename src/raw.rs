@@ -0,0 +1,61 @@
+//! Undecoded responses, for fields not yet covered by the typed responses and for debugging
+
+use crate::endpoint::{Error, Result};
+use reqwest::{header::HeaderMap, StatusCode};
+use serde::de::DeserializeOwned;
+
+/// Response of a request sent with one of the raw methods, like `send_raw` of
+/// [`DeepLApi::translate_text`](crate::DeepLApi::translate_text).
+///
+/// The response is returned as is, whatever the status code. Call
+/// [`error_for_status`](Self::error_for_status) to turn an error response into [`Error`] like
+/// the typed methods do.
+#[derive(Debug)]
+pub struct RawResponse(reqwest::Response);
+
+impl RawResponse {
+    pub(crate) fn new(resp: reqwest::Response) -> Self {
+        Self(resp)
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.0.status()
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        self.0.headers()
+    }
+
+    /// Return an error for non 2xx responses, with the message sent by DeepL
+    pub async fn error_for_status(self) -> Result<Self> {
+        if self.0.status().is_success() {
+            Ok(self)
+        } else {
+            crate::endpoint::extract_deepl_error(self.0).await
+        }
+    }
+
+    pub async fn bytes(self) -> Result<Vec<u8>> {
+        self.0
+            .bytes()
+            .await
+            .map(|bytes| bytes.to_vec())
+            .map_err(Error::from)
+    }
+
+    pub async fn text(self) -> Result<String> {
+        self.0.text().await.map_err(Error::from)
+    }
+
+    /// Decode the body as JSON into any type
+    pub async fn json<T: DeserializeOwned>(self) -> Result<T> {
+        self.0.json().await.map_err(|err| {
+            Error::InvalidResponse(format!("convert json bytes to Rust type: {err}"))
+        })
+    }
+
+    /// The underlying response of reqwest
+    pub fn into_inner(self) -> reqwest::Response {
+        self.0
+    }
+}
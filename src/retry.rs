@@ -0,0 +1,205 @@
+//! Retry of transient failures, see [`DeepLApiBuilder::retry`](crate::DeepLApiBuilder::retry)
+
+use reqwest::{header::RETRY_AFTER, StatusCode};
+use smallvec::SmallVec;
+use std::{
+    hash::{BuildHasher, RandomState},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Policy to retry idempotent requests on connection errors, 429 Too Many Requests and 5xx
+/// server errors
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    /// Number of attempts including the first one
+    pub max_attempts: u32,
+    /// Delay before the second attempt, doubled for each further attempt
+    pub initial_backoff: Duration,
+    /// Upper bound of the delay between two attempts, including one asked by `Retry-After`
+    pub max_backoff: Duration,
+    /// Randomize each delay between half and all of it, so clients don't retry in lockstep
+    pub jitter: bool,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// Delay before the attempt following attempt number `attempt`, starting at 1
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self
+            .initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff);
+        if !self.jitter {
+            return delay;
+        }
+
+        let random = RandomState::new().hash_one(attempt) as f64 / u64::MAX as f64;
+        delay.mul_f64(0.5 + random / 2.0)
+    }
+}
+
+/// Outcome of a single attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttemptOutcome {
+    /// The server responded with this status code
+    Status(u16),
+    /// The request failed before a response was received, e.g. the connection was reset
+    RequestFail,
+}
+
+/// A single attempt of a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attempt {
+    pub outcome: AttemptOutcome,
+    /// Delay applied before the next attempt, `None` for the last one
+    pub backoff: Option<Duration>,
+}
+
+/// History of the attempts of a request
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Attempts {
+    entries: SmallVec<[Attempt; 4]>,
+    elapsed: Duration,
+}
+
+impl Attempts {
+    /// Number of attempts
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Attempt> {
+        self.entries.iter()
+    }
+
+    /// Time from the first attempt until the last one finished
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub(crate) fn push(&mut self, outcome: AttemptOutcome, backoff: Option<Duration>) {
+        self.entries.push(Attempt { outcome, backoff });
+    }
+
+    pub(crate) fn set_elapsed(&mut self, elapsed: Duration) {
+        self.elapsed = elapsed;
+    }
+}
+
+impl<'a> IntoIterator for &'a Attempts {
+    type Item = &'a Attempt;
+    type IntoIter = std::slice::Iter<'a, Attempt>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Delay requested by the `Retry-After` header, either in seconds or as an HTTP date
+pub(crate) fn retry_after(resp: &reqwest::Response) -> Option<Duration> {
+    let value = resp.headers().get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    // the clock is not available on wasm32
+    if cfg!(target_arch = "wasm32") {
+        return None;
+    }
+    // a date in the past means retry now
+    let date = parse_http_date(value)?;
+    Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+/// Parse the preferred HTTP date format, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`
+fn parse_http_date(value: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let (_weekday, date) = value.split_once(", ")?;
+    let mut parts = date.split(' ');
+    let day: u64 = parts.next()?.parse().ok()?;
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|&m| m == month)? as u64 + 1;
+    let year: u64 = parts.next()?.parse().ok()?;
+    let mut time = parts
+        .next()?
+        .split(':')
+        .map(|part| part.parse::<u64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    if parts.next()? != "GMT" || parts.next().is_some() || !(1..=31).contains(&day) {
+        return None;
+    }
+    if year < 1970 || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    // days since the epoch of the civil date, see http://howardhinnant.github.io/date_algorithms.html
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y / 400;
+    let year_of_era = y - era * 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+#[test]
+fn test_backoff() {
+    let config = RetryConfig {
+        max_attempts: 5,
+        initial_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_millis(300),
+        jitter: false,
+    };
+    assert_eq!(config.backoff(1), Duration::from_millis(100));
+    assert_eq!(config.backoff(2), Duration::from_millis(200));
+    assert_eq!(config.backoff(3), Duration::from_millis(300));
+    assert_eq!(config.backoff(40), Duration::from_millis(300));
+
+    let config = RetryConfig {
+        jitter: true,
+        ..config
+    };
+    let delay = config.backoff(2);
+    assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+}
+
+#[test]
+fn test_parse_http_date() {
+    let date = parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+    assert_eq!(date, UNIX_EPOCH + Duration::from_secs(784111777));
+    let date = parse_http_date("Tue, 29 Feb 2000 00:00:00 GMT").unwrap();
+    assert_eq!(date, UNIX_EPOCH + Duration::from_secs(951782400));
+
+    assert!(parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT").is_none());
+    assert!(parse_http_date("Sun, 06 Nov 1994 08:49:37 UTC").is_none());
+    assert!(parse_http_date("Sun, 06 Foo 1994 08:49:37 GMT").is_none());
+    assert!(parse_http_date("3").is_none());
+}
@@ -0,0 +1,1142 @@
+//! A local fake of the DeepL API for offline testing.
+//!
+//! Enable the `test-util` feature to use it in your own test suite:
+//!
+//! ```rust
+//! use deepl::{testing::{mock_api, Fault, Route}, Lang};
+//!
+//! let (api, server) = mock_api().await;
+//!
+//! let resp = api.translate_text("Hello", Lang::DE).await.unwrap();
+//! assert_eq!(resp.translations[0].text, "[DE] Hello");
+//! server.assert_request_count(1);
+//!
+//! // make the next translate request fail with "quota exceeded"
+//! server.inject(Route::Translate, Fault::status(456).times(1));
+//! assert!(api.translate_text("Hello", Lang::DE).await.is_err());
+//! ```
+//!
+//! The server implements a minimal but faithful subset of the API:
+//!
+//! - `/translate` echoes every text prefixed with `[TARGET_LANG]`, at most 50 texts per request, and detects
+//!   Japanese, Chinese, Russian and Greek by script, anything else as English
+//! - `/usage` reports the number of characters translated so far
+//! - `/languages` returns a small fixed list of source and target languages
+//! - `/glossaries` and `/glossary-language-pairs` are backed by an in-memory store
+//! - `/document` upload, status and result complete instantly
+//!
+//! Every request without a `DeepL-Auth-Key` authorization header is rejected with 403.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+use crate::{
+    endpoint::translate::Sentence, DeepLApi, Lang, LangInfo, LangType, ProviderFuture,
+    TranslateOptions, TranslateTextResp, TranslationProvider, UsageResponse,
+};
+
+/// The key used by [`FakeServer::api`]
+pub const FAKE_AUTH_KEY: &str = "fake-deepl-key:fx";
+
+/// API routes implemented by the [`FakeServer`], used to target injected [`Fault`]s.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum Route {
+    /// `POST /translate`
+    Translate,
+    /// `GET|POST /usage`
+    Usage,
+    /// `GET /languages`
+    Languages,
+    /// `POST|GET|DELETE /glossaries[/{id}[/entries]]`
+    Glossaries,
+    /// `GET /glossary-language-pairs`
+    GlossaryLanguagePairs,
+    /// `POST /document`
+    DocumentUpload,
+    /// `POST /document/{id}`
+    DocumentStatus,
+    /// `POST /document/{id}/result`
+    DocumentResult,
+}
+
+/// A failure response the [`FakeServer`] returns instead of the normal one.
+#[derive(Debug, Clone)]
+pub struct Fault {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Option<String>,
+    remaining: Option<usize>,
+    skip: usize,
+}
+
+impl Fault {
+    /// Fail with the given HTTP status. Without a custom [`body`](Fault::body), a DeepL style
+    /// JSON error message is sent.
+    pub fn status(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: None,
+            remaining: None,
+            skip: 0,
+        }
+    }
+
+    /// Replace the response body
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Add a response header, e.g. `Retry-After`
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Only fail the next `n` requests to the route, then behave normally again.
+    /// Without this, the fault stays until [`FakeServer::clear_faults`] is called.
+    pub fn times(mut self, n: usize) -> Self {
+        self.remaining = Some(n);
+        self
+    }
+
+    /// Let the next `n` requests to the route through before failing
+    pub fn after(mut self, n: usize) -> Self {
+        self.skip = n;
+        self
+    }
+
+    fn into_response(self) -> Response {
+        let body = self.body.unwrap_or_else(|| {
+            let message = match self.status {
+                403 => "Authorization failed. Please supply a valid auth_key parameter.",
+                404 => "Not found",
+                429 => "Too many requests. Please wait and resend your request.",
+                456 => "Quota exceeded. The character limit has been reached.",
+                500..=599 => "Internal server error",
+                _ => "Bad request",
+            };
+            json!({ "message": message }).to_string()
+        });
+
+        let mut resp = Response::new(self.status, "application/json", body.into_bytes());
+        resp.headers.extend(self.headers);
+        resp
+    }
+}
+
+/// A DeepL API fake listening on a local port. The server shuts down when dropped.
+pub struct FakeServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    handle: JoinHandle<()>,
+}
+
+impl FakeServer {
+    /// Bind a random local port and start serving.
+    ///
+    /// # Panics
+    ///
+    /// Panic if no local port can be bound.
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("fail to bind a local port for the fake DeepL server");
+        let addr = listener.local_addr().unwrap();
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let shared = state.clone();
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, shared.clone()));
+            }
+        });
+
+        Self {
+            addr,
+            state,
+            handle,
+        }
+    }
+
+    /// The base URL of the fake API, equivalent to `https://api.deepl.com/v2/`
+    pub fn endpoint(&self) -> reqwest::Url {
+        reqwest::Url::parse(&format!("http://{}/v2/", self.addr)).unwrap()
+    }
+
+    /// Create a [`DeepLApi`] that sends all requests to this server
+    pub fn api(&self) -> DeepLApi {
+        DeepLApi::with(FAKE_AUTH_KEY)
+            .endpoint(self.endpoint())
+            .new()
+    }
+
+    /// Make the given route fail. A later fault on the same route replaces the former one.
+    pub fn inject(&self, route: Route, fault: Fault) {
+        self.state.lock().unwrap().faults.insert(route, fault);
+    }
+
+    /// Remove all injected faults
+    pub fn clear_faults(&self) {
+        self.state.lock().unwrap().faults.clear();
+    }
+
+    /// Delay every response of the route, e.g. to test timeouts
+    pub fn delay(&self, route: Route, delay: Duration) {
+        self.state.lock().unwrap().delays.insert(route, delay);
+    }
+
+    /// Delay the next responses of the route by the given delays in turn, e.g. to let
+    /// concurrent responses arrive out of order. Once used up, [`delay`](Self::delay) applies.
+    pub fn delay_each(&self, route: Route, delays: impl IntoIterator<Item = Duration>) {
+        let mut state = self.state.lock().unwrap();
+        state.delay_queues.entry(route).or_default().extend(delays);
+    }
+
+    /// Send the first half of every response body of the route, then pause before sending the
+    /// rest, e.g. to test a download interrupted midway
+    pub fn stall_body(&self, route: Route, delay: Duration) {
+        self.state.lock().unwrap().body_stalls.insert(route, delay);
+    }
+
+    /// Add a header to every response, e.g. an `x-trace-id`
+    pub fn response_header(&self, name: impl Into<String>, value: impl Into<String>) {
+        let mut state = self.state.lock().unwrap();
+        state.response_headers.push((name.into(), value.into()));
+    }
+
+    /// Reject every request with a `Content-Encoding` with 415 Unsupported Media Type
+    pub fn reject_compressed_requests(&self, reject: bool) {
+        self.state.lock().unwrap().reject_compressed_requests = reject;
+    }
+
+    /// Highest number of requests handled at the same time so far
+    pub fn max_concurrent_requests(&self) -> usize {
+        self.state.lock().unwrap().max_in_flight
+    }
+
+    /// All requests received so far, in order of arrival
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Number of requests received so far
+    pub fn request_count(&self) -> usize {
+        self.state.lock().unwrap().requests.len()
+    }
+
+    /// Panic unless exactly `count` requests were received so far
+    #[track_caller]
+    pub fn assert_request_count(&self, count: usize) {
+        let received = self.request_count();
+        assert_eq!(
+            received, count,
+            "expected {count} requests to the fake DeepL API, received {received}"
+        );
+    }
+}
+
+/// Start a [`FakeServer`] and return a client sending all requests to it, along with the server
+/// for assertions. The server is shut down when dropped.
+pub async fn mock_api() -> (DeepLApi, FakeServer) {
+    let server = FakeServer::start().await;
+    (server.api(), server)
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+#[derive(Default)]
+struct State {
+    requests: Vec<RecordedRequest>,
+    reject_compressed_requests: bool,
+    response_headers: Vec<(String, String)>,
+    faults: HashMap<Route, Fault>,
+    delays: HashMap<Route, Duration>,
+    delay_queues: HashMap<Route, VecDeque<Duration>>,
+    body_stalls: HashMap<Route, Duration>,
+    in_flight: usize,
+    max_in_flight: usize,
+    character_count: u64,
+    next_id: u64,
+    glossaries: Vec<Glossary>,
+    documents: HashMap<String, Document>,
+}
+
+impl State {
+    fn next_id(&mut self) -> String {
+        self.next_id += 1;
+        format!("00000000-0000-0000-0000-{:012}", self.next_id)
+    }
+}
+
+struct Glossary {
+    id: String,
+    name: String,
+    source_lang: String,
+    target_lang: String,
+    entries: Vec<(String, String)>,
+}
+
+impl Glossary {
+    fn to_json(&self) -> Value {
+        json!({
+            "glossary_id": self.id,
+            "name": self.name,
+            "ready": true,
+            "source_lang": self.source_lang,
+            "target_lang": self.target_lang,
+            "creation_time": "2024-01-01T00:00:00.000Z",
+            "entry_count": self.entries.len(),
+        })
+    }
+}
+
+struct Document {
+    key: String,
+    target_lang: String,
+    content: Vec<u8>,
+}
+
+/// A request received by the [`FakeServer`]
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    method: String,
+    path: String,
+    query: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl RecordedRequest {
+    /// HTTP method of the request
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    /// Request path, e.g. `/v2/translate`
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Get the value of a header, the name is case-insensitive
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Request body, after removing any `Content-Encoding`
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Request body decoded as JSON
+    pub fn json(&self) -> Option<Value> {
+        serde_json::from_slice(&self.body).ok()
+    }
+
+    fn form(&self) -> Vec<(String, String)> {
+        parse_urlencoded(&String::from_utf8_lossy(&self.body))
+    }
+
+    fn query(&self) -> Vec<(String, String)> {
+        parse_urlencoded(&self.query)
+    }
+}
+
+/// In-memory [`TranslationProvider`] for unit tests of code which doesn't need a server.
+///
+/// Texts are translated like the [`FakeServer`] does, by prefixing them with `[TARGET_LANG]`,
+/// unless a translation was registered with [`with_translation`](Self::with_translation).
+///
+/// ```rust
+/// use deepl::{testing::StaticProvider, Lang, TranslateOptions, TranslationProvider};
+///
+/// let provider = StaticProvider::new().with_translation("Hello", Lang::DE, "Hallo");
+/// let resp = provider
+///     .translate(vec!["Hello".to_string()], Lang::DE, TranslateOptions::default())
+///     .await
+///     .unwrap();
+/// assert_eq!(resp.translations[0].text, "Hallo");
+/// ```
+#[derive(Debug, Clone)]
+pub struct StaticProvider {
+    translations: HashMap<(String, Lang), String>,
+    usage: UsageResponse,
+    languages: Vec<LangInfo>,
+}
+
+impl Default for StaticProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StaticProvider {
+    /// A provider with no usage and English and German as supported languages
+    pub fn new() -> Self {
+        let lang = |language: &str, name: &str| LangInfo {
+            language: language.to_string(),
+            name: name.to_string(),
+            supports_formality: None,
+        };
+
+        Self {
+            translations: HashMap::new(),
+            usage: UsageResponse {
+                character_count: 0,
+                character_limit: 500_000,
+            },
+            languages: vec![lang("DE", "German"), lang("EN", "English")],
+        }
+    }
+
+    /// Translate `text` into `target_lang` as `translated`
+    pub fn with_translation(
+        mut self,
+        text: impl ToString,
+        target_lang: Lang,
+        translated: impl ToString,
+    ) -> Self {
+        self.translations
+            .insert((text.to_string(), target_lang), translated.to_string());
+        self
+    }
+
+    /// Usage returned by [`TranslationProvider::usage`]
+    pub fn with_usage(mut self, character_count: u64, character_limit: u64) -> Self {
+        self.usage = UsageResponse {
+            character_count,
+            character_limit,
+        };
+        self
+    }
+
+    /// Languages returned by [`TranslationProvider::languages`], for both source and target
+    pub fn with_languages(mut self, languages: Vec<LangInfo>) -> Self {
+        self.languages = languages;
+        self
+    }
+}
+
+impl TranslationProvider for StaticProvider {
+    fn translate(
+        &self,
+        texts: Vec<String>,
+        target_lang: Lang,
+        options: TranslateOptions,
+    ) -> ProviderFuture<'_, TranslateTextResp> {
+        let translations = texts
+            .into_iter()
+            .map(|text| Sentence {
+                detected_source_language: options.source_lang.clone().unwrap_or(Lang::EN),
+                text: match self.translations.get(&(text.clone(), target_lang.clone())) {
+                    Some(translated) => translated.clone(),
+                    None => format!("[{target_lang}] {text}"),
+                },
+                billed_characters: None,
+                model_type_used: None,
+            })
+            .collect();
+
+        Box::pin(std::future::ready(Ok(TranslateTextResp { translations })))
+    }
+
+    fn usage(&self) -> ProviderFuture<'_, UsageResponse> {
+        Box::pin(std::future::ready(Ok(self.usage.clone())))
+    }
+
+    fn languages(&self, _lang_type: LangType) -> ProviderFuture<'_, Vec<LangInfo>> {
+        Box::pin(std::future::ready(Ok(self.languages.clone())))
+    }
+}
+
+struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn new(status: u16, content_type: &str, body: Vec<u8>) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type".to_string(), content_type.to_string())],
+            body,
+        }
+    }
+
+    fn json(value: Value) -> Self {
+        Self::new(200, "application/json", value.to_string().into_bytes())
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self::new(
+            status,
+            "application/json",
+            json!({ "message": message }).to_string().into_bytes(),
+        )
+    }
+
+    fn no_content() -> Self {
+        Self {
+            status: 204,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+}
+
+fn parse_urlencoded(input: &str) -> Vec<(String, String)> {
+    reqwest::Url::parse(&format!("http://localhost/?{input}"))
+        .map(|url| url.query_pairs().into_owned().collect())
+        .unwrap_or_default()
+}
+
+async fn serve(stream: TcpStream, state: Arc<Mutex<State>>) {
+    let mut stream = BufReader::new(stream);
+    let Some(mut req) = read_request(&mut stream).await else {
+        return;
+    };
+
+    let (resp, delay, stall) = {
+        let mut state = state.lock().unwrap();
+        let mut resp = match decode_body(&mut req, state.reject_compressed_requests) {
+            Ok(()) => handle(&req, &mut state),
+            Err(resp) => resp,
+        };
+        resp.headers.extend(state.response_headers.iter().cloned());
+        state.requests.push(req.clone());
+        state.in_flight += 1;
+        state.max_in_flight = state.max_in_flight.max(state.in_flight);
+        let route = route_of(&req.method, &segments(&req.path));
+        let delay = route.and_then(|route| {
+            let queued = state
+                .delay_queues
+                .get_mut(&route)
+                .and_then(VecDeque::pop_front);
+            queued.or_else(|| state.delays.get(&route).copied())
+        });
+        let stall = route.and_then(|route| state.body_stalls.get(&route).copied());
+        (resp, delay, stall)
+    };
+    if let Some(delay) = delay {
+        tokio::time::sleep(delay).await;
+    }
+    state.lock().unwrap().in_flight -= 1;
+    #[cfg(feature = "gzip")]
+    let resp = compress_response(&req, resp);
+
+    let mut head = format!("HTTP/1.1 {} Fake\r\n", resp.status);
+    for (name, value) in &resp.headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        resp.body.len()
+    ));
+
+    let stream = stream.get_mut();
+    let _ = stream.write_all(head.as_bytes()).await;
+    let (first, rest) = resp.body.split_at(resp.body.len() / 2);
+    let _ = stream.write_all(first).await;
+    if let Some(stall) = stall {
+        let _ = stream.flush().await;
+        tokio::time::sleep(stall).await;
+    }
+    let _ = stream.write_all(rest).await;
+    let _ = stream.shutdown().await;
+}
+
+async fn read_request(stream: &mut BufReader<TcpStream>) -> Option<RecordedRequest> {
+    let mut line = String::new();
+    stream.read_line(&mut line).await.ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let mut target = parts.next()?;
+    // requests sent to a proxy use the absolute form `http://host/path`
+    if let Some(rest) = target.strip_prefix("http://") {
+        target = rest.find('/').map_or("/", |i| &rest[i..]);
+    }
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let (path, query) = (path.to_string(), query.to_string());
+
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        stream.read_line(&mut line).await.ok()?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':')?;
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+
+    let mut req = RecordedRequest {
+        method,
+        path,
+        query,
+        headers,
+        body: Vec::new(),
+    };
+
+    if req
+        .header("transfer-encoding")
+        .is_some_and(|v| v.eq_ignore_ascii_case("chunked"))
+    {
+        loop {
+            line.clear();
+            stream.read_line(&mut line).await.ok()?;
+            let size = usize::from_str_radix(line.trim(), 16).ok()?;
+            let mut chunk = vec![0; size + 2];
+            stream.read_exact(&mut chunk).await.ok()?;
+            if size == 0 {
+                break;
+            }
+            req.body.extend_from_slice(&chunk[..size]);
+        }
+    } else if let Some(len) = req.header("content-length") {
+        let mut body = vec![0; len.parse().ok()?];
+        stream.read_exact(&mut body).await.ok()?;
+        req.body = body;
+    }
+
+    Some(req)
+}
+
+/// Remove the `Content-Encoding` of the request body
+fn decode_body(req: &mut RecordedRequest, reject: bool) -> Result<(), Response> {
+    let Some(encoding) = req.header("content-encoding") else {
+        return Ok(());
+    };
+    let unsupported = || Response::error(415, "Unsupported Content-Encoding");
+    if reject || encoding != "gzip" {
+        return Err(unsupported());
+    }
+
+    #[cfg(feature = "gzip")]
+    {
+        use std::io::Read;
+
+        let mut body = Vec::new();
+        flate2::read::GzDecoder::new(req.body.as_slice())
+            .read_to_end(&mut body)
+            .map_err(|_| Response::error(400, "Invalid gzip body"))?;
+        req.body = body;
+        Ok(())
+    }
+    #[cfg(not(feature = "gzip"))]
+    Err(unsupported())
+}
+
+/// Compress the response body if the client accepts gzip
+#[cfg(feature = "gzip")]
+fn compress_response(req: &RecordedRequest, mut resp: Response) -> Response {
+    if req
+        .header("accept-encoding")
+        .is_some_and(|v| v.contains("gzip"))
+        && !resp.body.is_empty()
+    {
+        resp.body = crate::gzip_encode(&resp.body);
+        resp.headers
+            .push(("Content-Encoding".to_string(), "gzip".to_string()));
+    }
+    resp
+}
+
+/// Split the path after the API version into segments
+fn segments(path: &str) -> Vec<&str> {
+    // the API may be served behind a proxy path prefix, e.g. `/deepl/v2/`
+    let path = path.split_once("/v2/").map_or("", |(_, path)| path);
+    path.split('/').filter(|s| !s.is_empty()).collect()
+}
+
+fn route_of(method: &str, segments: &[&str]) -> Option<Route> {
+    let route = match (method, segments) {
+        ("POST", ["translate"]) => Route::Translate,
+        ("GET" | "POST", ["usage"]) => Route::Usage,
+        ("GET" | "POST", ["languages"]) => Route::Languages,
+        ("GET", ["glossary-language-pairs"]) => Route::GlossaryLanguagePairs,
+        ("GET" | "POST", ["glossaries"])
+        | ("GET" | "DELETE", ["glossaries", _])
+        | ("GET", ["glossaries", _, "entries"]) => Route::Glossaries,
+        ("POST", ["document"]) => Route::DocumentUpload,
+        ("POST", ["document", _]) => Route::DocumentStatus,
+        ("POST", ["document", _, "result"]) => Route::DocumentResult,
+        _ => return None,
+    };
+
+    Some(route)
+}
+
+fn handle(req: &RecordedRequest, state: &mut State) -> Response {
+    // the API may be served behind a proxy path prefix, e.g. `/deepl/v2/`
+    let path = req.path.split_once("/v2/").map_or("", |(_, path)| path);
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+    let Some(route) = route_of(&req.method, &segments) else {
+        return Response::error(404, "Not found");
+    };
+
+    if !req
+        .header("authorization")
+        .is_some_and(|v| v.starts_with("DeepL-Auth-Key "))
+    {
+        return Fault::status(403).into_response();
+    }
+
+    if let Some(fault) = state.faults.get_mut(&route) {
+        let fault = match fault.remaining.as_mut() {
+            _ if fault.skip > 0 => {
+                fault.skip -= 1;
+                None
+            }
+            Some(0) => None,
+            Some(n) => {
+                *n -= 1;
+                Some(fault.clone())
+            }
+            None => Some(fault.clone()),
+        };
+        if let Some(fault) = fault {
+            return fault.into_response();
+        }
+    }
+
+    match (route, segments.as_slice()) {
+        (Route::Translate, _) => translate(req, state),
+        (Route::Usage, _) => Response::json(json!({
+            "character_count": state.character_count,
+            "character_limit": 500000,
+        })),
+        (Route::Languages, _) => languages(req),
+        (Route::GlossaryLanguagePairs, _) => Response::json(json!({
+            "supported_languages": [
+                { "source_lang": "en", "target_lang": "de" },
+                { "source_lang": "de", "target_lang": "en" },
+                { "source_lang": "en", "target_lang": "fr" },
+            ]
+        })),
+        (Route::Glossaries, ["glossaries"]) if req.method == "POST" => create_glossary(req, state),
+        (Route::Glossaries, ["glossaries"]) => Response::json(json!({
+            "glossaries": state.glossaries.iter().map(Glossary::to_json).collect::<Vec<_>>()
+        })),
+        (Route::Glossaries, ["glossaries", id, rest @ ..]) => {
+            let Some(idx) = state.glossaries.iter().position(|g| g.id == *id) else {
+                return Response::error(404, "Glossary not found");
+            };
+            match (req.method.as_str(), rest) {
+                ("DELETE", _) => {
+                    state.glossaries.remove(idx);
+                    Response::no_content()
+                }
+                (_, ["entries"]) => {
+                    let tsv = state.glossaries[idx]
+                        .entries
+                        .iter()
+                        .map(|(s, t)| format!("{s}\t{t}"))
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    Response::new(200, "text/tab-separated-values", tsv.into_bytes())
+                }
+                _ => Response::json(state.glossaries[idx].to_json()),
+            }
+        }
+        (Route::DocumentUpload, _) => upload_document(req, state),
+        (Route::DocumentStatus, ["document", id]) => {
+            let Some(doc) = find_document(req, state, id) else {
+                return Response::error(404, "Document not found");
+            };
+            Response::json(json!({
+                "document_id": id,
+                "status": "done",
+                "billed_characters": String::from_utf8_lossy(&doc.content).chars().count(),
+            }))
+        }
+        (Route::DocumentResult, ["document", id, _]) => {
+            let Some(doc) = find_document(req, state, id) else {
+                return Response::error(404, "Document not found");
+            };
+            let mut content = format!("[{}] ", doc.target_lang).into_bytes();
+            content.extend_from_slice(&doc.content);
+            Response::new(200, "application/octet-stream", content)
+        }
+        _ => Response::error(404, "Not found"),
+    }
+}
+
+fn translate(req: &RecordedRequest, state: &mut State) -> Response {
+    let body = match req.header("content-type") {
+        Some(form) if form.starts_with("application/x-www-form-urlencoded") => form_to_json(req),
+        _ => req.json(),
+    };
+    let Some(body) = body else {
+        return Response::error(400, "Invalid request: expected a JSON body");
+    };
+
+    let texts: Vec<String> = match &body["text"] {
+        Value::Array(texts) => texts
+            .iter()
+            .filter_map(|t| t.as_str().map(str::to_string))
+            .collect(),
+        Value::String(text) => vec![text.clone()],
+        _ => Vec::new(),
+    };
+    if texts.is_empty() {
+        return Response::error(400, "Parameter 'text' not specified.");
+    }
+    if texts.len() > 50 {
+        return Response::error(400, "Too many texts, the limit is 50.");
+    }
+
+    let Some(target) = body["target_lang"].as_str() else {
+        return Response::error(400, "Value for 'target_lang' not supported.");
+    };
+    let target = target.to_uppercase();
+    let source = match body["source_lang"].as_str() {
+        Some(source) => source.to_uppercase(),
+        None => detect(&texts[0]).to_string(),
+    };
+    let show_billed_characters = body["show_billed_characters"] == true;
+    // the quality optimized model is available for every language pair here
+    let model_type_used = match body["model_type"].as_str() {
+        Some("latency_optimized") => Some("latency_optimized"),
+        Some(_) => Some("quality_optimized"),
+        None => None,
+    };
+
+    let translations: Vec<Value> = texts
+        .iter()
+        .map(|text| {
+            let characters = text.chars().count() as u64;
+            state.character_count += characters;
+            let mut translation = json!({
+                "detected_source_language": source,
+                "text": format!("[{target}] {text}"),
+            });
+            if show_billed_characters {
+                translation["billed_characters"] = characters.into();
+            }
+            if let Some(model_type_used) = model_type_used {
+                translation["model_type_used"] = model_type_used.into();
+            }
+            translation
+        })
+        .collect();
+
+    Response::json(json!({ "translations": translations }))
+}
+
+/// Parameters of a form body as JSON, collecting the `text` parameters into an array and
+/// turning `1` and `0` into booleans
+fn form_to_json(req: &RecordedRequest) -> Option<Value> {
+    let mut body = json!({ "text": [] });
+    for (name, value) in req.form() {
+        match (name.as_str(), value.as_str()) {
+            ("text", _) => body["text"].as_array_mut()?.push(value.into()),
+            (_, "1") => body[name] = true.into(),
+            (_, "0") if name != "split_sentences" => body[name] = false.into(),
+            _ => body[name] = value.into(),
+        }
+    }
+    Some(body)
+}
+
+/// Guess the language from the script, anything else is English
+fn detect(text: &str) -> &'static str {
+    let script = |range: std::ops::RangeInclusive<char>| text.chars().any(|c| range.contains(&c));
+    if script('\u{3040}'..='\u{30ff}') {
+        "JA"
+    } else if script('\u{4e00}'..='\u{9fff}') {
+        "ZH"
+    } else if script('\u{0400}'..='\u{04ff}') {
+        "RU"
+    } else if script('\u{0370}'..='\u{03ff}') {
+        "EL"
+    } else {
+        "EN"
+    }
+}
+
+fn languages(req: &RecordedRequest) -> Response {
+    let target = req
+        .query()
+        .into_iter()
+        .chain(req.form())
+        .any(|(k, v)| k == "type" && v == "target");
+
+    let langs = if target {
+        json!([
+            { "language": "DE", "name": "German", "supports_formality": true },
+            { "language": "EN-GB", "name": "English (British)", "supports_formality": false },
+            { "language": "EN-US", "name": "English (American)", "supports_formality": false },
+            { "language": "ES", "name": "Spanish", "supports_formality": true },
+            { "language": "FR", "name": "French", "supports_formality": true },
+            { "language": "JA", "name": "Japanese", "supports_formality": true },
+            { "language": "ZH", "name": "Chinese (simplified)", "supports_formality": false },
+        ])
+    } else {
+        json!([
+            { "language": "DE", "name": "German" },
+            { "language": "EN", "name": "English" },
+            { "language": "ES", "name": "Spanish" },
+            { "language": "FR", "name": "French" },
+            { "language": "JA", "name": "Japanese" },
+            { "language": "ZH", "name": "Chinese" },
+        ])
+    };
+
+    Response::json(langs)
+}
+
+fn create_glossary(req: &RecordedRequest, state: &mut State) -> Response {
+    let Some(body) = req.json() else {
+        return Response::error(400, "Invalid request: expected a JSON body");
+    };
+
+    let field = |name: &str| body[name].as_str().unwrap_or_default().to_string();
+    let separator = if field("entries_format") == "csv" {
+        ','
+    } else {
+        '\t'
+    };
+    let entries: Option<Vec<(String, String)>> = field("entries")
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            line.split_once(separator)
+                .map(|(s, t)| (s.to_string(), t.to_string()))
+        })
+        .collect();
+
+    let Some(entries) = entries.filter(|e| !e.is_empty()) else {
+        return Response::error(400, "Invalid glossary entries provided");
+    };
+
+    let glossary = Glossary {
+        id: state.next_id(),
+        name: field("name"),
+        source_lang: field("source_lang"),
+        target_lang: field("target_lang"),
+        entries,
+    };
+    let resp = glossary.to_json();
+    state.glossaries.push(glossary);
+
+    let mut resp = Response::json(resp);
+    resp.status = 201;
+    resp
+}
+
+fn upload_document(req: &RecordedRequest, state: &mut State) -> Response {
+    let Some(parts) = parse_multipart(req) else {
+        return Response::error(400, "Invalid request: expected a multipart body");
+    };
+
+    let text = |name: &str| {
+        parts
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| String::from_utf8_lossy(&p.content).to_string())
+    };
+    let Some(target_lang) = text("target_lang") else {
+        return Response::error(400, "Value for 'target_lang' not supported.");
+    };
+    let Some(file) = parts.iter().find(|p| p.name == "file") else {
+        return Response::error(400, "Parameter 'file' not specified.");
+    };
+
+    let id = state.next_id();
+    let key = format!("key-{id}");
+    state.documents.insert(
+        id.clone(),
+        Document {
+            key: key.clone(),
+            target_lang: target_lang.to_uppercase(),
+            content: file.content.clone(),
+        },
+    );
+
+    Response::json(json!({ "document_id": id, "document_key": key }))
+}
+
+fn find_document<'s>(req: &RecordedRequest, state: &'s State, id: &str) -> Option<&'s Document> {
+    let key = req
+        .form()
+        .into_iter()
+        .find(|(k, _)| k == "document_key")
+        .map(|(_, v)| v)?;
+    state.documents.get(id).filter(|doc| doc.key == key)
+}
+
+struct Part {
+    name: String,
+    content: Vec<u8>,
+}
+
+fn parse_multipart(req: &RecordedRequest) -> Option<Vec<Part>> {
+    let boundary = req
+        .header("content-type")?
+        .split(';')
+        .find_map(|p| p.trim().strip_prefix("boundary="))?;
+    let delimiter = format!("--{boundary}");
+
+    let mut parts = Vec::new();
+    let mut rest = req.body.as_slice();
+    // skip the preamble before the first delimiter
+    rest = &rest[find(rest, delimiter.as_bytes())? + delimiter.len()..];
+    while !rest.starts_with(b"--") {
+        let end = find(rest, delimiter.as_bytes())?;
+        let raw = rest[..end].strip_prefix(b"\r\n")?;
+        let raw = raw.strip_suffix(b"\r\n").unwrap_or(raw);
+        let head_end = find(raw, b"\r\n\r\n")?;
+        let head = String::from_utf8_lossy(&raw[..head_end]);
+        let name = head
+            .split(';')
+            .find_map(|p| p.trim().strip_prefix("name="))?
+            .lines()
+            .next()?
+            .trim_matches('"')
+            .to_string();
+        parts.push(Part {
+            name,
+            content: raw[head_end + 4..].to_vec(),
+        });
+        rest = &rest[end + delimiter.len()..];
+    }
+
+    Some(parts)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[tokio::test]
+async fn test_fake_server_glossaries() {
+    use crate::Lang;
+
+    let server = FakeServer::start().await;
+    let deepl = server.api();
+
+    let resp = deepl
+        .create_glossary("My Glossary")
+        .source_lang(Lang::EN)
+        .target_lang(Lang::DE)
+        .entries([("Hello", "Guten Tag"), ("Bye", "Auf Wiedersehen")])
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.entry_count, 2);
+
+    let detail = deepl
+        .retrieve_glossary_details(&resp.glossary_id)
+        .await
+        .unwrap();
+    assert_eq!(detail, resp);
+    assert_eq!(deepl.list_all_glossaries().await.unwrap(), vec![resp]);
+
+    let entries = deepl
+        .retrieve_glossary_entries(&detail.glossary_id)
+        .await
+        .unwrap();
+    assert_eq!(entries[0], ("Hello".to_string(), "Guten Tag".to_string()));
+
+    deepl.delete_glossary(&detail.glossary_id).await.unwrap();
+    assert!(deepl.list_all_glossaries().await.unwrap().is_empty());
+}
+
+#[cfg(feature = "tokio-fs")]
+#[tokio::test]
+async fn test_fake_server_document() {
+    use crate::Lang;
+
+    let server = FakeServer::start().await;
+    let deepl = server.api();
+
+    let dir = std::env::temp_dir().join("deepl-fake-server-document");
+    tokio::fs::create_dir_all(&dir).await.unwrap();
+    let input = dir.join("input.txt");
+    tokio::fs::write(&input, "Hello World").await.unwrap();
+
+    let resp = deepl.upload_document(&input, Lang::DE).await.unwrap();
+    let status = deepl.check_document_status(&resp).await.unwrap();
+    assert!(status.status.is_done());
+
+    let output = deepl
+        .download_document(&resp, dir.join("output.txt"))
+        .await
+        .unwrap();
+    let content = tokio::fs::read_to_string(output).await.unwrap();
+    assert_eq!(content, "[DE] Hello World");
+}
+
+#[tokio::test]
+async fn test_fake_server_fault() {
+    use crate::Lang;
+
+    let server = FakeServer::start().await;
+    let deepl = server.api();
+
+    server.inject(Route::Translate, Fault::status(456).times(1));
+    assert!(deepl.translate_text("Hello", Lang::DE).await.is_err());
+    assert!(deepl.translate_text("Hello", Lang::DE).await.is_ok());
+
+    server.inject(Route::Usage, Fault::status(429));
+    assert!(deepl.get_usage().await.is_err());
+    server.clear_faults();
+    assert_eq!(deepl.get_usage().await.unwrap().character_count, 5);
+}
+
+#[tokio::test]
+async fn test_static_provider() {
+    let provider: Arc<dyn TranslationProvider> = Arc::new(
+        StaticProvider::new()
+            .with_translation("Hello", Lang::DE, "Hallo")
+            .with_usage(5, 100),
+    );
+
+    let resp = provider
+        .translate(
+            vec!["Hello".to_string(), "World".to_string()],
+            Lang::DE,
+            TranslateOptions::default(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(resp.to_string(), "Hallo\n[DE] World");
+    assert_eq!(provider.usage().await.unwrap().character_count, 5);
+    assert_eq!(provider.languages(LangType::Source).await.unwrap().len(), 2);
+
+    // the real client behaves the same behind the trait
+    let server = FakeServer::start().await;
+    let provider: Arc<dyn TranslationProvider> = Arc::new(server.api());
+    let options = TranslateOptions {
+        source_lang: Some(Lang::EN),
+        ..Default::default()
+    };
+    let resp = provider
+        .translate(vec!["World".to_string()], Lang::DE, options)
+        .await
+        .unwrap();
+    assert_eq!(resp.translations[0].text, "[DE] World");
+    assert!(provider.usage().await.unwrap().character_count > 0);
+}
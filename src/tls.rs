@@ -0,0 +1,48 @@
+//! TLS settings of [`DeepLApiBuilder`](crate::DeepLApiBuilder), without naming reqwest types
+
+use crate::endpoint::{Error, Result};
+
+/// A TLS protocol version, see [`DeepLApiBuilder::min_tls_version`](crate::DeepLApiBuilder::min_tls_version)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TlsVersion {
+    Tls1_0,
+    Tls1_1,
+    Tls1_2,
+    Tls1_3,
+}
+
+impl From<TlsVersion> for reqwest::tls::Version {
+    fn from(version: TlsVersion) -> Self {
+        match version {
+            TlsVersion::Tls1_0 => Self::TLS_1_0,
+            TlsVersion::Tls1_1 => Self::TLS_1_1,
+            TlsVersion::Tls1_2 => Self::TLS_1_2,
+            TlsVersion::Tls1_3 => Self::TLS_1_3,
+        }
+    }
+}
+
+/// An additional trusted root certificate, e.g. of a corporate proxy, see
+/// [`DeepLApiBuilder::add_root_certificate`](crate::DeepLApiBuilder::add_root_certificate)
+#[derive(Debug, Clone)]
+pub struct Certificate(reqwest::Certificate);
+
+impl Certificate {
+    /// Parse a PEM encoded certificate
+    pub fn from_pem(pem: &[u8]) -> Result<Self> {
+        reqwest::Certificate::from_pem(pem)
+            .map(Self)
+            .map_err(|err| Error::InvalidConfig(format!("invalid PEM certificate: {err}")))
+    }
+
+    /// Parse a DER encoded certificate
+    pub fn from_der(der: &[u8]) -> Result<Self> {
+        reqwest::Certificate::from_der(der)
+            .map(Self)
+            .map_err(|err| Error::InvalidConfig(format!("invalid DER certificate: {err}")))
+    }
+
+    pub(crate) fn into_inner(self) -> reqwest::Certificate {
+        self.0
+    }
+}
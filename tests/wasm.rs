@@ -0,0 +1,33 @@
+//! Run against the fake server of `examples/fake_server.rs`:
+//!
+//! ```sh
+//! DEEPL_FAKE_SERVER=http://127.0.0.1:PORT/v2/ \
+//!     wasm-pack test --node --no-default-features
+//! ```
+#![cfg(target_arch = "wasm32")]
+
+use deepl::{DeepLApi, Lang};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn api() -> DeepLApi {
+    DeepLApi::with("fake-deepl-key:fx")
+        .endpoint(env!("DEEPL_FAKE_SERVER"))
+        .new()
+}
+
+#[wasm_bindgen_test]
+async fn test_translate_text() {
+    let resp = api().translate_text("Hello", Lang::DE).await.unwrap();
+    assert_eq!(resp.translations[0].text, "[DE] Hello");
+}
+
+#[wasm_bindgen_test]
+async fn test_document_bytes() {
+    let api = api();
+    let resp = api
+        .upload_document_from_bytes(b"Hello".to_vec(), "hello.txt", Lang::DE)
+        .await
+        .unwrap();
+    let content = api.download_document_bytes(&resp).await.unwrap();
+    assert_eq!(content, b"[DE] Hello");
+}